@@ -0,0 +1,158 @@
+//! Tick-to-signal cost of the momentum strategy
+//!
+//! Replays a busy stretch of BTC trades (50/s) and times each tick from the
+//! trade arriving to the momentum strategy's signals for both horizons being
+//! known: what the strategy host updates per trade (price history, EWMA and
+//! realized volatility, rolling windows), then what `on_market_update`
+//! reads from it (momentum, its z-score, and the fair probability of Up from
+//! each live window's open).
+//!
+//! - `linear scan`: the 15-minute history, scanned twice for momentum and
+//!   once per window for its open, as the strategy used to compute them
+//! - `incremental`: the 60-minute history searched for the opens, with
+//!   momentum from `src/rolling.rs` and realized volatility kept by
+//!   `pricing::RealizedVol`, as the host keeps them now
+//!
+//! Run with `cargo bench --bench momentum`.
+
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use trading_bot::pricing::{fair_probability_up, window_open_price, RealizedVol};
+use trading_bot::rolling::RollingWindows;
+use trading_bot::types::{MarketType, PriceSnapshot};
+use trading_bot::volatility::{z_score, VolEstimator};
+
+/// Price history kept by the host: before, and now
+const SCAN_HISTORY_MS: u64 = 900_000;
+const HISTORY_MS: u64 = 3_600_000;
+/// Momentum lookbacks of the hourly and 15-minute horizons
+const LOOKBACKS_MS: [u64; 2] = [600_000, 180_000];
+const HORIZONS: [(MarketType, u64); 2] = [(MarketType::Hourly, 3_600_000), (MarketType::Fifteen, 900_000)];
+const TRADES_PER_SEC: u64 = 50;
+/// Ticks timed once the history is full
+const MEASURED_TICKS: usize = 50_000;
+
+/// (price in cents, quantity, ts in ms) for a random walk
+fn trades(count: usize) -> Vec<(u64, f64, u64)> {
+    let (mut price, mut seed) = (10_000_000u64, 42u64);
+    (0..count as u64)
+        .map(|i| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            price = price + (seed >> 59) - 15;
+            (price, ((seed >> 40) & 0xff) as f64 / 1000.0, i * 1000 / TRADES_PER_SEC)
+        })
+        .collect()
+}
+
+/// Add a trade to the history and drop prices older than `history_ms`
+fn record_price(history: &mut VecDeque<PriceSnapshot>, price: u64, ts: u64, history_ms: u64) {
+    history.push_back(PriceSnapshot::new(price, ts));
+    let cutoff = ts.saturating_sub(history_ms);
+    while history.front().is_some_and(|p| p.timestamp_ms < cutoff) {
+        history.pop_front();
+    }
+}
+
+/// The strategy's former `calculate_momentum`
+fn scan_momentum(history: &VecDeque<PriceSnapshot>, now_ms: u64, window_ms: u64) -> f64 {
+    let cutoff = now_ms.saturating_sub(window_ms);
+    let oldest = history.iter().find(|p| p.timestamp_ms >= cutoff).map(|p| p.price_cents);
+    match (oldest, history.back().map(|p| p.price_cents)) {
+        (Some(old), Some(cur)) if old > 0 => (cur as f64 - old as f64) / old as f64,
+        _ => 0.0,
+    }
+}
+
+/// The former `window_open_price`
+fn scan_window_open(history: &VecDeque<PriceSnapshot>, start_ms: u64) -> Option<f64> {
+    history.iter().find(|s| s.timestamp_ms >= start_ms).map(|s| s.price_cents as f64 / 100.0)
+}
+
+/// Signals of one tick: momentum (summed separately, as both paths must
+/// agree on it) and z-score plus fair probability per horizon
+fn signals(
+    momentum: [f64; 2],
+    vol: &VolEstimator,
+    price: u64,
+    ts: u64,
+    open: impl Fn(u64) -> Option<f64>,
+) -> (f64, f64) {
+    let spot = price as f64 / 100.0;
+    let mut checksum = 0.0;
+    for ((horizon, window_ms), momentum) in HORIZONS.into_iter().zip(momentum) {
+        let sigma = vol.sigma(1, horizon).unwrap_or(0.0001);
+        let start_ms = ts / window_ms * window_ms;
+        let secs_left = (start_ms + window_ms - ts) as f64 / 1000.0;
+        checksum += z_score(momentum, sigma, secs_left);
+        if let Some(open) = open(start_ms) {
+            checksum += fair_probability_up(spot, open, sigma, secs_left);
+        }
+    }
+    (momentum.iter().sum(), checksum)
+}
+
+fn linear_scan(trades: &[(u64, f64, u64)], warmup: usize) -> (Duration, f64) {
+    let mut history = VecDeque::with_capacity(50_000);
+    let mut vol = VolEstimator::default();
+    let mut momentum_sum = 0.0;
+    let mut started = Instant::now();
+    for (i, &(price, _, ts)) in trades.iter().enumerate() {
+        if i == warmup {
+            started = Instant::now();
+        }
+        record_price(&mut history, price, ts, SCAN_HISTORY_MS);
+        vol.update(1, price, ts);
+        if i < warmup {
+            continue;
+        }
+        let momentum = LOOKBACKS_MS.map(|window_ms| scan_momentum(&history, ts, window_ms));
+        let (momentum, checksum) = signals(momentum, &vol, price, ts, |start_ms| scan_window_open(&history, start_ms));
+        momentum_sum += momentum;
+        black_box(checksum);
+    }
+    (started.elapsed(), momentum_sum)
+}
+
+fn incremental(trades: &[(u64, f64, u64)], warmup: usize) -> (Duration, f64) {
+    let mut history = VecDeque::with_capacity(200_000);
+    let mut vol = VolEstimator::default();
+    let mut realized = RealizedVol::default();
+    let mut windows = RollingWindows::default();
+    let mut momentum_sum = 0.0;
+    let mut started = Instant::now();
+    for (i, &(price, quantity, ts)) in trades.iter().enumerate() {
+        if i == warmup {
+            started = Instant::now();
+        }
+        record_price(&mut history, price, ts, HISTORY_MS);
+        vol.update(1, price, ts);
+        realized.update(price, ts);
+        windows.push(price, quantity, ts);
+        if i < warmup {
+            continue;
+        }
+        black_box(realized.sigma());
+        let momentum = LOOKBACKS_MS.map(|window_ms| windows.stats(window_ms).map_or(0.0, |w| w.momentum()));
+        let (momentum, checksum) = signals(momentum, &vol, price, ts, |start_ms| window_open_price(&history, start_ms));
+        momentum_sum += momentum;
+        black_box(checksum);
+    }
+    (started.elapsed(), momentum_sum)
+}
+
+fn main() {
+    let warmup = (HISTORY_MS / 1000 * TRADES_PER_SEC) as usize;
+    let trades = trades(warmup + MEASURED_TICKS);
+    let per_tick = |elapsed: Duration| elapsed.as_nanos() as f64 / MEASURED_TICKS as f64;
+
+    let (scan, scan_sum) = linear_scan(&trades, warmup);
+    let (fast, fast_sum) = incremental(&trades, warmup);
+    // Both must compute the same momentum
+    assert!((scan_sum - fast_sum).abs() < 1e-9 * scan_sum.abs().max(1.0), "{} vs {}", scan_sum, fast_sum);
+
+    println!("momentum signals for {} horizons at {} trades/s, {} ticks", HORIZONS.len(), TRADES_PER_SEC, MEASURED_TICKS);
+    println!("  linear scan  {:>10.1} ns/tick", per_tick(scan));
+    println!("  incremental  {:>10.1} ns/tick", per_tick(fast));
+    println!("  speedup      {:>10.1}x", scan.as_secs_f64() / fast.as_secs_f64());
+}
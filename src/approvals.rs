@@ -0,0 +1,204 @@
+//! USDC and conditional-token approvals for the exchange contracts
+//!
+//! Orders only settle if the exchange contracts may move our collateral
+//! (USDC `approve`) and our outcome tokens (CTF `setApprovalForAll`).
+//! Neg-risk markets settle through a separate exchange and the NegRisk
+//! adapter, so all three need both approvals. Startup reports what is
+//! missing; `trading_bot approve` sends the approval transactions.
+
+use crate::contracts::{ConditionalTokens, ContractConfig, Erc20, TxSender};
+use crate::exchange::ExchangeResult;
+use alloy_primitives::{Address, TxHash, U256};
+use alloy_sol_types::SolCall;
+
+/// USDC has 6 decimals
+const USDC_DECIMALS: f64 = 1_000_000.0;
+
+/// A contract that needs our approvals
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spender {
+    pub name: &'static str,
+    pub address: Address,
+}
+
+/// Everything that settles our trades
+pub fn spenders(config: &ContractConfig) -> Vec<Spender> {
+    vec![
+        Spender { name: "CTF Exchange", address: config.ctf_exchange },
+        Spender { name: "NegRisk CTF Exchange", address: config.neg_risk_exchange },
+        Spender { name: "NegRisk Adapter", address: config.neg_risk_adapter },
+    ]
+}
+
+/// Approval state for one spender
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowanceStatus {
+    pub spender: Spender,
+    pub usdc_allowance: U256,
+    pub ctf_approved: bool,
+}
+
+/// Collateral balance and approvals of the account holding funds
+#[derive(Debug, Clone)]
+pub struct AllowanceReport {
+    pub holder: Address,
+    pub usdc_balance: U256,
+    pub entries: Vec<AllowanceStatus>,
+}
+
+impl AllowanceStatus {
+    /// The USDC allowance covers the whole balance
+    fn usdc_ok(&self, balance: U256) -> bool {
+        !self.usdc_allowance.is_zero() && self.usdc_allowance >= balance
+    }
+}
+
+impl AllowanceReport {
+    /// Spenders missing a USDC allowance and/or CTF approval: (spender, needs_usdc, needs_ctf)
+    pub fn missing(&self) -> Vec<(Spender, bool, bool)> {
+        self.entries
+            .iter()
+            .filter_map(|e| {
+                let needs_usdc = !e.usdc_ok(self.usdc_balance);
+                let needs_ctf = !e.ctf_approved;
+                (needs_usdc || needs_ctf).then_some((e.spender, needs_usdc, needs_ctf))
+            })
+            .collect()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.missing().is_empty()
+    }
+
+    /// Print a human-readable report
+    pub fn print(&self) {
+        println!("🔐 Allowances for {:?} (USDC balance ${:.2})", self.holder, usdc_to_dollars(self.usdc_balance));
+        for e in &self.entries {
+            let usdc = if e.usdc_allowance == U256::MAX {
+                "unlimited".to_string()
+            } else {
+                format!("${:.2}", usdc_to_dollars(e.usdc_allowance))
+            };
+            println!(
+                "   {} {:<22} USDC allowance: {:<12} CTF approved: {}",
+                if e.usdc_ok(self.usdc_balance) && e.ctf_approved { "✅" } else { "❌" },
+                e.spender.name,
+                usdc,
+                if e.ctf_approved { "yes" } else { "NO" }
+            );
+        }
+        if !self.is_ready() {
+            println!("   ⚠️ Missing approvals: orders will fail to settle. Run `trading_bot approve` to fix.");
+        }
+    }
+}
+
+/// USDC base units to dollars (saturating for unlimited allowances)
+fn usdc_to_dollars(amount: U256) -> f64 {
+    if amount > U256::from(u128::MAX) {
+        return f64::INFINITY;
+    }
+    amount.to::<u128>() as f64 / USDC_DECIMALS
+}
+
+/// Read collateral balance and approvals for the sender's holder
+pub async fn check_allowances(sender: &TxSender, config: &ContractConfig) -> ExchangeResult<AllowanceReport> {
+    let client = sender.client();
+    let holder = sender.holder();
+
+    let usdc_balance = client.call(config.collateral, &Erc20::balanceOfCall { owner: holder }).await?._0;
+    let mut entries = Vec::new();
+    for spender in spenders(config) {
+        let allowance = Erc20::allowanceCall { owner: holder, spender: spender.address };
+        let approved = ConditionalTokens::isApprovedForAllCall { owner: holder, operator: spender.address };
+        entries.push(AllowanceStatus {
+            spender,
+            usdc_allowance: client.call(config.collateral, &allowance).await?._0,
+            ctf_approved: client.call(config.ctf, &approved).await?._0,
+        });
+    }
+    Ok(AllowanceReport { holder, usdc_balance, entries })
+}
+
+/// Send the approvals missing from `report`; returns the transaction hashes
+pub async fn approve_missing(
+    sender: &TxSender,
+    config: &ContractConfig,
+    report: &AllowanceReport,
+) -> ExchangeResult<Vec<TxHash>> {
+    let mut txs = Vec::new();
+    for (spender, needs_usdc, needs_ctf) in report.missing() {
+        if needs_usdc {
+            println!("   ⏳ Approving USDC for {}...", spender.name);
+            let calldata = Erc20::approveCall { spender: spender.address, amount: U256::MAX }.abi_encode();
+            txs.push(sender.send(config.collateral, calldata.into()).await?);
+        }
+        if needs_ctf {
+            println!("   ⏳ Approving outcome tokens for {}...", spender.name);
+            let calldata = ConditionalTokens::setApprovalForAllCall { operator: spender.address, approved: true }.abi_encode();
+            txs.push(sender.send(config.ctf, calldata.into()).await?);
+        }
+    }
+    Ok(txs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(name: &'static str, usdc_allowance: u64, ctf_approved: bool) -> AllowanceStatus {
+        AllowanceStatus {
+            spender: Spender { name, address: Address::ZERO },
+            usdc_allowance: U256::from(usdc_allowance),
+            ctf_approved,
+        }
+    }
+
+    #[test]
+    fn test_missing_approvals() {
+        let report = AllowanceReport {
+            holder: Address::ZERO,
+            usdc_balance: U256::from(50_000_000u64), // $50
+            entries: vec![
+                status("CTF Exchange", 100_000_000, true),  // OK
+                status("NegRisk CTF Exchange", 10_000_000, true), // Allowance below balance
+                status("NegRisk Adapter", 0, false),        // Nothing approved
+            ],
+        };
+
+        let missing = report.missing();
+        assert_eq!(missing.len(), 2);
+        assert_eq!((missing[0].0.name, missing[0].1, missing[0].2), ("NegRisk CTF Exchange", true, false));
+        assert_eq!((missing[1].0.name, missing[1].1, missing[1].2), ("NegRisk Adapter", true, true));
+        assert!(!report.is_ready());
+    }
+
+    #[test]
+    fn test_zero_allowance_is_missing_even_without_balance() {
+        let report = AllowanceReport {
+            holder: Address::ZERO,
+            usdc_balance: U256::ZERO,
+            entries: vec![status("CTF Exchange", 0, true)],
+        };
+        assert!(!report.is_ready());
+        assert_eq!(usdc_to_dollars(U256::MAX), f64::INFINITY);
+    }
+
+    /// Needs `anvil` (or `ANVIL_BIN`); skipped without it
+    #[tokio::test]
+    async fn test_approval_flow_on_mock_chain() {
+        let Some(chain) = crate::contracts::mock_chain::MockChain::start().await else { return };
+        chain.mint_collateral(U256::from(50_000_000u64)).await;
+        let sender = chain.sender();
+
+        let before = check_allowances(&sender, &chain.config).await.unwrap();
+        assert_eq!(before.usdc_balance, U256::from(50_000_000u64));
+        assert_eq!(before.missing().len(), 3);
+
+        let txs = approve_missing(&sender, &chain.config, &before).await.unwrap();
+        assert_eq!(txs.len(), 6);
+        let after = check_allowances(&sender, &chain.config).await.unwrap();
+        assert!(after.is_ready());
+        assert!(after.entries.iter().all(|e| e.usdc_allowance == U256::MAX && e.ctf_approved));
+    }
+}
@@ -0,0 +1,294 @@
+//! Polymarket API credential management
+//!
+//! L2 credentials (API key / secret / passphrase) can be created or derived
+//! from the wallet's private key via L1 auth: an EIP-712 `ClobAuth` signature
+//! sent to `/auth/api-key` (create) or `/auth/derive-api-key` (derive).
+//! Derived credentials are cached on disk (owner read/write only) so restarts
+//! don't need a round trip, and are re-created when the CLOB revokes them.
+
+use crate::exchange::{ExchangeError, ExchangeResult};
+use alloy_primitives::{Address, B256, U256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{eip712_domain, sol, SolStruct};
+use polymarket_rs::types::ApiCreds;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Polygon mainnet
+pub const POLYGON_CHAIN_ID: u64 = 137;
+/// Message the CLOB expects inside the `ClobAuth` struct
+const CLOB_AUTH_MESSAGE: &str = "This message attests that I control the given wallet";
+/// Default location of the credentials cache
+const DEFAULT_CREDS_FILE: &str = ".polymarket_creds.json";
+
+/// Where the L2 credentials in use came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredsSource {
+    Env,     // POLYMARKET_API_KEY / _API_SECRET / _PASSPHRASE
+    Cache,   // Previously derived, read from the creds file
+    Derived, // Created/derived via L1 auth at startup
+}
+
+/// Credentials as stored in the cache file (tied to the signing address)
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredCreds {
+    address: String,
+    #[serde(rename = "apiKey")]
+    api_key: String,
+    secret: String,
+    passphrase: String,
+}
+
+/// `/auth/api-key` and `/auth/derive-api-key` response
+#[derive(Debug, Deserialize)]
+struct ApiKeyResponse {
+    #[serde(rename = "apiKey")]
+    api_key: String,
+    secret: String,
+    passphrase: String,
+}
+
+/// Whether credentials may be created/derived from the private key
+/// (`POLYMARKET_DERIVE_API_KEY=true`)
+pub fn derive_enabled() -> bool {
+    std::env::var("POLYMARKET_DERIVE_API_KEY")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Path of the credentials cache (`POLYMARKET_CREDS_FILE`)
+pub fn creds_file() -> PathBuf {
+    std::env::var("POLYMARKET_CREDS_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_CREDS_FILE))
+}
+
+sol! {
+    /// What the wallet signs for L1 auth
+    struct ClobAuth {
+        address address;
+        string timestamp;
+        uint256 nonce;
+        string message;
+    }
+}
+
+/// EIP-712 digest of `ClobAuth` over `timestamp` and `nonce` in the
+/// `ClobAuthDomain` (version 1) domain
+fn clob_auth_digest(address: Address, timestamp: &str, nonce: u64, chain_id: u64) -> B256 {
+    let domain = eip712_domain! {
+        name: "ClobAuthDomain",
+        version: "1",
+        chain_id: chain_id,
+    };
+    let auth = ClobAuth {
+        address,
+        timestamp: timestamp.to_string(),
+        nonce: U256::from(nonce),
+        message: CLOB_AUTH_MESSAGE.to_string(),
+    };
+    auth.eip712_signing_hash(&domain)
+}
+
+/// L1 auth headers: the wallet's `ClobAuth` signature over `timestamp` and `nonce`
+fn l1_headers(wallet: &PrivateKeySigner, timestamp: &str, nonce: u64) -> ExchangeResult<reqwest::header::HeaderMap> {
+    let digest = clob_auth_digest(wallet.address(), timestamp, nonce, POLYGON_CHAIN_ID);
+    let signature = wallet.sign_hash_sync(&digest)?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("POLY_ADDRESS", format!("{:?}", wallet.address()).parse()?);
+    headers.insert("POLY_SIGNATURE", format!("0x{}", hex::encode(signature.as_bytes())).parse()?);
+    headers.insert("POLY_TIMESTAMP", timestamp.parse()?);
+    headers.insert("POLY_NONCE", nonce.to_string().parse()?);
+    Ok(headers)
+}
+
+/// Create (POST `/auth/api-key`) or derive (GET `/auth/derive-api-key`)
+/// L2 credentials for `wallet`.
+///
+/// Creating first means revoked credentials are replaced; deriving recovers
+/// the existing key when one is already registered for `nonce`.
+pub async fn create_or_derive_api_creds(
+    http: &reqwest::Client,
+    wallet: &PrivateKeySigner,
+    nonce: u64,
+) -> ExchangeResult<ApiCreds> {
+    let base = crate::endpoints::clob_url();
+
+    let timestamp = chrono::Utc::now().timestamp().to_string();
+    let created = http
+        .post(format!("{}/auth/api-key", base))
+        .headers(l1_headers(wallet, &timestamp, nonce)?)
+        .send()
+        .await?;
+    let response = if created.status().is_success() {
+        created
+    } else {
+        println!("🔑 API key create returned {}, deriving existing key", created.status());
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        http.get(format!("{}/auth/derive-api-key", base))
+            .headers(l1_headers(wallet, &timestamp, nonce)?)
+            .send()
+            .await?
+            .error_for_status()?
+    };
+
+    let key: ApiKeyResponse = response.json().await?;
+    Ok(ApiCreds {
+        api_key: key.api_key,
+        secret: key.secret,
+        passphrase: key.passphrase,
+    })
+}
+
+/// Read cached credentials; ignored if they belong to a different address
+fn load_cached_creds(path: &Path, address: &str) -> Option<ApiCreds> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let stored: StoredCreds = serde_json::from_str(&text).ok()?;
+    if !stored.address.eq_ignore_ascii_case(address) {
+        return None;
+    }
+    Some(ApiCreds {
+        api_key: stored.api_key,
+        secret: stored.secret,
+        passphrase: stored.passphrase,
+    })
+}
+
+/// Write credentials to the cache file, readable by the owner only
+pub fn save_cached_creds(path: &Path, address: &str, creds: &ApiCreds) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let stored = StoredCreds {
+        address: address.to_string(),
+        api_key: creds.api_key.clone(),
+        secret: creds.secret.clone(),
+        passphrase: creds.passphrase.clone(),
+    };
+    let json = serde_json::to_string_pretty(&stored)?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // `mode` only applies on creation; tighten an existing file too
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(json.as_bytes())
+}
+
+/// Resolve L2 credentials: env vars first, then (if derivation is enabled)
+/// the cache file, then create/derive via L1 auth and cache the result.
+pub async fn resolve_api_creds(
+    http: &reqwest::Client,
+    wallet: &PrivateKeySigner,
+) -> ExchangeResult<(ApiCreds, CredsSource)> {
+    if let (Ok(api_key), Ok(secret), Ok(passphrase)) = (
+        std::env::var("POLYMARKET_API_KEY"),
+        std::env::var("POLYMARKET_API_SECRET"),
+        std::env::var("POLYMARKET_PASSPHRASE"),
+    ) {
+        return Ok((ApiCreds { api_key, secret, passphrase }, CredsSource::Env));
+    }
+
+    if !derive_enabled() {
+        return Err(ExchangeError::from(
+            "POLYMARKET_API_KEY, POLYMARKET_API_SECRET or POLYMARKET_PASSPHRASE not set \
+             (set POLYMARKET_DERIVE_API_KEY=true to derive them from the private key)",
+        ));
+    }
+
+    let address = format!("{:?}", wallet.address());
+    let path = creds_file();
+    if let Some(creds) = load_cached_creds(&path, &address) {
+        return Ok((creds, CredsSource::Cache));
+    }
+
+    let creds = create_or_derive_api_creds(http, wallet, 0).await?;
+    if let Err(e) = save_cached_creds(&path, &address, &creds) {
+        eprintln!("⚠️ Failed to cache API credentials at {}: {}", path.display(), e);
+    }
+    Ok((creds, CredsSource::Derived))
+}
+
+/// Whether an error means the CLOB rejected our L2 credentials: an HTTP 401
+/// from our own requests or from the polymarket-rs clients
+pub fn is_unauthorized(err: &(dyn std::error::Error + 'static)) -> bool {
+    use polymarket_rs::Error;
+
+    let unauthorized = |e: &reqwest::Error| e.status() == Some(reqwest::StatusCode::UNAUTHORIZED);
+    if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+        return unauthorized(e);
+    }
+    match err.downcast_ref::<Error>() {
+        Some(Error::Api { status, .. }) => *status == reqwest::StatusCode::UNAUTHORIZED.as_u16(),
+        Some(Error::Http(e)) => unauthorized(e),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Well-known test key (anvil account #0)
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_clob_auth_signature_recovers_wallet() {
+        let wallet: PrivateKeySigner = TEST_KEY.parse().unwrap();
+        let digest = clob_auth_digest(wallet.address(), "1700000000", 0, POLYGON_CHAIN_ID);
+        // EIP-712 ClobAuth digest for the test key's address at timestamp 1700000000, nonce 0
+        assert_eq!(digest, "0xc85352894b3c41f3ea6152479d64b9233fbaf2de87eabc7e4bba3a161fd28493".parse::<B256>().unwrap());
+
+        let signature = wallet.sign_hash_sync(&digest).unwrap();
+        assert_eq!(signature.recover_address_from_prehash(&digest).unwrap(), wallet.address());
+
+        // Digest binds timestamp and nonce
+        assert_ne!(digest, clob_auth_digest(wallet.address(), "1700000001", 0, POLYGON_CHAIN_ID));
+        assert_ne!(digest, clob_auth_digest(wallet.address(), "1700000000", 1, POLYGON_CHAIN_ID));
+    }
+
+    #[test]
+    fn test_unauthorized_matches_status_only() {
+        let api = |status: u16, message: &str| -> ExchangeError {
+            Box::new(polymarket_rs::Error::Api { status, message: message.to_string() })
+        };
+        assert!(is_unauthorized(api(401, "Unauthorized/Invalid api key").as_ref()));
+        assert!(!is_unauthorized(api(400, "order size 401 below minimum").as_ref()));
+        // Messages that merely mention 401 or unauthorized don't rotate credentials
+        assert!(!is_unauthorized(ExchangeError::from("market 401 unauthorized").as_ref()));
+        assert!(!is_unauthorized(api(500, "unauthorized").as_ref()));
+    }
+
+    #[test]
+    fn test_creds_cache_round_trip() {
+        let path = std::env::temp_dir().join(format!("poly_creds_test_{}.json", std::process::id()));
+        let creds = ApiCreds {
+            api_key: "key".to_string(),
+            secret: "c2VjcmV0".to_string(),
+            passphrase: "pass".to_string(),
+        };
+
+        save_cached_creds(&path, "0xAbC", &creds).unwrap();
+        let loaded = load_cached_creds(&path, "0xabc").unwrap();
+        assert_eq!(loaded.api_key, "key");
+        assert!(load_cached_creds(&path, "0xdef").is_none());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(path).ok();
+    }
+}
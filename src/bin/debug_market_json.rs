@@ -1,5 +1,5 @@
-use std::env;
 use dotenv::dotenv;
+use trading_bot::endpoints;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -12,10 +12,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("=== GAMMA API (Markets) ===\n");
     
     // Fetch Gamma API events
-    let gamma_url = "https://gamma-api.polymarket.com/events?limit=5&active=true&closed=false";
+    let gamma_url = format!("{}/events?limit=5&active=true&closed=false", endpoints::gamma_url());
     println!("Fetching: {}\n", gamma_url);
     
-    let resp = client.get(gamma_url).send().await?;
+    let resp = client.get(&gamma_url).send().await?;
     println!("Status: {}", resp.status());
     
     let json: serde_json::Value = resp.json().await?;
@@ -53,13 +53,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Test unauthenticated CLOB endpoints
     let clob_endpoints = vec![
-        "https://clob.polymarket.com/alive",
-        "https://clob.polymarket.com/time",
+        format!("{}/alive", endpoints::clob_url()),
+        format!("{}/time", endpoints::clob_url()),
     ];
     
     for url in clob_endpoints {
         println!("Fetching: {}", url);
-        let resp = client.get(url).send().await?;
+        let resp = client.get(&url).send().await?;
         println!("  Status: {}", resp.status());
         if resp.status().is_success() {
             let text = resp.text().await?;
@@ -0,0 +1,336 @@
+//! Local mock of the Polymarket Gamma/CLOB APIs and the Binance/Polymarket
+//! WebSockets, driven by fixture files. Lets the whole bot run end-to-end
+//! (e.g. in CI) without network access.
+//!
+//! Usage:
+//!   cargo run --bin mock_venue -- [fixture_dir]     (default: fixtures/mock_venue)
+//!
+//! Then point the bot at it:
+//!   POLYMARKET_CLOB_URL=http://127.0.0.1:8089
+//!   POLYMARKET_GAMMA_URL=http://127.0.0.1:8089
+//!   POLYMARKET_DATA_API_URL=http://127.0.0.1:8089
+//!   POLYMARKET_WS_URL=ws://127.0.0.1:8090/ws/market
+//!   BINANCE_WS_URL=ws://127.0.0.1:8090
+//!   BINANCE_REST_URL=http://127.0.0.1:8089
+//!
+//! Fixture files (all optional):
+//!   markets.json        Gamma `/markets` (array, filtered by `slug`)
+//!   events.json         Gamma `/events` (array, filtered by `slug` / `series_slug`)
+//!   books.json          CLOB `/book` (object: token_id -> {"bids": [...], "asks": [...]})
+//!   api_key.json        CLOB `/auth/api-key` and `/auth/derive-api-key` L2 credentials
+//!                       (object: {"apiKey", "secret", "passphrase"})
+//!   klines.json         Binance `/api/v3/klines` open prices (object: symbol -> "open")
+//!   depth.json          Binance `/api/v3/depth` snapshots (object: symbol -> snapshot)
+//!   ws_market.jsonl     Messages replayed on the Polymarket market WebSocket
+//!   binance.jsonl       Messages replayed on the Binance combined stream
+//!   coinbase.jsonl, kraken.jsonl, okx.jsonl, bybit.jsonl
+//!                       Messages replayed on `/coinbase`, `/kraken`, `/okx`
+//!                       and `/bybit` (secondary spot feeds)
+//!
+//! Fixture text may use time placeholders, rendered on every request so that
+//! time-keyed slugs and windows always match "now":
+//!   {{WINDOW_15M_TS}}  {{WINDOW_15M_START}}  {{WINDOW_15M_END}}
+//!   {{HOUR_START}}  {{HOUR_END}}  {{DAY_END}}  {{NOW_MS}}
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Delay between replayed WebSocket messages
+const WS_REPLAY_INTERVAL_MS: u64 = 200;
+
+struct MockVenue {
+    fixtures: PathBuf,
+    next_order_id: AtomicU64,
+}
+
+impl MockVenue {
+    /// Read a fixture file with time placeholders rendered for `now`
+    fn fixture(&self, name: &str) -> Option<String> {
+        let text = std::fs::read_to_string(self.fixtures.join(name)).ok()?;
+        Some(render_placeholders(&text, Utc::now()))
+    }
+
+    fn fixture_json(&self, name: &str) -> Value {
+        self.fixture(name)
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or(Value::Null)
+    }
+
+    /// Route one HTTP request; returns (status, JSON body)
+    fn handle(&self, method: &str, target: &str) -> (u16, Value) {
+        let url = match url::Url::parse(&format!("http://mock{}", target)) {
+            Ok(url) => url,
+            Err(_) => return (400, json!({ "error": "bad request target" })),
+        };
+        let query = |key: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.into_owned())
+        };
+
+        match (method, url.path()) {
+            // --- Gamma ---
+            ("GET", "/markets") => (200, filter_by(self.fixture_json("markets.json"), "slug", query("slug"))),
+            ("GET", "/events") => {
+                let events = filter_by(self.fixture_json("events.json"), "slug", query("slug"));
+                (200, filter_by(events, "seriesSlug", query("series_slug")))
+            }
+
+            // --- CLOB ---
+            ("GET", "/time") => (200, json!(Utc::now().timestamp())),
+            ("GET", "/api/v3/time") => (200, json!({ "serverTime": Utc::now().timestamp_millis() })),
+            ("GET", "/api/v3/klines") => {
+                // One 1m kline at startTime, opening at the fixture price
+                let symbol = query("symbol").unwrap_or_default();
+                let start = query("startTime").and_then(|t| t.parse::<i64>().ok());
+                match (start, self.fixture_json("klines.json").get(&symbol)) {
+                    (Some(start), Some(open)) => (200, json!([[start, open, open, open, open, "0", start + 59_999]])),
+                    _ => (200, json!([])),
+                }
+            }
+            ("GET", "/api/v3/depth") => {
+                let symbol = query("symbol").unwrap_or_default();
+                match self.fixture_json("depth.json").get(&symbol) {
+                    Some(snapshot) => (200, snapshot.clone()),
+                    None => (400, json!({ "code": -1121, "msg": "Invalid symbol." })),
+                }
+            }
+            ("GET", "/book") => {
+                let token_id = query("token_id").unwrap_or_default();
+                match self.fixture_json("books.json").get(&token_id) {
+                    Some(book) => (200, book.clone()),
+                    None => (404, json!({ "error": "No orderbook exists for the requested token id" })),
+                }
+            }
+            ("POST", "/auth/api-key") | ("GET", "/auth/derive-api-key") => match self.fixture_json("api_key.json") {
+                Value::Null => (400, json!({ "error": "Could not create api key" })),
+                creds => (200, creds),
+            },
+            ("POST", "/order") => {
+                let id = self.next_order_id.fetch_add(1, Ordering::SeqCst);
+                (200, json!({ "success": true, "orderID": format!("mock-{}", id), "status": "matched", "errorMsg": "" }))
+            }
+            ("DELETE", "/order") => (200, json!({ "canceled": [], "not_canceled": {} })),
+            ("GET", "/data/orders") => (200, json!({ "data": [], "next_cursor": "LTE=" })),
+            ("GET", "/data/trades") => (200, json!({ "data": [], "next_cursor": "LTE=" })),
+            ("GET", "/balance-allowance") => (200, json!({ "balance": "100000000", "allowance": "0" })),
+
+            // --- Data API ---
+            ("GET", "/positions") => (200, json!([])),
+
+            _ => (404, json!({ "error": format!("no mock for {} {}", method, url.path()) })),
+        }
+    }
+}
+
+/// Keep array items whose `field` equals `value` (no filter if `value` is None)
+fn filter_by(items: Value, field: &str, value: Option<String>) -> Value {
+    match (items, value) {
+        (Value::Array(items), Some(value)) => Value::Array(
+            items
+                .into_iter()
+                .filter(|item| item.get(field).and_then(|v| v.as_str()) == Some(value.as_str()))
+                .collect(),
+        ),
+        (Value::Array(items), None) => Value::Array(items),
+        _ => json!([]),
+    }
+}
+
+/// Substitute time placeholders relative to `now`
+fn render_placeholders(text: &str, now: DateTime<Utc>) -> String {
+    let ts = now.timestamp();
+    let window_15m = Utc.timestamp_opt(ts - ts.rem_euclid(900), 0).single().unwrap_or(now);
+    let hour = Utc.timestamp_opt(ts - ts.rem_euclid(3600), 0).single().unwrap_or(now);
+    let day = Utc.timestamp_opt(ts - ts.rem_euclid(86_400), 0).single().unwrap_or(now);
+    let iso = |t: DateTime<Utc>| t.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    text.replace("{{NOW_MS}}", &now.timestamp_millis().to_string())
+        .replace("{{WINDOW_15M_TS}}", &window_15m.timestamp().to_string())
+        .replace("{{WINDOW_15M_START}}", &iso(window_15m))
+        .replace("{{WINDOW_15M_END}}", &iso(window_15m + Duration::seconds(900)))
+        .replace("{{HOUR_START}}", &iso(hour))
+        .replace("{{HOUR_END}}", &iso(hour + Duration::seconds(3600)))
+        .replace("{{DAY_END}}", &iso(day + Duration::seconds(86_400)))
+}
+
+/// Serve one HTTP/1.1 request (Connection: close)
+async fn serve_http(venue: Arc<MockVenue>, mut stream: TcpStream) {
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+
+    // Read headers
+    let header_end = loop {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("").to_string();
+    let target = request_line.next().unwrap_or("/").to_string();
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    // Drain the body (contents are not needed by any mock)
+    while buf.len() < header_end + content_length {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+
+    let (status, body) = venue.handle(&method, &target);
+    println!("[MOCK] {} {} -> {}", method, target, status);
+
+    let body = body.to_string();
+    let reason = if status == 200 { "OK" } else { "Error" };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Handshake callback that records the request path and accepts every client
+struct RecordPath<'a>(&'a mut String);
+
+impl Callback for RecordPath<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        *self.0 = request.uri().path().to_string();
+        Ok(response)
+    }
+}
+
+/// Replay a JSONL fixture to one WebSocket client, chosen by request path
+async fn serve_ws(venue: Arc<MockVenue>, stream: TcpStream) {
+    let mut path = String::new();
+    let ws = tokio_tungstenite::accept_hdr_async(stream, RecordPath(&mut path)).await;
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(e) => {
+            eprintln!("[MOCK] WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+
+    let fixture = match path.as_str() {
+        p if p.starts_with("/ws/market") => "ws_market.jsonl",
+        "/coinbase" => "coinbase.jsonl",
+        "/kraken" => "kraken.jsonl",
+        "/okx" => "okx.jsonl",
+        "/bybit" => "bybit.jsonl",
+        _ => "binance.jsonl",
+    };
+    println!("[MOCK] WS client on {} <- {}", path, fixture);
+
+    let (mut write, mut read) = ws.split();
+    // Drain client messages (subscriptions, pings) in the background
+    tokio::spawn(async move { while let Some(Ok(_)) = read.next().await {} });
+
+    // Replay the fixture in a loop until the client goes away
+    loop {
+        let lines = venue.fixture(fixture).unwrap_or_default();
+        let lines: Vec<&str> = lines.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            continue;
+        }
+        for line in lines {
+            if write.send(Message::Text(line.to_string())).await.is_err() {
+                return;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(WS_REPLAY_INTERVAL_MS)).await;
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv::dotenv().ok();
+
+    let fixtures = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new("fixtures").join("mock_venue"));
+    let http_addr = std::env::var("MOCK_HTTP_ADDR").unwrap_or_else(|_| "127.0.0.1:8089".to_string());
+    let ws_addr = std::env::var("MOCK_WS_ADDR").unwrap_or_else(|_| "127.0.0.1:8090".to_string());
+
+    let venue = Arc::new(MockVenue {
+        fixtures,
+        next_order_id: AtomicU64::new(1),
+    });
+
+    let http = TcpListener::bind(&http_addr).await?;
+    let ws = TcpListener::bind(&ws_addr).await?;
+    println!("🧪 Mock venue: HTTP on {}, WS on {} (fixtures: {})", http_addr, ws_addr, venue.fixtures.display());
+
+    let ws_venue = venue.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = ws.accept().await {
+            tokio::spawn(serve_ws(ws_venue.clone(), stream));
+        }
+    });
+
+    loop {
+        let (stream, _) = http.accept().await?;
+        tokio::spawn(serve_http(venue.clone(), stream));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 16, 12, 7, 30).unwrap();
+        let rendered = render_placeholders("btc-updown-15m-{{WINDOW_15M_TS}} {{WINDOW_15M_END}} {{HOUR_END}}", now);
+        assert_eq!(rendered, "btc-updown-15m-1768564800 2026-01-16T12:15:00Z 2026-01-16T13:00:00Z");
+    }
+
+    #[test]
+    fn test_routes_filter_fixtures_by_slug() {
+        let dir = std::env::temp_dir().join(format!("mock_venue_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("markets.json"), r#"[{"slug": "a", "id": "1"}, {"slug": "b", "id": "2"}]"#).unwrap();
+        let venue = MockVenue { fixtures: dir.clone(), next_order_id: AtomicU64::new(1) };
+
+        let (status, body) = venue.handle("GET", "/markets?slug=b");
+        assert_eq!(status, 200);
+        assert_eq!(body, json!([{"slug": "b", "id": "2"}]));
+
+        let (status, _) = venue.handle("GET", "/book?token_id=missing");
+        assert_eq!(status, 404);
+
+        let (_, order) = venue.handle("POST", "/order");
+        assert_eq!(order["orderID"], "mock-1");
+
+        // No credentials fixture: key creation fails like a venue refusing it
+        let (status, _) = venue.handle("POST", "/auth/api-key");
+        assert_eq!(status, 400);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
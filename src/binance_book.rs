@@ -0,0 +1,287 @@
+//! Local Binance L2 order book
+//!
+//! Maintained from the `@depth@100ms` diff stream plus a REST snapshot,
+//! following Binance's procedure: diffs are buffered until a
+//! `/api/v3/depth` snapshot arrives, diffs already covered by the snapshot
+//! are dropped, and every later diff must continue the update-id sequence
+//! (`U` = previous `u` + 1). A gap puts the book back to buffering until a
+//! fresh snapshot is loaded. Quantities are absolute; 0 removes the level.
+//!
+//! Strategies see a `DepthSummary` (best levels, microprice and imbalance)
+//! after every applied diff rather than the book itself.
+
+use crate::exchange::ExchangeResult;
+use std::collections::BTreeMap;
+
+/// Levels per side summed for the depth imbalance
+pub const IMBALANCE_LEVELS: usize = 10;
+/// Levels requested in the REST snapshot
+const SNAPSHOT_LIMIT: u32 = 1000;
+/// Diffs kept while waiting for a snapshot (10s of 100ms diffs)
+const MAX_BUFFERED_DIFFS: usize = 100;
+
+/// One `depthUpdate` event: (price, quantity) levels in dollars / base units
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthDiff {
+    pub symbol: u64,
+    pub first_update_id: u64, // U
+    pub final_update_id: u64, // u
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+    pub ts: u64, // Event time (ms)
+}
+
+/// `/api/v3/depth` response
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// What strategies see of a local book after each applied diff
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthSummary {
+    pub symbol: u64,
+    pub best_bid: f64,
+    pub best_ask: f64,
+    pub microprice: f64,
+    /// (bid qty - ask qty) / total over the top `IMBALANCE_LEVELS`, in [-1, 1]
+    pub imbalance: f64,
+    pub ts: u64,
+}
+
+/// Outcome of feeding a diff or snapshot to the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSync {
+    /// Waiting for a snapshot; the diff was kept for replay
+    Buffered,
+    /// Already covered by the book
+    Stale,
+    Updated,
+    /// Sequence gap or snapshot older than the buffered diffs: a new
+    /// snapshot is needed
+    OutOfSync,
+}
+
+/// Size-weighted mid: leans towards the side with less resting size
+pub fn microprice(bid: f64, bid_qty: f64, ask: f64, ask_qty: f64) -> Option<f64> {
+    let total = bid_qty + ask_qty;
+    (total > 0.0).then(|| (bid * ask_qty + ask * bid_qty) / total)
+}
+
+/// Order book imbalance in [-1, 1]: positive when bids outweigh asks
+pub fn imbalance(bid_qty: f64, ask_qty: f64) -> Option<f64> {
+    let total = bid_qty + ask_qty;
+    (total > 0.0).then(|| (bid_qty - ask_qty) / total)
+}
+
+/// Binance prices have at most 8 decimals: key levels by integer 1e-8 units
+fn price_key(price: f64) -> u64 {
+    (price * 1e8).round() as u64
+}
+
+fn key_price(key: u64) -> f64 {
+    key as f64 / 1e8
+}
+
+#[derive(Debug, Default)]
+pub struct LocalBook {
+    synced: bool,
+    last_update_id: u64,
+    bids: BTreeMap<u64, f64>,
+    asks: BTreeMap<u64, f64>,
+    buffer: Vec<DepthDiff>,
+}
+
+impl LocalBook {
+    /// Drop the book and buffer diffs until the next snapshot
+    fn reset(&mut self) {
+        self.synced = false;
+        self.bids.clear();
+        self.asks.clear();
+        self.buffer.clear();
+    }
+
+    fn set_levels(&mut self, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        for (side, levels) in [(&mut self.bids, bids), (&mut self.asks, asks)] {
+            for &(price, qty) in levels {
+                if qty > 0.0 {
+                    side.insert(price_key(price), qty);
+                } else {
+                    side.remove(&price_key(price));
+                }
+            }
+        }
+    }
+
+    pub fn apply_diff(&mut self, diff: DepthDiff) -> BookSync {
+        if !self.synced {
+            if self.buffer.len() >= MAX_BUFFERED_DIFFS {
+                self.buffer.remove(0);
+            }
+            self.buffer.push(diff);
+            return BookSync::Buffered;
+        }
+        if diff.final_update_id <= self.last_update_id {
+            return BookSync::Stale;
+        }
+        if diff.first_update_id > self.last_update_id + 1 {
+            self.reset();
+            self.buffer.push(diff);
+            return BookSync::OutOfSync;
+        }
+        self.set_levels(&diff.bids, &diff.asks);
+        self.last_update_id = diff.final_update_id;
+        BookSync::Updated
+    }
+
+    /// Load a snapshot and replay the buffered diffs that follow it
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> BookSync {
+        // The snapshot must reach the first buffered diff, else diffs are missing
+        if self.buffer.first().is_some_and(|d| d.first_update_id > snapshot.last_update_id + 1) {
+            return BookSync::OutOfSync;
+        }
+        let buffered = std::mem::take(&mut self.buffer);
+        self.reset();
+        self.set_levels(&snapshot.bids, &snapshot.asks);
+        self.last_update_id = snapshot.last_update_id;
+        self.synced = true;
+        for diff in buffered {
+            if self.apply_diff(diff) == BookSync::OutOfSync {
+                return BookSync::OutOfSync;
+            }
+        }
+        BookSync::Updated
+    }
+
+    /// Best bid (price, qty)
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids.iter().next_back().map(|(&k, &q)| (key_price(k), q))
+    }
+
+    /// Best ask (price, qty)
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks.iter().next().map(|(&k, &q)| (key_price(k), q))
+    }
+
+    /// Imbalance of the resting size over the top `levels` per side
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let bid_qty: f64 = self.bids.values().rev().take(levels).sum();
+        let ask_qty: f64 = self.asks.values().take(levels).sum();
+        imbalance(bid_qty, ask_qty)
+    }
+
+    /// Summary for strategies; `None` until synced with both sides present
+    pub fn summary(&self, symbol: u64, ts: u64) -> Option<DepthSummary> {
+        if !self.synced {
+            return None;
+        }
+        let (bid, bid_qty) = self.best_bid()?;
+        let (ask, ask_qty) = self.best_ask()?;
+        Some(DepthSummary {
+            symbol,
+            best_bid: bid,
+            best_ask: ask,
+            microprice: microprice(bid, bid_qty, ask, ask_qty)?,
+            imbalance: self.imbalance(IMBALANCE_LEVELS)?,
+            ts,
+        })
+    }
+}
+
+/// Levels from `[["price", "qty"], ...]`
+fn parse_levels(levels: &serde_json::Value) -> Vec<(f64, f64)> {
+    levels
+        .as_array()
+        .map(|levels| {
+            levels
+                .iter()
+                .filter_map(|l| Some((l.get(0)?.as_str()?.parse().ok()?, l.get(1)?.as_str()?.parse().ok()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_snapshot(body: &serde_json::Value) -> Option<DepthSnapshot> {
+    Some(DepthSnapshot {
+        last_update_id: body["lastUpdateId"].as_u64()?,
+        bids: parse_levels(&body["bids"]),
+        asks: parse_levels(&body["asks"]),
+    })
+}
+
+/// REST depth snapshot for a Binance symbol ("BTCUSDT")
+pub async fn fetch_depth_snapshot(http: &reqwest::Client, symbol: &str) -> ExchangeResult<DepthSnapshot> {
+    let url = format!(
+        "{}/api/v3/depth?symbol={}&limit={}",
+        crate::endpoints::binance_rest_url(),
+        symbol,
+        SNAPSHOT_LIMIT
+    );
+    let body: serde_json::Value = http.get(url).send().await?.error_for_status()?.json().await?;
+    parse_snapshot(&body).ok_or_else(|| format!("Malformed depth snapshot for {}", symbol).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(first: u64, last: u64, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> DepthDiff {
+        DepthDiff { symbol: 1, first_update_id: first, final_update_id: last, bids: bids.to_vec(), asks: asks.to_vec(), ts: last }
+    }
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot {
+            last_update_id,
+            bids: vec![(100.0, 2.0), (99.5, 4.0)],
+            asks: vec![(100.5, 1.0), (101.0, 3.0)],
+        }
+    }
+
+    #[test]
+    fn test_snapshot_replays_buffered_diffs() {
+        let mut book = LocalBook::default();
+        assert_eq!(book.apply_diff(diff(8, 10, &[(99.0, 1.0)], &[])), BookSync::Buffered);
+        assert_eq!(book.apply_diff(diff(11, 12, &[(100.0, 0.0)], &[(100.5, 3.0)])), BookSync::Buffered);
+        assert!(book.summary(1, 0).is_none());
+
+        // Snapshot at 10: the first diff is already included, the second applies
+        assert_eq!(book.apply_snapshot(snapshot(10)), BookSync::Updated);
+        assert!(book.summary(1, 0).is_some());
+        assert_eq!(book.best_bid(), Some((99.5, 4.0)));
+        assert_eq!(book.best_ask(), Some((100.5, 3.0)));
+        assert_eq!(book.apply_diff(diff(12, 12, &[(200.0, 1.0)], &[])), BookSync::Stale);
+
+        // Sequence gap: back to buffering until the next snapshot
+        assert_eq!(book.apply_diff(diff(14, 15, &[], &[])), BookSync::OutOfSync);
+        assert!(book.summary(1, 0).is_none());
+        assert_eq!(book.apply_snapshot(snapshot(12)), BookSync::OutOfSync); // Older than the buffered diff
+        assert_eq!(book.apply_snapshot(snapshot(14)), BookSync::Updated);
+        assert_eq!(book.best_bid(), Some((100.0, 2.0)));
+    }
+
+    #[test]
+    fn test_summary_microprice_and_imbalance() {
+        let mut book = LocalBook::default();
+        book.apply_snapshot(snapshot(1));
+        let summary = book.summary(1, 42).unwrap();
+        assert_eq!((summary.best_bid, summary.best_ask, summary.ts), (100.0, 100.5, 42));
+        // More size on the bid: the microprice leans towards the ask
+        assert!((summary.microprice - (100.0 * 1.0 + 100.5 * 2.0) / 3.0).abs() < 1e-9);
+        assert!((summary.imbalance - (6.0 - 4.0) / 10.0).abs() < 1e-9);
+
+        assert_eq!(microprice(1.0, 0.0, 2.0, 0.0), None);
+        assert_eq!(imbalance(3.0, 1.0), Some(0.5));
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let body = serde_json::json!({"lastUpdateId": 1027024, "bids": [["97000.10", "0.5"]], "asks": [["97000.20", "1.25"], ["bad"]]});
+        assert_eq!(
+            parse_snapshot(&body),
+            Some(DepthSnapshot { last_update_id: 1027024, bids: vec![(97_000.1, 0.5)], asks: vec![(97_000.2, 1.25)] })
+        );
+        assert_eq!(parse_snapshot(&serde_json::json!({"code": -1121})), None);
+    }
+}
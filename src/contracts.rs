@@ -0,0 +1,414 @@
+//! On-chain Polymarket contracts (Polygon)
+//!
+//! Bindings, addresses and a transaction sender shared by redemption and the
+//! approval flow. Positions and collateral are held either by the signing
+//! EOA or by its Polymarket proxy wallet (Magic.Link / email accounts); in
+//! the proxy case every call is routed through the proxy wallet factory,
+//! which forwards it from the signer's proxy.
+//!
+//! Calls are ABI-encoded with `alloy-sol-types` and sent over the node's
+//! JSON-RPC API by `ChainClient`, which signs legacy (EIP-155) transactions
+//! with the bot's key.
+//!
+//! Addresses default to Polygon mainnet and can be overridden (`CTF_ADDRESS`,
+//! `NEG_RISK_ADAPTER_ADDRESS`, `PROXY_FACTORY_ADDRESS`, `COLLATERAL_ADDRESS`,
+//! `CTF_EXCHANGE_ADDRESS`, `NEG_RISK_EXCHANGE_ADDRESS`) to run against test
+//! deployments on a local chain.
+
+use crate::exchange::ExchangeResult;
+use alloy_consensus::{SignableTransaction, TxLegacy};
+use alloy_primitives::{Address, Bytes, TxHash, TxKind, U256, U64};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolCall};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::Arc;
+
+sol! {
+    interface ConditionalTokens {
+        function payoutDenominator(bytes32 conditionId) external view returns (uint256);
+        function balanceOf(address owner, uint256 id) external view returns (uint256);
+        function redeemPositions(address collateralToken, bytes32 parentCollectionId, bytes32 conditionId, uint256[] indexSets) external;
+        function isApprovedForAll(address owner, address operator) external view returns (bool);
+        function setApprovalForAll(address operator, bool approved) external;
+    }
+
+    interface NegRiskAdapter {
+        function redeemPositions(bytes32 conditionId, uint256[] amounts) external;
+    }
+
+    interface ProxyWalletFactory {
+        struct ProxyTransaction { uint8 typeCode; address to; uint256 value; bytes data; }
+        function proxy(ProxyTransaction[] calls) external payable returns (bytes[]);
+    }
+
+    interface Erc20 {
+        function balanceOf(address owner) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+}
+
+/// Polygon mainnet Conditional Tokens Framework
+const POLYGON_CTF: &str = "0x4D97DCd97eC945f40cF65F87097ACe5EA0476045";
+/// Polygon mainnet NegRisk adapter
+const POLYGON_NEG_RISK_ADAPTER: &str = "0xd91E80cF2E7be2e162c6513ceD06f1dD0dA35296";
+/// Polygon mainnet Polymarket proxy wallet factory
+const POLYGON_PROXY_FACTORY: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
+/// Polygon mainnet USDC.e (Polymarket collateral)
+const POLYGON_USDC: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+/// Polygon mainnet CTF Exchange
+const POLYGON_CTF_EXCHANGE: &str = "0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E";
+/// Polygon mainnet NegRisk CTF Exchange
+const POLYGON_NEG_RISK_EXCHANGE: &str = "0xC5d563A36AE78145C45a50134d48A1215220f80a";
+
+/// Proxy factory call type for a plain CALL
+const PROXY_CALL_TYPE_CALL: u8 = 1;
+
+/// Headroom over `eth_estimateGas` (percent)
+const GAS_LIMIT_MARGIN_PCT: u64 = 20;
+/// How often, and how many times, to poll for a sent transaction's receipt
+const RECEIPT_POLL_INTERVAL_MS: u64 = 1_000;
+const RECEIPT_POLLS: u32 = 180;
+
+/// Contract addresses
+#[derive(Debug, Clone)]
+pub struct ContractConfig {
+    pub ctf: Address,
+    pub neg_risk_adapter: Address,
+    pub proxy_factory: Address,
+    pub collateral: Address,
+    pub ctf_exchange: Address,
+    pub neg_risk_exchange: Address,
+}
+
+impl ContractConfig {
+    /// Polygon mainnet addresses, with env overrides for test deployments
+    pub fn from_env() -> ExchangeResult<Self> {
+        let address = |var: &str, default: &str| -> ExchangeResult<Address> {
+            let value = std::env::var(var).unwrap_or_else(|_| default.to_string());
+            Ok(Address::from_str(&value)?)
+        };
+        Ok(Self {
+            ctf: address("CTF_ADDRESS", POLYGON_CTF)?,
+            neg_risk_adapter: address("NEG_RISK_ADAPTER_ADDRESS", POLYGON_NEG_RISK_ADAPTER)?,
+            proxy_factory: address("PROXY_FACTORY_ADDRESS", POLYGON_PROXY_FACTORY)?,
+            collateral: address("COLLATERAL_ADDRESS", POLYGON_USDC)?,
+            ctf_exchange: address("CTF_EXCHANGE_ADDRESS", POLYGON_CTF_EXCHANGE)?,
+            neg_risk_exchange: address("NEG_RISK_EXCHANGE_ADDRESS", POLYGON_NEG_RISK_EXCHANGE)?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    result: serde_json::Value,
+    error: Option<RpcError>,
+}
+
+/// The fields of a transaction receipt we use
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxReceipt {
+    pub transaction_hash: TxHash,
+    pub status: Option<U64>,
+    #[allow(dead_code)] // Only the test deployments create contracts
+    pub contract_address: Option<Address>,
+}
+
+impl TxReceipt {
+    pub fn succeeded(&self) -> bool {
+        self.status == Some(U64::from(1))
+    }
+}
+
+/// JSON-RPC client that signs transactions with the bot's key
+pub struct ChainClient {
+    http: reqwest::Client,
+    rpc_url: String,
+    signer: PrivateKeySigner,
+    chain_id: u64,
+}
+
+impl ChainClient {
+    pub fn new(rpc_url: &str, signer: PrivateKeySigner, chain_id: u64) -> Self {
+        Self { http: reqwest::Client::new(), rpc_url: rpc_url.to_string(), signer, chain_id }
+    }
+
+    /// Address of the signing key
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> ExchangeResult<T> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: RpcResponse = self.http.post(&self.rpc_url).json(&body).send().await?.error_for_status()?.json().await?;
+        if let Some(error) = response.error {
+            return Err(format!("{} failed ({}): {}", method, error.code, error.message).into());
+        }
+        Ok(serde_json::from_value(response.result)?)
+    }
+
+    /// Call a view function of `to` at the latest block
+    pub async fn call<C: SolCall>(&self, to: Address, call: &C) -> ExchangeResult<C::Return> {
+        let data: Bytes = self
+            .request("eth_call", json!([{ "to": to, "data": Bytes::from(call.abi_encode()) }, "latest"]))
+            .await?;
+        Ok(C::abi_decode_returns(&data, true)?)
+    }
+
+    /// Sign and send a transaction from the signer (`TxKind::Create` deploys
+    /// `input`); returns its receipt once mined, whether or not it reverted
+    pub async fn send_transaction(&self, to: TxKind, input: Bytes) -> ExchangeResult<TxReceipt> {
+        let from = self.address();
+        let mut request = json!({ "from": from, "data": &input });
+        if let TxKind::Call(to) = to {
+            request["to"] = json!(to);
+        }
+        let nonce: U64 = self.request("eth_getTransactionCount", json!([from, "pending"])).await?;
+        let gas_price: U256 = self.request("eth_gasPrice", json!([])).await?;
+        let gas: U64 = self.request("eth_estimateGas", json!([request])).await?;
+
+        let tx = TxLegacy {
+            chain_id: Some(self.chain_id),
+            nonce: nonce.to(),
+            gas_price: gas_price.to(),
+            gas_limit: gas.to::<u64>() * (100 + GAS_LIMIT_MARGIN_PCT) / 100,
+            to,
+            value: U256::ZERO,
+            input,
+        };
+        let hash: TxHash = self.request("eth_sendRawTransaction", json!([self.sign(tx)?])).await?;
+
+        for _ in 0..RECEIPT_POLLS {
+            let receipt: Option<TxReceipt> = self.request("eth_getTransactionReceipt", json!([hash])).await?;
+            if let Some(receipt) = receipt {
+                return Ok(receipt);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(RECEIPT_POLL_INTERVAL_MS)).await;
+        }
+        Err(format!("transaction {:?} not mined", hash).into())
+    }
+
+    /// RLP-encoded signed transaction (EIP-155)
+    fn sign(&self, tx: TxLegacy) -> ExchangeResult<Bytes> {
+        let signature = self.signer.sign_hash_sync(&tx.signature_hash())?;
+        let mut raw = Vec::new();
+        tx.into_signed(signature).rlp_encode(&mut raw);
+        Ok(raw.into())
+    }
+}
+
+/// Signing client on `rpc_url` for `private_key`
+pub async fn chain_client(rpc_url: &str, private_key: &str) -> ExchangeResult<Arc<ChainClient>> {
+    let signer: PrivateKeySigner = private_key.parse()?;
+    let mut client = ChainClient::new(rpc_url, signer, 0);
+    let chain_id: U64 = client.request("eth_chainId", json!([])).await?;
+    client.chain_id = chain_id.to();
+    Ok(Arc::new(client))
+}
+
+/// Proxy wallet holding funds (`POLYMARKET_FUNDER_ADDRESS`), if any
+pub fn proxy_wallet_from_env() -> ExchangeResult<Option<Address>> {
+    match std::env::var("POLYMARKET_FUNDER_ADDRESS") {
+        Ok(addr) => Ok(Some(Address::from_str(&addr)?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Sends transactions on behalf of the account holding funds: the signer
+/// itself, or its proxy wallet through the factory
+pub struct TxSender {
+    client: Arc<ChainClient>,
+    proxy_factory: Option<Address>,
+    holder: Address,
+}
+
+impl TxSender {
+    /// `proxy_wallet`: the proxy holding funds, if not the signer itself
+    pub fn new(client: Arc<ChainClient>, config: &ContractConfig, proxy_wallet: Option<Address>) -> Self {
+        Self {
+            proxy_factory: proxy_wallet.map(|_| config.proxy_factory),
+            holder: proxy_wallet.unwrap_or(client.address()),
+            client,
+        }
+    }
+
+    /// Address holding funds and positions
+    pub fn holder(&self) -> Address {
+        self.holder
+    }
+
+    pub fn client(&self) -> Arc<ChainClient> {
+        self.client.clone()
+    }
+
+    /// Send `calldata` to `to` from the holder; returns the transaction hash
+    /// once mined successfully
+    pub async fn send(&self, to: Address, calldata: Bytes) -> ExchangeResult<TxHash> {
+        let (to, calldata) = match self.proxy_factory {
+            Some(factory) => {
+                let calls = vec![ProxyWalletFactory::ProxyTransaction {
+                    typeCode: PROXY_CALL_TYPE_CALL,
+                    to,
+                    value: U256::ZERO,
+                    data: calldata,
+                }];
+                (factory, ProxyWalletFactory::proxyCall { calls }.abi_encode().into())
+            }
+            None => (to, calldata),
+        };
+        let receipt = self.client.send_transaction(TxKind::Call(to), calldata).await?;
+        if !receipt.succeeded() {
+            return Err(format!("transaction {:?} reverted", receipt.transaction_hash).into());
+        }
+        Ok(receipt.transaction_hash)
+    }
+}
+
+/// Local chain for tests: an anvil node with the mock collateral and CTF
+/// from `fixtures/mock_contracts`, deployed by anvil account #0
+#[cfg(test)]
+pub(crate) mod mock_chain {
+    use super::*;
+    use alloy_primitives::{keccak256, B256};
+
+    /// anvil account #0
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    /// anvil's default chain ID
+    const ANVIL_CHAIN_ID: u64 = 31337;
+
+    sol! {
+        interface MockCollateral {
+            function mint(address to, uint256 amount) external;
+        }
+
+        interface MockConditionalTokens {
+            function mint(address to, uint256 id, uint256 amount) external;
+            function setPayoutDenominator(bytes32 conditionId, uint256 denominator) external;
+        }
+    }
+
+    pub struct MockChain {
+        _node: tokio::process::Child, // Killed on drop
+        pub client: Arc<ChainClient>,
+        pub config: ContractConfig,
+    }
+
+    /// Position ID in the mock CTF: keccak256(conditionId, indexSet)
+    pub fn position_id(condition_id: B256, index_set: u64) -> U256 {
+        let hash = keccak256([condition_id.as_slice(), &U256::from(index_set).to_be_bytes::<32>()].concat());
+        U256::from_be_bytes(hash.0)
+    }
+
+    impl MockChain {
+        /// Start anvil (`ANVIL_BIN`, default `anvil` on the PATH) and deploy
+        /// the mocks; `None` if anvil can't be started
+        pub async fn start() -> Option<Self> {
+            let port = std::net::TcpListener::bind("127.0.0.1:0").ok()?.local_addr().ok()?.port();
+            let bin = std::env::var("ANVIL_BIN").unwrap_or_else(|_| "anvil".to_string());
+            let node = match tokio::process::Command::new(&bin)
+                .args(["--port", &port.to_string()])
+                .stdout(std::process::Stdio::null())
+                .kill_on_drop(true)
+                .spawn()
+            {
+                Ok(node) => node,
+                Err(e) => {
+                    eprintln!("skipping: could not start {} ({})", bin, e);
+                    return None;
+                }
+            };
+
+            let signer: PrivateKeySigner = TEST_KEY.parse().unwrap();
+            let client = ChainClient::new(&format!("http://127.0.0.1:{}", port), signer, ANVIL_CHAIN_ID);
+            for _ in 0..100 {
+                if client.request::<U64>("eth_chainId", json!([])).await.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            }
+
+            let ctf = deploy(&client, include_str!("../fixtures/mock_contracts/ctf.hex")).await;
+            let collateral = deploy(&client, include_str!("../fixtures/mock_contracts/collateral.hex")).await;
+            let config = ContractConfig {
+                ctf,
+                neg_risk_adapter: Address::repeat_byte(0xad),
+                proxy_factory: Address::repeat_byte(0xfa),
+                collateral,
+                ctf_exchange: Address::repeat_byte(0xe1),
+                neg_risk_exchange: Address::repeat_byte(0xe2),
+            };
+            Some(Self { _node: node, client: Arc::new(client), config })
+        }
+
+        /// Deployer's account
+        pub fn signer(&self) -> Address {
+            self.client.address()
+        }
+
+        /// Sender for the deployer's own account
+        pub fn sender(&self) -> TxSender {
+            TxSender::new(self.client.clone(), &self.config, None)
+        }
+
+        /// Credit the deployer with collateral (base units)
+        pub async fn mint_collateral(&self, amount: U256) {
+            let call = MockCollateral::mintCall { to: self.signer(), amount };
+            self.sender().send(self.config.collateral, call.abi_encode().into()).await.unwrap();
+        }
+
+        /// Credit the deployer with outcome tokens
+        pub async fn mint_position(&self, id: U256, amount: U256) {
+            let call = MockConditionalTokens::mintCall { to: self.signer(), id, amount };
+            self.sender().send(self.config.ctf, call.abi_encode().into()).await.unwrap();
+        }
+
+        /// Resolve a condition
+        pub async fn resolve(&self, condition_id: B256) {
+            let call = MockConditionalTokens::setPayoutDenominatorCall { conditionId: condition_id, denominator: U256::from(1) };
+            self.sender().send(self.config.ctf, call.abi_encode().into()).await.unwrap();
+        }
+    }
+
+    async fn deploy(client: &ChainClient, creation_code: &str) -> Address {
+        let code = hex::decode(creation_code.trim()).unwrap();
+        let receipt = client.send_transaction(TxKind::Create, code.into()).await.unwrap();
+        receipt.contract_address.expect("mock deployed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signs_eip155_transactions() {
+        // The example transaction from EIP-155
+        let signer: PrivateKeySigner = "0x4646464646464646464646464646464646464646464646464646464646464646".parse().unwrap();
+        let client = ChainClient::new("http://127.0.0.1:1", signer, 1);
+        let tx = TxLegacy {
+            chain_id: Some(1),
+            nonce: 9,
+            gas_price: 20_000_000_000,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0x35)),
+            value: U256::from(1_000_000_000_000_000_000u128),
+            input: Bytes::new(),
+        };
+        assert_eq!(
+            hex::encode(client.sign(tx).unwrap()),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+}
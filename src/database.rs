@@ -4,8 +4,8 @@
 //! heartbeats, and wallet balance snapshots to the dashboard database.
 
 use sqlx::{postgres::PgPoolOptions, PgPool, Error};
-// use chrono::{DateTime, Utc};
-use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use bigdecimal::{BigDecimal, ToPrimitive};
 use std::str::FromStr;
 
 /// Initialize the database connection pool.
@@ -22,40 +22,56 @@ pub async fn init_pool() -> Result<PgPool, Error> {
         .await?;
     
     println!("✅ Database connection pool initialized");
+    ensure_schema(&pool).await?;
     Ok(pool)
 }
 
+/// Add columns and tables introduced after the dashboard schema was created.
+async fn ensure_schema(pool: &PgPool) -> Result<(), Error> {
+    sqlx::query("ALTER TABLE trade_logs ADD COLUMN IF NOT EXISTS fee NUMERIC")
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS window_opens (
+            asset TEXT NOT NULL,
+            window_start TIMESTAMPTZ NOT NULL,
+            open_price NUMERIC NOT NULL,
+            source TEXT NOT NULL,
+            captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (asset, window_start)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 /// Insert a trade log entry.
 /// 
 /// Called from execution.rs when a trade is executed.
-pub async fn insert_trade_log(
-    pool: &PgPool,
-    ticker: &str,
-    side: &str,
-    price: f64,
-    size: f64,
-    value: f64,
-    latency_ms: Option<i32>,
-    pnl: Option<f64>,
-) -> Result<(), Error> {
-    let price_bd = BigDecimal::from_str(&format!("{:.8}", price)).unwrap_or_default();
-    let size_bd = BigDecimal::from_str(&format!("{:.8}", size)).unwrap_or_default();
-    let value_bd = BigDecimal::from_str(&format!("{:.8}", value)).unwrap_or_default();
-    let pnl_bd = pnl.map(|p| BigDecimal::from_str(&format!("{:.8}", p)).unwrap_or_default());
+pub async fn insert_trade_log(pool: &PgPool, msg: &TradeLogMsg) -> Result<(), Error> {
+    let price_bd = BigDecimal::from_str(&format!("{:.8}", msg.price)).unwrap_or_default();
+    let size_bd = BigDecimal::from_str(&format!("{:.8}", msg.size)).unwrap_or_default();
+    let value_bd = BigDecimal::from_str(&format!("{:.8}", msg.value)).unwrap_or_default();
+    let pnl_bd = msg.pnl.map(|p| BigDecimal::from_str(&format!("{:.8}", p)).unwrap_or_default());
+    let fee_bd = msg.fee.map(|f| BigDecimal::from_str(&format!("{:.8}", f)).unwrap_or_default());
     
     sqlx::query(
         r#"
-        INSERT INTO trade_logs (ticker, side, price, size, value, latency_ms, pnl, executed_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+        INSERT INTO trade_logs (ticker, side, price, size, value, latency_ms, pnl, fee, executed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
         "#
     )
-    .bind(ticker)
-    .bind(side)
+    .bind(&msg.ticker)
+    .bind(&msg.side)
     .bind(&price_bd)
     .bind(&size_bd)
     .bind(&value_bd)
-    .bind(latency_ms)
+    .bind(msg.latency_ms)
     .bind(&pnl_bd)
+    .bind(&fee_bd)
     .execute(pool)
     .await?;
     
@@ -182,6 +198,102 @@ pub async fn insert_activity_log(
     Ok(())
 }
 
+/// Persist the open price (strike) of an Up/Down window.
+/// 
+/// Called from window_open.rs when a window's open is captured; the first
+/// capture wins.
+pub async fn insert_window_open(
+    pool: &PgPool,
+    asset: &str,
+    window_start: DateTime<Utc>,
+    open_price: f64,
+    source: &str,
+) -> Result<(), Error> {
+    let price_bd = BigDecimal::from_str(&format!("{:.8}", open_price)).unwrap_or_default();
+    
+    sqlx::query(
+        r#"
+        INSERT INTO window_opens (asset, window_start, open_price, source)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (asset, window_start) DO NOTHING
+        "#
+    )
+    .bind(asset)
+    .bind(window_start)
+    .bind(&price_bd)
+    .bind(source)
+    .execute(pool)
+    .await?;
+    
+    Ok(())
+}
+
+/// Fetch window opens for windows starting at or after `since`.
+/// 
+/// Called at startup so windows opened before a restart keep their strike.
+pub async fn fetch_window_opens_since(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<Vec<(String, DateTime<Utc>, f64)>, Error> {
+    let rows: Vec<(String, DateTime<Utc>, BigDecimal)> = sqlx::query_as(
+        r#"
+        SELECT asset, window_start, open_price
+        FROM window_opens
+        WHERE window_start >= $1
+        ORDER BY window_start
+        "#
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(rows
+        .into_iter()
+        .map(|(asset, start, price)| (asset, start, price.to_f64().unwrap_or(0.0)))
+        .collect())
+}
+
+/// A row of `trade_logs`, as read back for reconciliation.
+#[derive(Debug, Clone)]
+pub struct TradeLogRow {
+    pub ticker: String,
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Fetch trade log entries executed at or after `since`, oldest first.
+/// 
+/// Called at startup to compare the DB's view of open positions with the exchange.
+pub async fn fetch_trade_logs_since(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<Vec<TradeLogRow>, Error> {
+    let rows: Vec<(String, String, BigDecimal, BigDecimal, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT ticker, side, price, size, executed_at
+        FROM trade_logs
+        WHERE executed_at >= $1
+        ORDER BY executed_at
+        "#
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    
+    Ok(rows
+        .into_iter()
+        .map(|(ticker, side, price, size, executed_at)| TradeLogRow {
+            ticker,
+            side,
+            price: price.to_f64().unwrap_or(0.0),
+            size: size.to_f64().unwrap_or(0.0),
+            executed_at,
+        })
+        .collect())
+}
+
 /// Database log sender that can be used from sync code.
 /// 
 /// This struct holds a channel sender that forwards log requests
@@ -209,6 +321,7 @@ pub struct TradeLogMsg {
     pub value: f64,
     pub latency_ms: Option<i32>,
     pub pnl: Option<f64>,
+    pub fee: Option<f64>,      // Trading fee paid (USDC)
 }
 
 /// Activity log message for bot_activity_log table
@@ -247,16 +360,7 @@ impl DbLogger {
         let pool_clone = pool.clone();
         tokio::spawn(async move {
             while let Some(msg) = trade_rx.recv().await {
-                if let Err(e) = insert_trade_log(
-                    &pool_clone,
-                    &msg.ticker,
-                    &msg.side,
-                    msg.price,
-                    msg.size,
-                    msg.value,
-                    msg.latency_ms,
-                    msg.pnl,
-                ).await {
+                if let Err(e) = insert_trade_log(&pool_clone, &msg).await {
                     eprintln!("[DB] Trade log error: {:?}", e);
                 }
             }
@@ -0,0 +1,76 @@
+//! Venue base URLs
+//!
+//! Defaults point at the production APIs. Each can be overridden through the
+//! environment, e.g. to run the whole bot against the local mock venue
+//! (`src/bin/mock_venue.rs`) with no network access.
+
+use std::sync::OnceLock;
+
+/// Read an env override once, falling back to the production URL
+fn from_env(cell: &'static OnceLock<String>, var: &str, default: &str) -> &'static str {
+    cell.get_or_init(|| {
+        std::env::var(var)
+            .map(|v| v.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| default.to_string())
+    })
+}
+
+/// Polymarket CLOB REST API (`POLYMARKET_CLOB_URL`)
+pub fn clob_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "POLYMARKET_CLOB_URL", "https://clob.polymarket.com")
+}
+
+/// Polymarket Gamma (market metadata) API (`POLYMARKET_GAMMA_URL`)
+pub fn gamma_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "POLYMARKET_GAMMA_URL", "https://gamma-api.polymarket.com")
+}
+
+/// Polymarket data API (positions) (`POLYMARKET_DATA_API_URL`)
+pub fn data_api_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "POLYMARKET_DATA_API_URL", "https://data-api.polymarket.com")
+}
+
+/// Polymarket CLOB market WebSocket (`POLYMARKET_WS_URL`)
+pub fn clob_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "POLYMARKET_WS_URL", "wss://ws-subscriptions-clob.polymarket.com/ws/market")
+}
+
+/// Binance WebSocket base, without the `/stream` path (`BINANCE_WS_URL`)
+pub fn binance_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "BINANCE_WS_URL", "wss://stream.binance.com:9443")
+}
+
+/// Binance REST API (`BINANCE_REST_URL`)
+pub fn binance_rest_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "BINANCE_REST_URL", "https://api.binance.com")
+}
+
+/// Coinbase Exchange market data WebSocket (`COINBASE_WS_URL`)
+pub fn coinbase_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "COINBASE_WS_URL", "wss://ws-feed.exchange.coinbase.com")
+}
+
+/// Kraken v2 public WebSocket (`KRAKEN_WS_URL`)
+pub fn kraken_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "KRAKEN_WS_URL", "wss://ws.kraken.com/v2")
+}
+
+/// OKX v5 public WebSocket (`OKX_WS_URL`)
+pub fn okx_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "OKX_WS_URL", "wss://ws.okx.com:8443/ws/v5/public")
+}
+
+/// Bybit v5 spot public WebSocket (`BYBIT_WS_URL`)
+pub fn bybit_ws_url() -> &'static str {
+    static CELL: OnceLock<String> = OnceLock::new();
+    from_env(&CELL, "BYBIT_WS_URL", "wss://stream.bybit.com/v5/public/spot")
+}
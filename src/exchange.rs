@@ -0,0 +1,328 @@
+//! Exchange abstraction
+//!
+//! `ExchangeClient` covers everything execution and market discovery need from
+//! a venue. `PolymarketClient` implements it against the real CLOB/Gamma APIs;
+//! `SimulatedExchange` is an in-memory venue for tests.
+
+use crate::polymarket::{CachedMarket, OrderSide};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Error type for venue calls (Send + Sync so it can cross tokio tasks)
+pub type ExchangeError = Box<dyn std::error::Error + Send + Sync>;
+pub type ExchangeResult<T> = Result<T, ExchangeError>;
+
+/// A resting order on the venue
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub token_id: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub size: f64,         // Original size in shares
+    pub size_matched: f64, // Filled so far in shares
+}
+
+/// One of our fills on the venue
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeFill {
+    pub trade_id: String,
+    pub order_id: String, // Our order that was matched
+    pub token_id: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub size: f64,                     // Shares
+    pub matched_at: DateTime<Utc>,
+}
+
+/// Shares held in one outcome token
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPosition {
+    pub token_id: String,
+    pub condition_id: String,
+    pub size: f64,      // Shares
+    pub avg_price: f64, // Average entry price
+}
+
+/// Venue operations used by execution and market discovery.
+///
+/// Methods return `Send` futures so implementors can be driven from
+/// `tokio::spawn`ed tasks.
+pub trait ExchangeClient: Send + Sync + 'static {
+    /// Discover tradeable markets, keyed by asset symbol ("BTC", ...)
+    fn discover_markets(&self) -> impl Future<Output = ExchangeResult<HashMap<String, Vec<CachedMarket>>>> + Send;
+
+    /// Best bid/ask for a token, `None` if unavailable
+    fn fetch_orderbook(&self, token_id: &str) -> impl Future<Output = Option<(f64, f64)>> + Send;
+
+    /// Place a GTC limit order for a token of `market` (`size` in shares),
+    /// signed with the market's fee rate and tick size; returns the order ID
+    fn place_order(
+        &self,
+        market: &CachedMarket,
+        token_id: &str,
+        side: OrderSide,
+        size: f64,
+        price: f64,
+    ) -> impl Future<Output = ExchangeResult<String>> + Send;
+
+    /// Cancel a resting order
+    fn cancel_order(&self, order_id: &str) -> impl Future<Output = ExchangeResult<()>> + Send;
+
+    /// All resting orders for this account
+    fn fetch_open_orders(&self) -> impl Future<Output = ExchangeResult<Vec<OpenOrder>>> + Send;
+
+    /// Fills for this account matched at or after `after` (all of them if
+    /// `None`), newest first
+    fn fetch_trades(&self, after: Option<DateTime<Utc>>) -> impl Future<Output = ExchangeResult<Vec<TradeFill>>> + Send;
+
+    /// Available collateral (USDC) balance
+    fn fetch_balance(&self) -> impl Future<Output = ExchangeResult<f64>> + Send;
+
+    /// Current outcome token positions
+    fn fetch_positions(&self) -> impl Future<Output = ExchangeResult<Vec<TokenPosition>>> + Send;
+}
+
+/// In-memory venue: fixed markets and books, immediate fills for marketable
+/// orders, resting orders otherwise.
+#[cfg(test)]
+pub struct SimulatedExchange {
+    state: Mutex<SimState>,
+}
+
+#[cfg(test)]
+struct SimState {
+    markets: HashMap<String, Vec<CachedMarket>>,
+    books: HashMap<String, (f64, f64)>,
+    orders: Vec<OpenOrder>,
+    trades: Vec<TradeFill>,
+    positions: HashMap<String, TokenPosition>,
+    balance: f64,
+    next_order_id: u64,
+    discovery_down: bool,
+}
+
+#[cfg(test)]
+impl SimulatedExchange {
+    /// Create a simulated venue with a starting USDC balance
+    pub fn new(balance: f64) -> Self {
+        Self {
+            state: Mutex::new(SimState {
+                markets: HashMap::new(),
+                books: HashMap::new(),
+                orders: Vec::new(),
+                trades: Vec::new(),
+                positions: HashMap::new(),
+                balance,
+                next_order_id: 1,
+                discovery_down: false,
+            }),
+        }
+    }
+
+    /// Add a market returned by `discover_markets`
+    pub fn add_market(&self, market: CachedMarket) {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        state.markets.entry(market.asset.clone()).or_default().push(market);
+    }
+
+    /// Set the best bid/ask for a token
+    pub fn set_book(&self, token_id: &str, bid: f64, ask: f64) {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        state.books.insert(token_id.to_string(), (bid, ask));
+    }
+
+    /// Make `discover_markets` fail (e.g. Gamma unreachable) until reset
+    pub fn set_discovery_down(&self, down: bool) {
+        self.state.lock().expect("sim state lock poisoned").discovery_down = down;
+    }
+
+    /// Match up to `shares` of a resting order at its limit price, as if a
+    /// taker crossed it; the order leaves the book once fully matched
+    pub fn match_order(&self, order_id: &str, shares: f64) -> ExchangeResult<()> {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        let order = state
+            .orders
+            .iter()
+            .find(|o| o.order_id == order_id)
+            .cloned()
+            .ok_or_else(|| format!("unknown order: {}", order_id))?;
+        let shares = shares.min(order.size - order.size_matched);
+        state.fill(&order.order_id, &order.token_id, order.side, shares, order.price)?;
+        for resting in state.orders.iter_mut().filter(|o| o.order_id == order_id) {
+            resting.size_matched += shares;
+        }
+        state.orders.retain(|o| o.size - o.size_matched > 1e-9);
+        Ok(())
+    }
+
+    /// Credit shares of a token (e.g. held from before startup)
+    pub fn set_position(&self, position: TokenPosition) {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        state.positions.insert(position.token_id.clone(), position);
+    }
+}
+
+#[cfg(test)]
+impl SimState {
+    fn fill(&mut self, order_id: &str, token_id: &str, side: OrderSide, size: f64, price: f64) -> ExchangeResult<()> {
+        match side {
+            OrderSide::BUY => {
+                let cost = size * price;
+                if cost > self.balance {
+                    return Err(format!("insufficient balance: need ${:.2}, have ${:.2}", cost, self.balance).into());
+                }
+                self.balance -= cost;
+                let position = self.positions.entry(token_id.to_string()).or_insert_with(|| TokenPosition {
+                    token_id: token_id.to_string(),
+                    condition_id: String::new(),
+                    size: 0.0,
+                    avg_price: 0.0,
+                });
+                let total = position.size + size;
+                position.avg_price = (position.avg_price * position.size + price * size) / total;
+                position.size = total;
+            }
+            OrderSide::SELL => {
+                let held = self.positions.get(token_id).map(|p| p.size).unwrap_or(0.0);
+                if held + 1e-9 < size {
+                    return Err(format!("insufficient shares: need {:.2}, have {:.2}", size, held).into());
+                }
+                self.balance += size * price;
+                if let Some(position) = self.positions.get_mut(token_id) {
+                    position.size -= size;
+                    if position.size <= 1e-9 {
+                        self.positions.remove(token_id);
+                    }
+                }
+            }
+        }
+        self.trades.insert(0, TradeFill {
+            trade_id: format!("sim-trade-{}", self.trades.len() + 1),
+            order_id: order_id.to_string(),
+            token_id: token_id.to_string(),
+            side,
+            price,
+            size,
+            matched_at: Utc::now(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl ExchangeClient for SimulatedExchange {
+    async fn discover_markets(&self) -> ExchangeResult<HashMap<String, Vec<CachedMarket>>> {
+        let state = self.state.lock().expect("sim state lock poisoned");
+        if state.discovery_down {
+            return Err("market discovery unavailable".into());
+        }
+        Ok(state.markets.clone())
+    }
+
+    async fn fetch_orderbook(&self, token_id: &str) -> Option<(f64, f64)> {
+        self.state.lock().expect("sim state lock poisoned").books.get(token_id).copied()
+    }
+
+    async fn place_order(&self, _market: &CachedMarket, token_id: &str, side: OrderSide, size: f64, price: f64) -> ExchangeResult<String> {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        let order_id = format!("sim-{}", state.next_order_id);
+        state.next_order_id += 1;
+
+        // Marketable orders fill at the touch, others rest on the book
+        let touch = state.books.get(token_id).copied();
+        let fill_price = match (side, touch) {
+            (OrderSide::BUY, Some((_, ask))) if price >= ask => Some(ask),
+            (OrderSide::SELL, Some((bid, _))) if price <= bid => Some(bid),
+            _ => None,
+        };
+
+        match fill_price {
+            Some(fill) => state.fill(&order_id, token_id, side, size, fill)?,
+            None => state.orders.push(OpenOrder {
+                order_id: order_id.clone(),
+                token_id: token_id.to_string(),
+                side,
+                price,
+                size,
+                size_matched: 0.0,
+            }),
+        }
+        Ok(order_id)
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> ExchangeResult<()> {
+        let mut state = self.state.lock().expect("sim state lock poisoned");
+        let before = state.orders.len();
+        state.orders.retain(|o| o.order_id != order_id);
+        if state.orders.len() == before {
+            return Err(format!("unknown order: {}", order_id).into());
+        }
+        Ok(())
+    }
+
+    async fn fetch_open_orders(&self) -> ExchangeResult<Vec<OpenOrder>> {
+        Ok(self.state.lock().expect("sim state lock poisoned").orders.clone())
+    }
+
+    async fn fetch_trades(&self, after: Option<DateTime<Utc>>) -> ExchangeResult<Vec<TradeFill>> {
+        let state = self.state.lock().expect("sim state lock poisoned");
+        Ok(state.trades.iter().filter(|t| after.is_none_or(|a| t.matched_at >= a)).cloned().collect())
+    }
+
+    async fn fetch_balance(&self) -> ExchangeResult<f64> {
+        Ok(self.state.lock().expect("sim state lock poisoned").balance)
+    }
+
+    async fn fetch_positions(&self) -> ExchangeResult<Vec<TokenPosition>> {
+        Ok(self.state.lock().expect("sim state lock poisoned").positions.values().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market() -> CachedMarket {
+        crate::polymarket::test_market("BTC", Utc::now() + chrono::Duration::minutes(10))
+    }
+
+    #[tokio::test]
+    async fn test_marketable_buy_fills_at_ask() {
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("up", 0.44, 0.46);
+
+        sim.place_order(&market(), "up", OrderSide::BUY, 10.0, 0.50).await.unwrap();
+
+        assert!((sim.fetch_balance().await.unwrap() - 95.4).abs() < 1e-9);
+        let positions = sim.fetch_positions().await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert!((positions[0].avg_price - 0.46).abs() < 1e-9);
+        assert!(sim.fetch_open_orders().await.unwrap().is_empty());
+        assert_eq!(sim.fetch_trades(None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_passive_order_rests_and_cancels() {
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("up", 0.44, 0.46);
+
+        let id = sim.place_order(&market(), "up", OrderSide::BUY, 10.0, 0.40).await.unwrap();
+        assert_eq!(sim.fetch_open_orders().await.unwrap().len(), 1);
+
+        sim.cancel_order(&id).await.unwrap();
+        assert!(sim.fetch_open_orders().await.unwrap().is_empty());
+        assert!(sim.cancel_order(&id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sell_requires_shares() {
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("up", 0.44, 0.46);
+
+        assert!(sim.place_order(&market(), "up", OrderSide::SELL, 5.0, 0.40).await.is_err());
+    }
+}
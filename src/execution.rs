@@ -1,10 +1,15 @@
 use crate::database::{insert_wallet_balance, DbLogger, TradeLogMsg};
-use crate::polymarket::PolymarketClient;
+use crate::exchange::{ExchangeClient, OpenOrder, TokenPosition};
+use crate::ledger::SharedLedger;
+use crate::polymarket::{live_market, select_live_market, CachedMarket, MarketCache, MarketSnapshot, OrderSide};
 use crate::risk::RiskManager;
+use crate::timesync::{SharedClock, Venue};
+use crate::types::{MarketType, Side};
 use alloy_signer::Signer;
 use alloy_signer::Signature;
 use alloy_signer_local::PrivateKeySigner;
-use rtrb::Consumer;
+use chrono::{DateTime, Utc};
+use rtrb::{Consumer, Producer};
 use sqlx::PgPool;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -12,25 +17,505 @@ use std::sync::Arc;
 /// Toggle between live trading and dry run
 pub const LIVE_MODE: bool = true; // 🔴 LIVE TRADING ENABLED
 
+/// Strategy that produced an instruction (decides which filters apply,
+/// and which strategy its fill is reported to)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignalKind {
+    Momentum,
+    LatencyArb,
+}
+
+impl SignalKind {
+    /// Name used in `STRATEGIES`, env vars and logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignalKind::Momentum => "momentum",
+            SignalKind::LatencyArb => "latency",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "momentum" => Some(SignalKind::Momentum),
+            "latency" => Some(SignalKind::LatencyArb),
+            _ => None,
+        }
+    }
+}
+
 pub struct TradeInstruction {
     pub symbol: u64, // 15 = 15-min market, 60 = 60-min market
     pub side: u8,    // 0 = Buy YES (up), 1 = Buy NO (down)
     pub price_cents: u64,
     pub size: u64,
+    pub fair_prob: Option<f64>, // Model probability of the bought side winning (entries, if known)
+    pub kind: SignalKind,
+}
+
+/// An instruction's order placed on the venue. GTC orders can rest: their
+/// shares are reported as `Fill`s once the venue matches them.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub kind: SignalKind,
+    pub symbol: u64, // Same encoding as the instruction
+    pub side: u8,
+    pub order_id: String,
+    pub price: f64,
+    pub shares: f64,
+    pub expires_at: DateTime<Utc>, // Expiry of the window traded
+}
+
+/// Shares of an instruction's order the venue matched, reported back to the
+/// strategy that sent it (one per reconciliation that saw new shares)
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub kind: SignalKind,
+    pub symbol: u64, // Same encoding as the instruction
+    pub side: u8,
+    pub price: f64,
+    pub shares: f64,
+    pub cost: f64, // Spent on entries, received on exits, fees included ($)
+    pub fee: f64,
+    pub pnl: f64, // If the entry wins; realized for exits
+    pub remaining: f64, // Shares of the order still resting (0 once filled)
+    pub expires_at: DateTime<Utc>, // Expiry of the window traded
+}
+
+/// An instruction execution refused, or whose order left the book before
+/// it filled, reported back to the strategy that sent it so it can release
+/// what it set aside
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    pub kind: SignalKind,
+    pub symbol: u64, // Same encoding as the instruction
+    pub side: u8,
+    pub filled: f64, // Shares matched before the order left the book
+    pub reason: String,
+}
+
+impl Rejection {
+    fn of(trade: &TradeInstruction, reason: impl Into<String>) -> Self {
+        Self { kind: trade.kind, symbol: trade.symbol, side: trade.side, filled: 0.0, reason: reason.into() }
+    }
+}
+
+/// What became of an instruction
+#[derive(Debug, Clone)]
+pub enum ExecutionReport {
+    Placed(Placement),
+    Filled(Fill),
+    Rejected(Rejection),
+}
+
+impl ExecutionReport {
+    pub fn kind(&self) -> SignalKind {
+        match self {
+            ExecutionReport::Placed(placement) => placement.kind,
+            ExecutionReport::Filled(fill) => fill.kind,
+            ExecutionReport::Rejected(rejection) => rejection.kind,
+        }
+    }
+}
+
+/// The instruction behind an order, kept by the ledger so the order's fills
+/// can be reported to the strategy that sent it
+#[derive(Debug, Clone)]
+pub struct OrderOrigin {
+    pub kind: SignalKind,
+    pub symbol: u64,
+    pub side: u8,
+    pub fee_rate: f64,
+    pub cost_basis: f64, // Average entry price of the position an exit sells
+    pub expires_at: DateTime<Utc>,
+}
+
+impl OrderOrigin {
+    /// Report `shares` matched at `price`, with `remaining` still resting
+    pub fn fill(&self, price: f64, shares: f64, remaining: f64) -> Fill {
+        let fee = crate::prices::taker_fee(price, shares, self.fee_rate);
+        let (cost, pnl) = if self.symbol >= 1000 {
+            (shares * price - fee, crate::prices::exit_pnl(self.cost_basis, price, shares, self.fee_rate))
+        } else {
+            (shares * price + fee, shares - (shares * price + fee))
+        };
+        Fill {
+            kind: self.kind,
+            symbol: self.symbol,
+            side: self.side,
+            price,
+            shares,
+            cost,
+            fee,
+            pnl,
+            remaining,
+            expires_at: self.expires_at,
+        }
+    }
+
+    /// Report the order leaving the book with `filled` of its shares matched
+    pub fn rejection(&self, filled: f64, reason: impl Into<String>) -> Rejection {
+        Rejection { kind: self.kind, symbol: self.symbol, side: self.side, filled, reason: reason.into() }
+    }
+}
+
+/// Exposure follows the venue's reports: an exit releases its position's
+/// once it has sold (fully, or partly before leaving the book), an entry
+/// releases its own if it left the book without a fill
+pub fn settle_exposure(risk_manager: &RiskManager, report: &ExecutionReport) {
+    let (symbol, side, kind, settled) = match report {
+        ExecutionReport::Filled(fill) => (fill.symbol, fill.side, fill.kind, fill.symbol >= 1000 && fill.remaining <= 0.0),
+        ExecutionReport::Rejected(rejection) => (
+            rejection.symbol,
+            rejection.side,
+            rejection.kind,
+            if rejection.symbol >= 1000 { rejection.filled > 0.0 } else { rejection.filled <= 0.0 },
+        ),
+        ExecutionReport::Placed(_) => return,
+    };
+    if settled {
+        let target = Target::of(symbol, side, kind);
+        risk_manager.release_exposure(target.asset, target.market_type(), target.side);
+    }
+}
+
+/// Report an instruction's outcome to the strategy thread
+fn report(reports: &mut Producer<ExecutionReport>, report: ExecutionReport) {
+    if reports.push(report).is_err() {
+        eprintln!("⚠️ Report queue full: strategy will not see this outcome");
+    }
+}
+
+/// What an instruction's symbol and side refer to
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    asset: &'static str,
+    horizon: Option<MarketType>,
+    is_sell: bool,
+    side: Side, // Directional side of the position opened or closed
+    kind: SignalKind,
+}
+
+impl Target {
+    /// Format: asset*100 + market_type (e.g., 160 = BTC 60-min, 215 = ETH 15-min)
+    /// SELL format: asset*100 + market_type + 1000 (e.g., 1160 = BTC 60-min SELL)
+    /// SELL instructions carry the opposite side of the entry.
+    fn decode(trade: &TradeInstruction) -> Self {
+        Self::of(trade.symbol, trade.side, trade.kind)
+    }
+
+    fn of(symbol: u64, side: u8, kind: SignalKind) -> Self {
+        let is_sell = symbol >= 1000;
+        let base_symbol = if is_sell { symbol - 1000 } else { symbol };
+        let asset = match base_symbol / 100 {
+            1 => "BTC",
+            2 => "ETH",
+            3 => "SOL",
+            4 => "XRP",
+            _ => "UNKNOWN",
+        };
+        let side = match (is_sell, side) {
+            (false, 0) | (true, 1) => Side::Yes,
+            _ => Side::No,
+        };
+        Self { asset, horizon: MarketType::from_minutes(base_symbol % 100), is_sell, side, kind }
+    }
+
+    fn market_type(&self) -> &'static str {
+        self.horizon.map(|h| h.label()).unwrap_or("UNKNOWN")
+    }
+}
+
+/// Token of `side` in `market`: "Up"/"Yes" for Yes, "Down"/"No" for No,
+/// else by position (0 = Up/Yes, 1 = Down/No)
+fn side_token(market: &CachedMarket, side: Side) -> Option<&String> {
+    let (outcome, alt, fallback) = match side {
+        Side::Yes => ("Up", "Yes", 0),
+        Side::No => ("Down", "No", 1),
+    };
+    let idx = market
+        .outcomes
+        .iter()
+        .position(|o| o.eq_ignore_ascii_case(outcome))
+        .or_else(|| market.outcomes.iter().position(|o| o.eq_ignore_ascii_case(alt)))
+        .unwrap_or(fallback);
+    market.token_ids.get(idx)
+}
+
+/// Why a live instruction was not placed, with its activity-log entry
+/// (level, details) if it deserves one
+#[derive(Debug)]
+struct Skipped {
+    reason: String,
+    activity: Option<(&'static str, String)>,
+}
+
+impl Skipped {
+    fn quiet(reason: impl Into<String>) -> Self {
+        Self { reason: reason.into(), activity: None }
+    }
+
+    fn logged(level: &'static str, reason: impl Into<String>, details: String) -> Self {
+        Self { reason: reason.into(), activity: Some((level, details)) }
+    }
+}
+
+/// Market and token an instruction trades, plus the position held for exits
+/// (if the ledger knows them). Entries take the live window of the signal's
+/// horizon (never another horizon: the signal would be priced against the
+/// wrong window). Latency arbitrage trades the last minutes of a window by
+/// design, so its entries skip the minimum time to expiry (the strategy
+/// applies its own). Exits sell the held position in its own window, however
+/// close to expiry.
+fn resolve_market(
+    target: Target,
+    markets: &MarketSnapshot,
+    ledger: &SharedLedger,
+    now: DateTime<Utc>,
+) -> Result<(CachedMarket, String, Option<TokenPosition>), Skipped> {
+    let no_market = || Skipped::logged(
+        "warning",
+        "no market for this horizon",
+        format!(r#"{{"asset": "{}", "market": "{}", "reason": "no matching horizon"}}"#, target.asset, target.market_type()),
+    );
+    let horizon = target.horizon.ok_or_else(no_market)?;
+    let asset_markets = markets.get(target.asset).map(Vec::as_slice).unwrap_or_default();
+
+    if target.is_sell {
+        let held = ledger
+            .lock()
+            .expect("ledger lock poisoned")
+            .live_positions(now)
+            .find(|p| p.asset == target.asset && p.market_type == horizon && p.side == target.side && p.shares > 0.0)
+            .cloned();
+        let market = held
+            .as_ref()
+            .and_then(|p| asset_markets.iter().find(|m| m.condition_id == p.condition_id))
+            .or_else(|| live_market(asset_markets, horizon, now))
+            .ok_or_else(no_market)?;
+        return Ok(match held {
+            Some(position) => {
+                let held = TokenPosition {
+                    token_id: position.token_id.clone(),
+                    condition_id: position.condition_id,
+                    size: position.shares,
+                    avg_price: position.avg_price,
+                };
+                (market.clone(), position.token_id, Some(held))
+            }
+            None => {
+                let token_id = side_token(market, target.side).ok_or_else(|| Skipped::quiet("token not found"))?;
+                (market.clone(), token_id.clone(), None)
+            }
+        });
+    }
+
+    let market = match target.kind {
+        SignalKind::LatencyArb => live_market(asset_markets, horizon, now),
+        SignalKind::Momentum => select_live_market(asset_markets, horizon, now),
+    }
+    .ok_or_else(no_market)?;
+    let token_id = side_token(market, target.side).ok_or_else(|| Skipped::quiet("token not found"))?.clone();
+    Ok((market.clone(), token_id, None))
+}
+
+/// Place one instruction on the venue: find its market and token, price it
+/// off the book, apply the entry filters and the exposure cap, send the
+/// order and record it in the ledger, which reports its fills as the
+/// reconciler sees them. Exits sell at the bid, unfiltered.
+async fn execute_live<E: ExchangeClient>(
+    client: &E,
+    trade: &TradeInstruction,
+    target: Target,
+    market_cache: &MarketCache,
+    ledger: &SharedLedger,
+    risk_manager: &RiskManager,
+    now: DateTime<Utc>,
+) -> Result<Placement, Skipped> {
+    let size_f = trade.size as f64;
+    let (asset_name, market_type) = (target.asset, target.market_type());
+
+    // Lock-free snapshot; not held across awaits
+    let (market, token_id, held) = {
+        let snapshot = market_cache.load();
+        println!(" Cache:    v{}", snapshot.version);
+        resolve_market(target, &snapshot, ledger, now).inspect_err(|_| {
+            println!(" Status:   ❌ NO ACTIVE MARKET FOUND IN CACHE for {} {} - refusing", asset_name, market_type);
+        })?
+    };
+    println!(" Market:   {} ({})", market.question_id, market.condition_id);
+    println!(" Expiry:   {} ({}s left)", market.end_date.format("%H:%M:%S"), market.time_to_expiry(now).as_secs());
+    println!(" Token ID: {}...", &token_id[..20.min(token_id.len())]);
+
+    println!(" Status:   ⏳ FETCHING ORDERBOOK PRICE...");
+    let (bid, ask) = client.fetch_orderbook(&token_id).await.ok_or_else(|| {
+        println!(" Status:   ❌ COULD NOT FETCH ORDERBOOK");
+        Skipped::quiet("no orderbook")
+    })?;
+
+    // Entries pay the ask, exits hit the bid, snapped to the market's tick size
+    let tick = market.minimum_tick_size;
+    let entry_price = (if target.is_sell { bid } else { ask } / tick).round() * tick;
+    let fee_rate = market.taker_fee_rate;
+    println!(" Orderbook: bid=${:.3}, ask=${:.3} (tick {}, fee {:.2}%)", bid, ask, tick, fee_rate * 100.0);
+
+    // Entries buy what the budget pays for once fees come out of it; exits
+    // sell everything held (asking the venue if the ledger doesn't know)
+    let (expected_shares, cost_basis) = if target.is_sell {
+        let held = match held {
+            Some(held) => Some(held),
+            None => client
+                .fetch_positions()
+                .await
+                .map_err(|e| Skipped::quiet(format!("could not fetch positions: {}", e)))?
+                .into_iter()
+                .find(|p| p.token_id == token_id),
+        };
+        held.map_or((0.0, 0.0), |p| (market.floor_to_lot(p.size), p.avg_price))
+    } else {
+        (market.floor_to_lot(crate::prices::shares_for_budget(size_f, entry_price, fee_rate)), entry_price)
+    };
+
+    if target.is_sell && expected_shares <= 0.0 {
+        println!(" Status:   ⏭️ SKIPPED - no shares held");
+        return Err(Skipped::quiet("no shares held"));
+    }
+    if !target.is_sell {
+        if expected_shares < market.minimum_order_size {
+            println!(" Filters:  ⏭️ SKIPPED - below minimum order size ({} shares)", market.minimum_order_size);
+            return Err(Skipped::quiet("below minimum order size"));
+        }
+
+        // Apply value filters, plus the fair-value edge when the
+        // strategy priced the window
+        let filters = match (trade.kind, trade.fair_prob) {
+            (SignalKind::LatencyArb, Some(fair)) => {
+                println!(" Fair:     {:.1}% (edge {:.1}¢)", fair * 100.0,
+                    crate::prices::calculate_edge(entry_price, fair, fee_rate) * 100.0);
+                crate::prices::passes_latency_filters(entry_price, bid, ask, fair, fee_rate)
+            }
+            (_, fair_prob) => crate::prices::passes_value_filters(entry_price, bid, ask, fee_rate)
+                .and_then(|passed| match fair_prob {
+                    Some(fair) => crate::prices::passes_edge_filter(entry_price, fair, fee_rate).map(|edge| {
+                        println!(" Fair:     {:.1}% (edge {:.1}¢)", fair * 100.0, edge * 100.0);
+                        passed
+                    }),
+                    None => Ok(passed),
+                }),
+        };
+        let (upside, spread) = filters.map_err(|reason| {
+            println!(" Filters:  ⏭️ SKIPPED - {}", reason);
+            println!("           price=${:.3}, max=${:.2}", entry_price, crate::prices::MAX_ENTRY_PRICE);
+            Skipped::logged(
+                "info",
+                reason,
+                format!(r#"{{"asset": "{}", "market": "{}", "reason": "{}", "price": {:.3}, "bid": {:.3}, "ask": {:.3}}}"#,
+                    asset_name, market_type, reason, entry_price, bid, ask),
+            )
+        })?;
+        println!(" Filters:  ✅ PASSED (upside={:.1}%, spread={:.1}%)", upside * 100.0, spread * 100.0);
+
+        // Correlated exposure check (entries only)
+        if let Err(reason) = risk_manager.try_add_exposure(asset_name, market_type, target.side, size_f, market.end_date) {
+            println!(" Risk:     ⏭️ SKIPPED - {}", reason);
+            return Err(Skipped::logged(
+                "warning",
+                reason,
+                format!(r#"{{"asset": "{}", "market": "{}", "net_exposure": {:.2}, "cap": {:.2}}}"#,
+                    asset_name, market_type, risk_manager.get_net_exposure(), risk_manager.get_correlated_exposure_cap()),
+            ));
+        }
+    }
+
+    println!(" Status:   ⏳ PLACING ORDER @ ${:.3}...", entry_price);
+    let order_side = if target.is_sell { OrderSide::SELL } else { OrderSide::BUY };
+    let order_id = match client.place_order(&market, &token_id, order_side, expected_shares, entry_price).await {
+        Ok(id) => id,
+        Err(e) => {
+            println!(" Status:   ❌ ORDER FAILED: {}", e);
+            if !target.is_sell {
+                risk_manager.release_exposure(asset_name, market_type, target.side);
+            }
+            return Err(Skipped::quiet(format!("order failed: {}", e)));
+        }
+    };
+
+    println!(" Status:   ✅ ORDER PLACED: {}", order_id);
+    println!(" Order:    {:.2} shares @ ${:.3}{}", expected_shares, entry_price,
+        if target.is_sell { format!(" (entry ${:.3})", cost_basis) } else { String::new() });
+    // GTC orders can rest: the reconciler moves shares into positions and
+    // reports them as they fill (and releases an exit's exposure once sold)
+    ledger.lock().expect("ledger lock poisoned").record_order(OpenOrder {
+        order_id: order_id.clone(),
+        token_id: token_id.clone(),
+        side: order_side,
+        price: entry_price,
+        size: expected_shares,
+        size_matched: 0.0,
+    }, OrderOrigin {
+        kind: trade.kind,
+        symbol: trade.symbol,
+        side: trade.side,
+        fee_rate,
+        cost_basis,
+        expires_at: market.end_date,
+    }, Utc::now());
+
+    Ok(Placement {
+        kind: trade.kind,
+        symbol: trade.symbol,
+        side: trade.side,
+        order_id,
+        price: entry_price,
+        shares: expected_shares,
+        expires_at: market.end_date,
+    })
+}
+
+/// Place one instruction as the live engine does, returning its placement
+/// or why it was skipped (for tests driving strategies against execution)
+#[cfg(test)]
+pub(crate) async fn execute_instruction<E: ExchangeClient>(
+    client: &E,
+    trade: &TradeInstruction,
+    market_cache: &MarketCache,
+    ledger: &SharedLedger,
+    risk_manager: &RiskManager,
+    now: DateTime<Utc>,
+) -> Result<Placement, String> {
+    execute_live(client, trade, Target::decode(trade), market_cache, ledger, risk_manager, now)
+        .await
+        .map_err(|skipped| skipped.reason)
+}
+
+/// Handles the execution engine shares with the rest of the bot
+pub struct ExecutionContext<E> {
+    /// Venue client; trades are simulated without one
+    pub client: Option<Arc<E>>,
+    pub market_cache: MarketCache,
+    pub ledger: SharedLedger,
+    pub risk_manager: Arc<RiskManager>,
+    /// Fills and rejections back to the strategy thread
+    pub reports: Producer<ExecutionReport>,
+    pub clock: SharedClock,
 }
 
-pub async fn run_execution(
+pub async fn run_execution<E: ExchangeClient>(
     mut consumer: Consumer<TradeInstruction>,
+    ctx: ExecutionContext<E>,
     db_logger: Arc<DbLogger>,
     db_pool: PgPool,
-    risk_manager: Arc<RiskManager>,
-    poly_client: Option<Arc<PolymarketClient>>,
-    market_cache: crate::polymarket::MarketCache,
 ) {
+    let ExecutionContext { client: poly_client, market_cache, ledger, risk_manager, mut reports, clock } = ctx;
     println!("Starting Execution Engine...");
+    // LIVE_MODE only takes effect with a working API client; otherwise trades
+    // are simulated and reported as such
+    let live_mode = LIVE_MODE && poly_client.is_some();
+    if LIVE_MODE && !live_mode {
+        println!("⚠️ LIVE_MODE is set but no Polymarket client is available");
+    }
     println!(
         "Mode: {}",
-        if LIVE_MODE {
+        if live_mode {
             "🔴 LIVE TRADING"
         } else {
             "🟢 DRY RUN"
@@ -48,12 +533,12 @@ pub async fn run_execution(
         "system",
         &format!(
             "Execution Engine started: {} mode, {} tier",
-            if LIVE_MODE { "LIVE" } else { "DRY RUN" },
+            if live_mode { "LIVE" } else { "DRY RUN" },
             risk_manager.current_tier().name()
         ),
         Some(format!(
             r#"{{"live_mode": {}, "tier": "{}", "trade_size": {}, "max_exposure": {:.2}}}"#,
-            LIVE_MODE,
+            live_mode,
             risk_manager.current_tier().name(),
             risk_manager.get_trade_size(),
             risk_manager.get_max_exposure()
@@ -70,47 +555,34 @@ pub async fn run_execution(
 
     // PnL Tracking - use starting balance from risk manager
     let starting_balance = 58.36;
-    let mut total_balance: f64 = starting_balance;
-    let mut _total_profit: f64 = 0.0;
+    let mut session = Session {
+        total_balance: starting_balance,
+        total_profit: 0.0,
+        total_fees: 0.0,
+        fill_count: 0,
+        last_logged_balance: starting_balance,
+    };
     let mut trade_count: u64 = 0;
-    let mut last_logged_balance: f64 = starting_balance;
 
     loop {
+        // Fills and rejections the reconciler saw on the venue since the last pass
+        let settled = ledger.lock().expect("ledger lock poisoned").take_reports();
+        for settled in settled {
+            if let ExecutionReport::Filled(fill) = &settled {
+                log_fill(fill, &mut session, &db_logger, &db_pool, &risk_manager).await;
+            }
+            report(&mut reports, settled);
+        }
+
         if let Ok(trade) = consumer.pop() {
             trade_count += 1;
             let price_f = trade.price_cents as f64 / 100.0;
             let size_f = trade.size as f64;
 
-            // Calculate Projected Profit
-            let shares = size_f / price_f;
-            let payout = shares * 1.00;
-            let profit = payout - size_f;
-
-            // Decode asset and market type from symbol
-            // Format: asset*100 + market_type (e.g., 160 = BTC 60-min, 215 = ETH 15-min)
-            // SELL format: asset*100 + market_type + 1000 (e.g., 1160 = BTC 60-min SELL)
-            let is_sell = trade.symbol >= 1000;
-            let base_symbol = if is_sell {
-                trade.symbol - 1000
-            } else {
-                trade.symbol
-            };
-            let asset_id = base_symbol / 100;
-            let market_mins = base_symbol % 100;
-
-            let asset_name = match asset_id {
-                1 => "BTC",
-                2 => "ETH",
-                3 => "SOL",
-                4 => "XRP",
-                _ => "UNKNOWN",
-            };
-
-            let market_type = match market_mins {
-                15 => "15-MIN",
-                60 => "60-MIN",
-                _ => "UNKNOWN",
-            };
+            let target = Target::decode(&trade);
+            let (is_sell, asset_name, market_type, exposure_side) =
+                (target.is_sell, target.asset, target.market_type(), target.side);
+            let market_mins = trade.symbol % 100;
 
             let action = if is_sell {
                 "🔴 STOP-LOSS EXIT"
@@ -134,7 +606,7 @@ pub async fn run_execution(
             println!("\n══════════════════════════════════════════════════");
             println!(
                 " {} TRADE #{} - {}",
-                if LIVE_MODE {
+                if live_mode {
                     "🔴 LIVE"
                 } else {
                     "🟢 DRY RUN"
@@ -146,156 +618,61 @@ pub async fn run_execution(
             println!(" Side:     {}", side_str);
             println!(" Budget:   ${:.2}", size_f);
 
+            // Entries are blocked while trading is halted; exits still go through
+            if !is_sell {
+                if let Some(reason) = risk_manager.halt_reason() {
+                    println!(" Risk:     🛑 HALTED - {}", reason);
+                    println!("══════════════════════════════════════════════════\n");
+                    report(&mut reports, ExecutionReport::Rejected(Rejection::of(&trade, reason)));
+                    continue;
+                }
+            }
+
             // Execute trade (live or simulated)
-            let mut _order_success = false;
-            let mut _order_id: Option<String> = None;
-
-            if LIVE_MODE {
-                if let Some(ref client) = poly_client {
-                    println!(" Status:   ⏳ LOOKING UP MARKET IN CACHE...");
-
-                    // Lookup market in cache
-                    let mut found_market = None;
-                    if let Ok(cache) = market_cache.read() {
-                        if let Some(markets) = cache.get(asset_name) {
-                            // Try to find exact market type first (15-MIN or 60-MIN)
-                            found_market = markets
-                                .iter()
-                                .find(|m| m.market_type == market_type)
-                                .cloned();
-                            
-                            // Fallback: If no 60-MIN or 15-MIN market found, use DAILY
-                            // (Polymarket may not have hourly crypto markets available)
-                            if found_market.is_none() {
-                                found_market = markets
-                                    .iter()
-                                    .find(|m| m.market_type == "DAILY")
-                                    .cloned();
-                                if found_market.is_some() {
-                                    println!(" Fallback: Using DAILY market (no {} available)", market_type);
-                                }
-                            }
-                        }
+            if live_mode {
+                let outcome = match poly_client {
+                    Some(ref client) => {
+                        println!(" Status:   ⏳ LOOKING UP MARKET IN CACHE...");
+                        let now = clock.now(Venue::Polymarket);
+                        execute_live(client.as_ref(), &trade, target, &market_cache, &ledger, &risk_manager, now).await
                     }
-
-                    if let Some(market) = found_market {
-                        println!(
-                            " Market:   {} ({})",
-                            market.question_id, market.condition_id
-                        );
-
-                        // Find the correct token ID
-                        // For crypto markets: side 0 = Up (price going up), side 1 = Down
-                        // Some markets use "Yes"/"No" instead of "Up"/"Down"
-                        let target_outcome = if trade.side == 0 { "Up" } else { "Down" };
-                        let alt_outcome = if trade.side == 0 { "Yes" } else { "No" };
-                        
-                        // Debug: show available outcomes
-                        println!(" Outcomes: {:?}", market.outcomes);
-                        
-                        // Try primary outcome first, then fallback
-                        let token_index = market
-                            .outcomes
-                            .iter()
-                            .position(|o| o.eq_ignore_ascii_case(target_outcome))
-                            .or_else(|| market.outcomes.iter().position(|o| o.eq_ignore_ascii_case(alt_outcome)))
-                            .or_else(|| {
-                                // Last resort: use index directly (0 for Up/Yes, 1 for Down/No)
-                                if trade.side == 0 && market.outcomes.len() >= 1 { Some(0) }
-                                else if trade.side == 1 && market.outcomes.len() >= 2 { Some(1) }
-                                else { None }
-                            });
-
-                        if let Some(idx) = token_index {
-                            let token_id = &market.token_ids[idx];
-                            println!(" Token ID: {}...", &token_id[..20.min(token_id.len())]);
-
-                            // ============================================
-                            // VALUE FILTERING: Check price before trading
-                            // ============================================
-                            println!(" Status:   ⏳ FETCHING ORDERBOOK PRICE...");
-                            
-                            let orderbook = crate::prices::fetch_orderbook(token_id).await;
-                            
-                            if let Some((bid, ask)) = orderbook {
-                                let entry_price = ask; // We pay the ask
-                                println!(" Orderbook: bid=${:.3}, ask=${:.3}", bid, ask);
-                                
-                                // Apply value filters
-                                match crate::prices::passes_value_filters(entry_price, bid, ask) {
-                                    Ok((upside, spread)) => {
-                                        println!(" Filters:  ✅ PASSED (upside={:.1}%, spread={:.1}%)", 
-                                            upside * 100.0, spread * 100.0);
-                                        
-                                        println!(" Status:   ⏳ PLACING ORDER @ ${:.3}...", entry_price);
-                                        
-                                        // Place the order with actual orderbook price
-                                        match client
-                                            .place_order(
-                                                token_id,
-                                                if is_sell {
-                                                    crate::polymarket::OrderSide::SELL
-                                                } else {
-                                                    crate::polymarket::OrderSide::BUY
-                                                },
-                                                size_f,
-                                                entry_price, // Use actual orderbook price
-                                            )
-                                            .await
-                                        {
-                                            Ok(id) => {
-                                                _order_success = true;
-                                                _order_id = Some(id.clone());
-                                                // Recalculate profit with actual price
-                                                let actual_shares = size_f / entry_price;
-                                                let actual_profit = actual_shares * 1.0 - size_f;
-                                                println!(" Status:   ✅ ORDER PLACED: {}", id);
-                                                println!(" Actual:   {} shares @ ${:.3}, profit=${:.2} if win", 
-                                                    actual_shares, entry_price, actual_profit);
-                                                total_balance += actual_profit;
-                                                _total_profit += actual_profit;
-                                            }
-                                            Err(e) => {
-                                                println!(" Status:   ❌ ORDER FAILED: {}", e);
-                                            }
-                                        }
-                                    }
-                                    Err(reason) => {
-                                        println!(" Filters:  ⏭️ SKIPPED - {}", reason);
-                                        println!("           price=${:.3}, max=${:.2}", 
-                                            entry_price, crate::prices::MAX_ENTRY_PRICE);
-                                        
-                                        // Log skipped trade to activity log
-                                        db_logger.log_activity(
-                                            "info",
-                                            "filter",
-                                            &format!("{} {} skipped: {}", asset_name, market_type, reason),
-                                            Some(format!(r#"{{"asset": "{}", "market": "{}", "reason": "{}", "price": {:.3}, "bid": {:.3}, "ask": {:.3}}}"#,
-                                                asset_name, market_type, reason, entry_price, bid, ask)),
-                                        );
-                                    }
-                                }
-                            } else {
-                                println!(" Status:   ❌ COULD NOT FETCH ORDERBOOK");
-                            }
-                        } else {
-                            println!(
-                                " Status:   ❌ TOKEN NOT FOUND for outcome: {}",
-                                target_outcome
+                    None => {
+                        println!(" Status:   ❌ NO API CLIENT - Skipped");
+                        Err(Skipped::quiet("no API client"))
+                    }
+                };
+                println!("══════════════════════════════════════════════════\n");
+
+                // Placed orders are reported filled once the venue matches them
+                match outcome {
+                    Ok(placement) => report(&mut reports, ExecutionReport::Placed(placement)),
+                    Err(skipped) => {
+                        if let Some((level, details)) = skipped.activity {
+                            db_logger.log_activity(
+                                level,
+                                "filter",
+                                &format!("{} {} skipped: {}", asset_name, market_type, skipped.reason),
+                                Some(details),
                             );
                         }
-                    } else {
-                        println!(
-                            " Status:   ❌ NO ACTIVE MARKET FOUND IN CACHE for {} {}",
-                            asset_name, market_type
-                        );
-                        // Optional: Trigger immediate discovery if not found?
+                        report(&mut reports, ExecutionReport::Rejected(Rejection::of(&trade, skipped.reason)));
                     }
-                } else {
-                    println!(" Status:   ❌ NO API CLIENT - Skipped");
                 }
             } else {
-                // Dry run - just simulate
+                // Dry run - still enforce the correlated exposure cap
+                let expires_at = clock.now(Venue::Polymarket) + chrono::Duration::minutes(market_mins as i64);
+                if is_sell {
+                    risk_manager.release_exposure(asset_name, market_type, exposure_side);
+                } else if let Err(reason) = risk_manager.try_add_exposure(
+                    asset_name, market_type, exposure_side, size_f, expires_at,
+                ) {
+                    println!(" Risk:     ⏭️ SKIPPED - {}", reason);
+                    println!("══════════════════════════════════════════════════\n");
+                    report(&mut reports, ExecutionReport::Rejected(Rejection::of(&trade, reason)));
+                    continue;
+                }
+
+                // Dry run - just simulate: filled in full at the signal's price
                 let message = format!(
                     "Buy {} at {}",
                     if trade.side == 0 { "YES" } else { "NO" },
@@ -304,86 +681,119 @@ pub async fn run_execution(
                 .into_bytes();
                 let signature: Signature = signer.sign_message(&message).await.expect("Signing failed");
 
-                _order_success = true;
-                total_balance += profit;
-                _total_profit += profit;
+                let shares = size_f / price_f;
+                let fill = Fill {
+                    kind: trade.kind,
+                    symbol: trade.symbol,
+                    side: trade.side,
+                    price: price_f,
+                    shares,
+                    cost: size_f,
+                    fee: 0.0,
+                    pnl: shares * 1.00 - size_f,
+                    remaining: 0.0,
+                    expires_at,
+                };
 
                 println!(" ──────────────────────────────────────────────────");
-                println!(" Projected Profit: ${:.2} (if win)", profit);
-                println!(" Total Balance:    ${:.2}", total_balance);
-                println!(" Total Profit:     ${:.2}", _total_profit);
+                println!(" Projected Profit: ${:.2} (if win)", fill.pnl);
+                println!(" Total Balance:    ${:.2}", session.total_balance + fill.pnl);
+                println!(" Total Profit:     ${:.2}", session.total_profit + fill.pnl);
                 println!(" Signature: 0x{}...", &format!("{:?}", signature)[..40]);
+                println!("══════════════════════════════════════════════════\n");
+
+                log_fill(&fill, &mut session, &db_logger, &db_pool, &risk_manager).await;
+                report(&mut reports, ExecutionReport::Filled(fill));
             }
+        }
 
-            println!("══════════════════════════════════════════════════\n");
-
-            // Only log and track P&L for successfully executed trades
-            if _order_success {
-                // Log trade to database
-                let ticker = format!("{}-{}", asset_name, market_type);
-                let side_db = if is_sell {
-                    if trade.side == 0 {
-                        "sell_no"
-                    } else {
-                        "sell_yes"
-                    }
-                } else {
-                    if trade.side == 0 {
-                        "buy_yes"
-                    } else {
-                        "buy_no"
-                    }
-                };
+        tokio::task::yield_now().await;
+    }
+}
 
-                db_logger.log_trade(TradeLogMsg {
-                    ticker: ticker.clone(),
-                    side: side_db.to_string(),
-                    price: price_f,
-                    size: size_f,
-                    value: price_f * size_f,
-                    latency_ms: None, // TODO: Calculate tick-to-trade latency
-                    pnl: Some(profit),
-                });
-
-                // Log trade execution with PnL to activity log
-                let pnl_pct = (profit / size_f) * 100.0;
-
-                // Update risk tier based on P&L
-                let old_tier = risk_manager.current_tier();
-                let new_tier = risk_manager.update_pnl(profit);
-
-                // Log tier transition if changed
-                if old_tier != new_tier {
-                    db_logger.log_activity("info", "system", 
-                        &format!("Risk tier changed: {} → {}", old_tier.name(), new_tier.name()),
-                        Some(format!(r#"{{"old_tier": "{}", "new_tier": "{}", "session_pnl": {:.2}, "new_trade_size": {}}}"#,
-                            old_tier.name(), new_tier.name(), risk_manager.get_session_pnl(), new_tier.trade_size())));
-                }
+/// P&L of the session so far, from the fills reported
+struct Session {
+    total_balance: f64,
+    total_profit: f64,
+    total_fees: f64,
+    fill_count: u64,
+    last_logged_balance: f64,
+}
 
-                db_logger.log_activity(
-                    if profit >= 0.0 { "success" } else { "warning" }, 
-                    "trade", 
-                    &format!("{}: {} | PnL: ${:.2} ({:.1}%) | Balance: ${:.2} | Tier: {}", 
-                        ticker, side_db, profit, pnl_pct, total_balance, new_tier.name()),
-                    Some(format!(r#"{{"ticker": "{}", "side": "{}", "price": {:.2}, "size": {:.2}, "pnl": {:.2}, "pnl_pct": {:.2}, "total_balance": {:.2}, "total_profit": {:.2}, "trade_count": {}, "tier": "{}", "session_pnl": {:.2}}}"#,
-                        ticker, side_db, price_f, size_f, profit, pnl_pct, total_balance, _total_profit, trade_count, new_tier.name(), risk_manager.get_session_pnl())));
-
-                // Log wallet balance if changed significantly
-                if (total_balance - last_logged_balance).abs() > 0.01 {
-                    if let Err(e) = insert_wallet_balance(&db_pool, total_balance, "USDC").await {
-                        eprintln!("[WALLET] DB error: {:?}", e);
-                    }
-                    last_logged_balance = total_balance;
-                }
-            }
+/// Record a fill: the trade log (what was traded, not what the signal asked
+/// for), the risk tier, the activity log and the wallet balance
+async fn log_fill(fill: &Fill, session: &mut Session, db_logger: &DbLogger, db_pool: &PgPool, risk_manager: &RiskManager) {
+    session.fill_count += 1;
+    session.total_balance += fill.pnl;
+    session.total_profit += fill.pnl;
+    session.total_fees += fill.fee;
+
+    let target = Target::of(fill.symbol, fill.side, fill.kind);
+    let ticker = format!("{}-{}", target.asset, target.market_type());
+    let side_db = if target.is_sell {
+        if fill.side == 0 {
+            "sell_no"
+        } else {
+            "sell_yes"
         }
+    } else {
+        if fill.side == 0 {
+            "buy_yes"
+        } else {
+            "buy_no"
+        }
+    };
+    println!("[EXEC] Fill: {} {} {:.2} shares @ ${:.3}, fee=${:.4}, pnl=${:.2}{}",
+        ticker, side_db, fill.shares, fill.price, fill.fee, fill.pnl,
+        if fill.remaining > 0.0 { format!(" ({:.2} still resting)", fill.remaining) } else { String::new() });
+
+    // `size` is in dollars: the reconciler reads shares back as size / price
+    let notional = fill.shares * fill.price;
+    db_logger.log_trade(TradeLogMsg {
+        ticker: ticker.clone(),
+        side: side_db.to_string(),
+        price: fill.price,
+        size: notional,
+        value: notional,
+        latency_ms: None, // TODO: Calculate tick-to-trade latency
+        pnl: Some(fill.pnl),
+        fee: Some(fill.fee),
+    });
+
+    // Log trade execution with PnL to activity log
+    let pnl_pct = if fill.cost > 0.0 { (fill.pnl / fill.cost) * 100.0 } else { 0.0 };
+
+    // Update risk tier based on P&L
+    let old_tier = risk_manager.current_tier();
+    let new_tier = risk_manager.update_pnl(fill.pnl);
+
+    // Log tier transition if changed
+    if old_tier != new_tier {
+        db_logger.log_activity("info", "system", 
+            &format!("Risk tier changed: {} → {}", old_tier.name(), new_tier.name()),
+            Some(format!(r#"{{"old_tier": "{}", "new_tier": "{}", "session_pnl": {:.2}, "new_trade_size": {}}}"#,
+                old_tier.name(), new_tier.name(), risk_manager.get_session_pnl(), new_tier.trade_size())));
+    }
 
-        tokio::task::yield_now().await;
+    db_logger.log_activity(
+        if fill.pnl >= 0.0 { "success" } else { "warning" }, 
+        "trade", 
+        &format!("{}: {} | PnL: ${:.2} ({:.1}%) | Fee: ${:.4} | Balance: ${:.2} | Tier: {}", 
+            ticker, side_db, fill.pnl, pnl_pct, fill.fee, session.total_balance, new_tier.name()),
+        Some(format!(r#"{{"ticker": "{}", "side": "{}", "price": {:.3}, "shares": {:.2}, "size": {:.2}, "pnl": {:.2}, "pnl_pct": {:.2}, "fee": {:.4}, "total_fees": {:.4}, "total_balance": {:.2}, "total_profit": {:.2}, "trade_count": {}, "tier": "{}", "session_pnl": {:.2}}}"#,
+            ticker, side_db, fill.price, fill.shares, notional, fill.pnl, pnl_pct, fill.fee, session.total_fees, session.total_balance, session.total_profit, session.fill_count, new_tier.name(), risk_manager.get_session_pnl())));
+
+    // Log wallet balance if changed significantly
+    if (session.total_balance - session.last_logged_balance).abs() > 0.01 {
+        if let Err(e) = insert_wallet_balance(db_pool, session.total_balance, "USDC").await {
+            eprintln!("[WALLET] DB error: {:?}", e);
+        }
+        session.last_logged_balance = session.total_balance;
     }
 }
 
 /// Fallback: Run execution without database logging
-pub async fn run_execution_no_db(mut consumer: Consumer<TradeInstruction>) {
+pub async fn run_execution_no_db(mut consumer: Consumer<TradeInstruction>, mut reports: Producer<ExecutionReport>) {
     println!("Starting Execution Engine (NO DB MODE)...");
 
     let private_key =
@@ -421,6 +831,18 @@ pub async fn run_execution_no_db(mut consumer: Consumer<TradeInstruction>) {
 
             total_balance += profit;
             _total_profit += profit;
+            report(&mut reports, ExecutionReport::Filled(Fill {
+                kind: trade.kind,
+                symbol: trade.symbol,
+                side: trade.side,
+                price: price_f,
+                shares,
+                cost: size_f,
+                fee: 0.0,
+                pnl: profit,
+                remaining: 0.0,
+                expires_at: Utc::now() + chrono::Duration::minutes((trade.symbol % 100) as i64),
+            }));
 
             println!(
                 "[EXEC] Trade #{}: {} BTC, balance=${:.2}",
@@ -431,3 +853,110 @@ pub async fn run_execution_no_db(mut consumer: Consumer<TradeInstruction>) {
         tokio::task::yield_now().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchange::{SimulatedExchange, TokenPosition};
+    use crate::polymarket::{test_market, MarketStore};
+    use std::collections::HashMap;
+
+    fn cache_with(market: CachedMarket) -> MarketCache {
+        let cache: MarketCache = Arc::new(MarketStore::new());
+        cache.publish(HashMap::from([(market.asset.clone(), vec![market])]));
+        cache
+    }
+
+    fn instruction(symbol: u64, side: u8, size: u64) -> TradeInstruction {
+        TradeInstruction { symbol, side, price_cents: 50, size, fair_prob: None, kind: SignalKind::Momentum }
+    }
+
+    #[tokio::test]
+    async fn test_exit_30s_before_expiry() {
+        let now = Utc::now();
+        let mut market = test_market("BTC", now + chrono::Duration::seconds(30));
+        market.taker_fee_rate = 0.02;
+        let cache = cache_with(market.clone());
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("111", 0.30, 0.32);
+        sim.set_book("222", 0.68, 0.70);
+        sim.set_position(TokenPosition { token_id: "111".into(), condition_id: "0xabc".into(), size: 10.0, avg_price: 0.5 });
+        let ledger = SharedLedger::default();
+        ledger.lock().unwrap().record_fill(&market, "111", OrderSide::BUY, 10.0, 0.5, now - chrono::Duration::minutes(5));
+        let risk_manager = RiskManager::new(100.0);
+
+        // Too late to enter the window...
+        let entry = instruction(115, 0, 5);
+        assert!(execute_live(&sim, &entry, Target::decode(&entry), &cache, &ledger, &risk_manager, now).await.is_err());
+
+        // ...but the held Up position is still sold, at the bid (SELL carries the opposite side)
+        let exit = instruction(1115, 1, 5);
+        let placement = execute_live(&sim, &exit, Target::decode(&exit), &cache, &ledger, &risk_manager, now).await.unwrap();
+        assert!((placement.price - 0.30).abs() < 1e-9);
+        assert!((placement.shares - 10.0).abs() < 1e-9);
+        assert!(sim.fetch_positions().await.unwrap().is_empty());
+
+        // Reported once the reconciler sees it matched. Realized: 10 × (0.30 - 0.50),
+        // less 1¢ entry and 0.6¢ exit fees per share
+        let venue = crate::ledger::fetch_venue_ledger(&sim, &cache, None).await.unwrap();
+        let reports = ledger.lock().unwrap().apply_fills(&venue, &cache.load().markets, now);
+        let [ExecutionReport::Filled(fill)] = reports.as_slice() else { panic!("expected one fill: {:?}", reports) };
+        assert_eq!((fill.symbol, fill.remaining), (1115, 0.0));
+        assert!((fill.fee - 0.06).abs() < 1e-9);
+        assert!((fill.pnl + 2.16).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_exposure_settles_on_venue_reports() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let market_end = market.end_date;
+        let cache = cache_with(market);
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("111", 0.29, 0.30);
+        let ledger = SharedLedger::default();
+        let risk_manager = RiskManager::new(100.0);
+
+        let entry = instruction(115, 0, 5);
+        execute_live(&sim, &entry, Target::decode(&entry), &cache, &ledger, &risk_manager, now).await.unwrap();
+        assert!((risk_manager.get_net_exposure() - 5.0).abs() < 1e-9);
+
+        // A partial exit doesn't close the position; a finished one does
+        let origin = |symbol, side| OrderOrigin {
+            kind: SignalKind::Momentum, symbol, side, fee_rate: 0.0, cost_basis: 0.30, expires_at: market_end,
+        };
+        let exit = origin(1115, 1);
+        settle_exposure(&risk_manager, &ExecutionReport::Filled(exit.fill(0.40, 5.0, 11.66)));
+        assert!((risk_manager.get_net_exposure() - 5.0).abs() < 1e-9);
+        settle_exposure(&risk_manager, &ExecutionReport::Filled(exit.fill(0.40, 11.66, 0.0)));
+        assert_eq!(risk_manager.get_net_exposure(), 0.0);
+
+        // An entry that left the book unfilled gives its exposure back
+        execute_live(&sim, &entry, Target::decode(&entry), &cache, &ledger, &risk_manager, now).await.unwrap();
+        settle_exposure(&risk_manager, &ExecutionReport::Rejected(origin(115, 0).rejection(0.0, "cancelled")));
+        assert_eq!(risk_manager.get_net_exposure(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_orders_are_sized_in_shares() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let cache = cache_with(market.clone());
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("111", 0.29, 0.30);
+        let ledger = SharedLedger::default();
+        let risk_manager = RiskManager::new(100.0);
+
+        // $5 at 0.30 is 16.666... shares: 16.66 go on the order, not 5
+        let entry = instruction(115, 0, 5);
+        let placement = execute_live(&sim, &entry, Target::decode(&entry), &cache, &ledger, &risk_manager, now).await.unwrap();
+        assert!((placement.shares - 16.66).abs() < 1e-9);
+        let positions = sim.fetch_positions().await.unwrap();
+        assert!((positions[0].size - 16.66).abs() < 1e-9);
+
+        // The ledger holds the order until the reconciler sees it fill
+        let ledger = ledger.lock().unwrap();
+        assert_eq!((ledger.open_orders.len(), ledger.positions.len()), (1, 0));
+        assert!((ledger.open_orders[0].size - 16.66).abs() < 1e-9);
+    }
+}
@@ -1,22 +1,141 @@
-use futures_util::StreamExt;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
 use simd_json::prelude::*;
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 use url::Url;
 
 use rtrb::Producer;
 
+use crate::binance_book::{BookSync, DepthDiff, DepthSnapshot, DepthSummary, LocalBook};
+use crate::exchange::ExchangeResult;
+use crate::polymarket::MarketCache;
+use crate::spot_feeds::VenueTick;
+use crate::timesync::{SharedClock, Venue};
+
 pub struct MarketUpdate {
     pub symbol: u64,
     pub price: u64,
+    pub quantity: f64, // Base asset units
+    pub ts: u64,
+}
+
+/// Top of the Polymarket book for one outcome token
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookUpdate {
+    pub token_id: String,
+    pub best_bid: f64,
+    pub best_ask: f64,
+    pub ts: u64, // Receipt time on Binance's clock (ms), comparable with MarketUpdate::ts
+}
+
+/// Binance kline interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BarInterval {
+    OneMinute,
+    OneHour,
+}
+
+impl BarInterval {
+    /// Binance interval code (`1m`, `1h`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            BarInterval::OneMinute => "1m",
+            BarInterval::OneHour => "1h",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "1m" => Some(BarInterval::OneMinute),
+            "1h" => Some(BarInterval::OneHour),
+            _ => None,
+        }
+    }
+}
+
+/// Binance kline (candle), sent on every update of the current bar and
+/// once more with `closed` set when the bar ends. Prices in dollars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub symbol: u64,
+    pub interval: BarInterval,
+    pub open_time: u64, // Bar start (ms), a window start for aligned bars
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,       // Base asset volume
+    pub quote_volume: f64, // USDT volume
+    pub closed: bool,
     pub ts: u64,
 }
 
+impl Bar {
+    /// Volume-weighted average price of the bar so far
+    pub fn vwap(&self) -> Option<f64> {
+        (self.volume > 0.0).then(|| self.quote_volume / self.volume)
+    }
+}
+
+/// Binance aggregated trade (fills of one taker order at one price)
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)] // Read by strategies through `on_agg_trade`
+pub struct AggTrade {
+    pub symbol: u64,
+    pub price: u64, // Cents, like MarketUpdate::price
+    pub quantity: f64,
+    pub buyer_is_maker: bool, // Taker sold
+    pub ts: u64,
+}
+
+/// Binance best bid/offer (`@bookTicker`), in dollars / base units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    pub symbol: u64,
+    pub bid: f64,
+    pub bid_qty: f64,
+    pub ask: f64,
+    pub ask_qty: f64,
+    pub ts: u64, // Receipt time on Binance's clock (ms); the stream has no timestamp
+}
+
+impl Quote {
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    /// Size-weighted mid (the plain mid when both sizes are empty)
+    pub fn microprice(&self) -> f64 {
+        crate::binance_book::microprice(self.bid, self.bid_qty, self.ask, self.ask_qty).unwrap_or_else(|| self.mid())
+    }
+
+    /// Top-of-book imbalance in [-1, 1]
+    pub fn imbalance(&self) -> Option<f64> {
+        crate::binance_book::imbalance(self.bid_qty, self.ask_qty)
+    }
+}
+
+/// Everything the strategy thread consumes
+pub enum MarketEvent {
+    Spot(MarketUpdate),    // Binance trade
+    Book(BookUpdate),      // Polymarket top-of-book change
+    Bar(Bar),              // Binance kline update
+    AggTrade(AggTrade),    // Binance aggregated trade
+    Quote(Quote),          // Binance best bid/offer
+    Depth(DepthSummary),   // Binance local L2 book after a diff
+    VenuePrice(VenueTick), // Another venue's spot price (composite index)
+}
+
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::time::{sleep, Duration};
 use tokio_tungstenite::WebSocketStream;
 use tokio_tungstenite::MaybeTlsStream;
 use tokio::net::TcpStream;
 
 type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type PolySink = SplitSink<WsStream, Message>;
+type PolyRead = SplitStream<WsStream>;
 
 async fn connect_with_retry(url: &str) -> WsStream {
     loop {
@@ -45,104 +164,472 @@ fn get_symbol_id(symbol_str: Option<&str>) -> Option<u64> {
     }
 }
 
-pub async fn run_ingestion(mut producer: Producer<MarketUpdate>) {
+/// Streams always subscribed per symbol (the strategies' price feed)
+const BASE_STREAM: &str = "trade";
+/// Optional per-symbol streams, enabled through `BINANCE_EXTRA_STREAMS`:
+/// (config name, stream name)
+const EXTRA_STREAMS: [(&str, &str); 5] = [
+    ("aggTrade", "aggTrade"),
+    ("kline_1m", "kline_1m"),
+    ("kline_1h", "kline_1h"),
+    ("bookTicker", "bookTicker"),
+    ("depth", "depth@100ms"),
+];
+
+/// Combined-stream names for every symbol: `@trade` plus the extra streams
+/// listed in `extra` (comma-separated `aggTrade`, `kline_1m`, `kline_1h`,
+/// `bookTicker`, `depth`)
+fn binance_streams(extra: &str) -> Vec<String> {
+    let mut kinds = vec![BASE_STREAM];
+    for name in extra.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        match EXTRA_STREAMS.iter().find(|(config, _)| config.eq_ignore_ascii_case(name)).map(|(_, stream)| stream) {
+            Some(kind) if !kinds.contains(kind) => kinds.push(kind),
+            Some(_) => {}
+            None => eprintln!("⚠️ Unknown Binance stream '{}' in BINANCE_EXTRA_STREAMS, ignored", name),
+        }
+    }
+    ["btcusdt", "ethusdt", "solusdt", "xrpusdt"]
+        .iter()
+        .flat_map(|symbol| kinds.iter().map(move |kind| format!("{}@{}", symbol, kind)))
+        .collect()
+}
+
+/// Binance sends prices and quantities as strings
+fn decimal(value: &simd_json::OwnedValue, key: &str) -> Option<f64> {
+    value.get(key)?.as_str()?.parse().ok()
+}
+
+/// Millisecond timestamps may be parsed as integers or floats
+fn millis(value: &simd_json::OwnedValue, key: &str) -> Option<u64> {
+    let t = value.get(key)?;
+    t.as_u64().or_else(|| t.as_f64().map(|t| t as u64))
+}
+
+/// `[["price", "qty"], ...]` levels of a depth diff
+fn depth_levels(data: &simd_json::OwnedValue, key: &str) -> Vec<(f64, f64)> {
+    let level = |l: &simd_json::OwnedValue| -> Option<(f64, f64)> {
+        Some((l.get_idx(0)?.as_str()?.parse().ok()?, l.get_idx(1)?.as_str()?.parse().ok()?))
+    };
+    data.get(key)
+        .and_then(|levels| levels.as_array())
+        .map(|levels| levels.iter().filter_map(level).collect())
+        .unwrap_or_default()
+}
+
+/// A `depthUpdate` payload, applied to the local book rather than forwarded
+fn parse_depth_diff(data: &simd_json::OwnedValue) -> Option<DepthDiff> {
+    if data.get("e")?.as_str()? != "depthUpdate" {
+        return None;
+    }
+    Some(DepthDiff {
+        symbol: get_symbol_id(data.get("s")?.as_str())?,
+        first_update_id: data.get("U")?.as_u64()?,
+        final_update_id: data.get("u")?.as_u64()?,
+        bids: depth_levels(data, "b"),
+        asks: depth_levels(data, "a"),
+        ts: millis(data, "E")?,
+    })
+}
+
+/// Normalize one Binance stream payload (the `data` of a combined-stream
+/// message) into an event, keyed on its `e` field. `@bookTicker` payloads
+/// carry no `e` (nor a timestamp) and are stamped with `now_ms`.
+fn parse_binance_event(data: &simd_json::OwnedValue, now_ms: u64) -> Option<MarketEvent> {
+    let symbol = get_symbol_id(data.get("s")?.as_str())?;
+    let Some(event) = data.get("e") else {
+        return Some(MarketEvent::Quote(Quote {
+            symbol,
+            bid: decimal(data, "b")?,
+            bid_qty: decimal(data, "B")?,
+            ask: decimal(data, "a")?,
+            ask_qty: decimal(data, "A")?,
+            ts: now_ms,
+        }));
+    };
+    match event.as_str()? {
+        "trade" => Some(MarketEvent::Spot(MarketUpdate {
+            symbol,
+            price: (decimal(data, "p")? * 100.0) as u64,
+            quantity: decimal(data, "q")?,
+            ts: millis(data, "T")?,
+        })),
+        "aggTrade" => Some(MarketEvent::AggTrade(AggTrade {
+            symbol,
+            price: (decimal(data, "p")? * 100.0) as u64,
+            quantity: decimal(data, "q")?,
+            buyer_is_maker: data.get("m").and_then(|m| m.as_bool()).unwrap_or(false),
+            ts: millis(data, "T")?,
+        })),
+        "kline" => {
+            let k = data.get("k")?;
+            Some(MarketEvent::Bar(Bar {
+                symbol,
+                interval: BarInterval::from_code(k.get("i")?.as_str()?)?,
+                open_time: millis(k, "t")?,
+                open: decimal(k, "o")?,
+                high: decimal(k, "h")?,
+                low: decimal(k, "l")?,
+                close: decimal(k, "c")?,
+                volume: decimal(k, "v")?,
+                quote_volume: decimal(k, "q")?,
+                closed: k.get("x").and_then(|x| x.as_bool()).unwrap_or(false),
+                ts: millis(data, "E")?,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Depth snapshot fetched in the background for a symbol
+type SnapshotResult = (u64, ExchangeResult<DepthSnapshot>);
+
+/// Fetch a depth snapshot after `delay` without blocking the streams
+/// (diffs keep being buffered meanwhile)
+fn request_snapshot(http: &reqwest::Client, tx: &UnboundedSender<SnapshotResult>, symbol: u64, delay: Duration) {
+    let (http, tx) = (http.clone(), tx.clone());
+    tokio::spawn(async move {
+        sleep(delay).await;
+        let binance_symbol = format!("{}USDT", crate::strategy::get_asset_name(symbol));
+        let result = crate::binance_book::fetch_depth_snapshot(&http, &binance_symbol).await;
+        tx.send((symbol, result)).ok();
+    });
+}
+
+/// Token ids of every cached market, sorted (the Polymarket subscription set)
+fn subscribed_tokens(market_cache: &MarketCache) -> Vec<String> {
+    let snapshot = market_cache.load();
+    let mut tokens: Vec<String> = snapshot
+        .markets
+        .values()
+        .flatten()
+        .flat_map(|m| m.token_ids.iter().cloned())
+        .collect();
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+/// Connect to the Polymarket market channel and subscribe to `tokens`
+async fn connect_polymarket(url: &str, tokens: &[String]) -> (PolySink, PolyRead) {
+    loop {
+        let (mut write, read) = connect_with_retry(url).await.split();
+        let subscribe = serde_json::json!({ "assets_ids": tokens, "type": "market" }).to_string();
+        match write.send(Message::Text(subscribe)).await {
+            Ok(()) => {
+                println!("Subscribed to {} Polymarket tokens", tokens.len());
+                return (write, read);
+            }
+            Err(e) => {
+                eprintln!("Polymarket subscribe failed: {:?}. Retrying in 5s...", e);
+                sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+/// Parse a price string or number
+fn level_price(value: &simd_json::OwnedValue) -> Option<f64> {
+    value.as_str().and_then(|s| s.parse().ok()).or_else(|| value.as_f64())
+}
+
+/// Top-of-book updates in a Polymarket market-channel message.
+///
+/// `book` snapshots carry full bid/ask ladders; `price_change` events carry
+/// the new best bid/ask per asset. Messages may arrive batched in an array.
+fn parse_book_updates(json: &simd_json::OwnedValue, ts: u64, out: &mut Vec<BookUpdate>) {
+    if let Some(messages) = json.as_array() {
+        for message in messages {
+            parse_book_updates(message, ts, out);
+        }
+        return;
+    }
+
+    match json["event_type"].as_str() {
+        Some("book") => {
+            let Some(token_id) = json["asset_id"].as_str() else { return };
+            let best = |side: &str, pick: fn(f64, f64) -> f64| {
+                json[side]
+                    .as_array()
+                    .and_then(|levels| levels.iter().filter_map(|l| level_price(&l["price"])).reduce(pick))
+            };
+            out.push(BookUpdate {
+                token_id: token_id.to_string(),
+                best_bid: best("bids", f64::max).unwrap_or(0.0),
+                best_ask: best("asks", f64::min).unwrap_or(1.0),
+                ts,
+            });
+        }
+        Some("price_change") => {
+            let Some(changes) = json["price_changes"].as_array() else { return };
+            for change in changes {
+                if let (Some(token_id), Some(best_bid), Some(best_ask)) = (
+                    change["asset_id"].as_str(),
+                    level_price(&change["best_bid"]),
+                    level_price(&change["best_ask"]),
+                ) {
+                    out.push(BookUpdate { token_id: token_id.to_string(), best_bid, best_ask, ts });
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+pub async fn run_ingestion(mut producer: Producer<MarketEvent>, clock: SharedClock, market_cache: MarketCache) {
     println!("Starting Ingestion Engine...");
     
     // Binance Combined Streams - BTC, ETH, SOL, XRP
-    let binance_url = "wss://stream.binance.com:9443/stream?streams=btcusdt@trade/ethusdt@trade/solusdt@trade/xrpusdt@trade";
+    let streams = binance_streams(&std::env::var("BINANCE_EXTRA_STREAMS").unwrap_or_default());
+    let binance_url = format!("{}/stream?streams={}", crate::endpoints::binance_ws_url(), streams.join("/"));
     
     // Polymarket Connection (CLOB WebSocket - Market Channel)
-    let poly_url = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+    let poly_url = crate::endpoints::clob_ws_url();
 
     println!("Connecting to Binance (BTC, ETH, SOL, XRP): {}", binance_url);
-    let binance_stream = connect_with_retry(binance_url).await;
+    let binance_stream = connect_with_retry(&binance_url).await;
 
+    // Subscribe to the tokens of every cached market; resubscribe when the
+    // cache rolls over to new windows
+    let mut poly_tokens = subscribed_tokens(&market_cache);
+    let mut poly_cache_version = market_cache.version();
     println!("Connecting to Polymarket: {}", poly_url);
-    let poly_stream = connect_with_retry(poly_url).await;
+    let (mut _poly_write, mut poly_read) = connect_polymarket(poly_url, &poly_tokens).await;
+    let mut resubscribe_check = tokio::time::interval(Duration::from_secs(1));
 
     let (_, mut binance_read) = binance_stream.split();
-    let (_, mut poly_read) = poly_stream.split();
+    let mut book_updates = Vec::new();
+
+    // Local Binance L2 books (only fed when the depth stream is subscribed)
+    let http = reqwest::Client::new();
+    let (snapshot_tx, mut snapshot_rx) = unbounded_channel::<SnapshotResult>();
+    let mut depth_books: HashMap<u64, LocalBook> = HashMap::new();
+    let mut snapshots_pending: HashSet<u64> = HashSet::new();
+
+    // Other spot venues, forwarded to the strategy thread's composite index
+    let (venue_tx, mut venue_rx) = unbounded_channel::<VenueTick>();
+    for feed in crate::spot_feeds::feeds_from_env(&std::env::var("SPOT_FEEDS").unwrap_or_default()) {
+        tokio::spawn(crate::spot_feeds::run_spot_feed(feed, venue_tx.clone(), clock.clone()));
+    }
 
     loop {
         tokio::select! {
             Some(msg) = binance_read.next() => {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        let mut bytes = text.into_bytes();
-                        match simd_json::to_owned_value(&mut bytes) {
-                            Ok(json) => {
-                                // Combined streams wrap data in {"stream": "...", "data": {...}}
-                                let data = if json.get("data").is_some() {
-                                    &json["data"]
-                                } else {
-                                    &json
-                                };
-                                
-                                // Get symbol from the trade data (s field)
-                                let symbol_id = get_symbol_id(data["s"].as_str());
-                                
-                                if let (Some(symbol), Some(price_str), Some(ts)) = (symbol_id, data["p"].as_str(), data["T"].as_f64()) {
-                                    if let Ok(price_f) = price_str.parse::<f64>() {
-                                        let price = (price_f * 100.0) as u64;
-                                        let update = MarketUpdate {
-                                            symbol,
-                                            price,
-                                            ts: ts as u64,
-                                        };
-                                        
-                                        if let Err(e) = producer.push(update) {
-                                            eprintln!("Ring Buffer Full! Dropping update: {:?}", e);
+                if let Ok(Message::Text(text)) = msg {
+                    let mut bytes = text.into_bytes();
+                    match simd_json::to_owned_value(&mut bytes) {
+                        Ok(json) => {
+                            // Combined streams wrap data in {"stream": "...", "data": {...}}
+                            let data = if json.get("data").is_some() {
+                                &json["data"]
+                            } else {
+                                &json
+                            };
+                            
+                            if let Some(diff) = parse_depth_diff(data) {
+                                let (symbol, ts) = (diff.symbol, diff.ts);
+                                let book = depth_books.entry(symbol).or_default();
+                                match book.apply_diff(diff) {
+                                    BookSync::Updated => {
+                                        if let Some(summary) = book.summary(symbol, ts) {
+                                            if producer.push(MarketEvent::Depth(summary)).is_err() {
+                                                eprintln!("Ring Buffer Full! Dropping depth update");
+                                            }
                                         }
                                     }
-                                } else if let (Some(symbol), Some(price_str), Some(ts)) = (symbol_id, data["p"].as_str(), data["T"].as_u64()) {
-                                    // Fallback if T is u64
-                                    if let Ok(price_f) = price_str.parse::<f64>() {
-                                        let price = (price_f * 100.0) as u64;
-                                        let update = MarketUpdate {
-                                            symbol,
-                                            price,
-                                            ts,
-                                        };
-                                        producer.push(update).ok();
+                                    BookSync::Buffered | BookSync::OutOfSync => {
+                                        if snapshots_pending.insert(symbol) {
+                                            println!("Syncing Binance depth book for {}", crate::strategy::get_asset_name(symbol));
+                                            request_snapshot(&http, &snapshot_tx, symbol, Duration::ZERO);
+                                        }
                                     }
+                                    BookSync::Stale => {}
+                                }
+                            } else if let Some(event) = parse_binance_event(data, clock.now_ms(Venue::Binance)) {
+                                if producer.push(event).is_err() {
+                                    eprintln!("Ring Buffer Full! Dropping Binance update");
                                 }
                             }
-                            Err(e) => eprintln!("Binance JSON Error: {:?}", e),
                         }
+                        Err(e) => eprintln!("Binance JSON Error: {:?}", e),
                     }
-                    _ => {}
                 }
             }
-            Some(msg) = poly_read.next() => {
+            msg = poly_read.next() => {
                 match msg {
-                    Ok(Message::Text(text)) => {
-                        // Polymarket sends JSON messages
+                    Some(Ok(Message::Text(text))) => {
+                        // Polymarket sends JSON messages (single or batched)
                         let mut bytes = text.into_bytes();
                         match simd_json::to_owned_value(&mut bytes) {
                             Ok(json) => {
-                                // Parse price from Polymarket format
-                                // Example: {"event_type": "price_change", "price": "0.55", ...}
-                                if let Some(price_str) = json["price"].as_str() {
-                                    if let Ok(price_f) = price_str.parse::<f64>() {
-                                        let price = (price_f * 100.0) as u64;
-                                        let update = MarketUpdate {
-                                            symbol: 2, // Polymarket
-                                            price,
-                                            ts: std::time::SystemTime::now()
-                                                .duration_since(std::time::UNIX_EPOCH)
-                                                .unwrap()
-                                                .as_millis() as u64,
-                                        };
-                                        producer.push(update).ok();
+                                // Stamp on Binance's clock so both feeds share
+                                // the timeline of the Binance `T` timestamps
+                                parse_book_updates(&json, clock.now_ms(Venue::Binance), &mut book_updates);
+                                for update in book_updates.drain(..) {
+                                    if let Err(e) = producer.push(MarketEvent::Book(update)) {
+                                        eprintln!("Ring Buffer Full! Dropping book update: {:?}", e);
                                     }
                                 }
                             }
                             Err(e) => eprintln!("Polymarket JSON Error: {:?}", e),
                         }
                     }
-                    _ => {}
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        eprintln!("Polymarket WebSocket error: {:?}. Reconnecting...", e);
+                        (_poly_write, poly_read) = connect_polymarket(poly_url, &poly_tokens).await;
+                    }
+                    None => {
+                        eprintln!("Polymarket WebSocket closed. Reconnecting...");
+                        (_poly_write, poly_read) = connect_polymarket(poly_url, &poly_tokens).await;
+                    }
+                }
+            }
+            Some((symbol, result)) = snapshot_rx.recv() => {
+                let book = depth_books.entry(symbol).or_default();
+                let asset = crate::strategy::get_asset_name(symbol);
+                match result.map(|snapshot| book.apply_snapshot(snapshot)) {
+                    Ok(BookSync::OutOfSync) => {
+                        // Snapshot older than the buffered diffs (or a gap in them)
+                        request_snapshot(&http, &snapshot_tx, symbol, Duration::from_millis(500));
+                    }
+                    Ok(_) => {
+                        snapshots_pending.remove(&symbol);
+                        println!("Binance depth book synced for {}", asset);
+                        if let Some(summary) = book.summary(symbol, clock.now_ms(Venue::Binance)) {
+                            producer.push(MarketEvent::Depth(summary)).ok();
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Binance depth snapshot for {} failed: {}. Retrying in 5s...", asset, e);
+                        request_snapshot(&http, &snapshot_tx, symbol, Duration::from_secs(5));
+                    }
+                }
+            }
+            Some(tick) = venue_rx.recv() => {
+                if producer.push(MarketEvent::VenuePrice(tick)).is_err() {
+                    eprintln!("Ring Buffer Full! Dropping {} price", tick.venue.name());
+                }
+            }
+            _ = resubscribe_check.tick() => {
+                let version = market_cache.version();
+                if version != poly_cache_version {
+                    poly_cache_version = version;
+                    let tokens = subscribed_tokens(&market_cache);
+                    if tokens != poly_tokens {
+                        poly_tokens = tokens;
+                        (_poly_write, poly_read) = connect_polymarket(poly_url, &poly_tokens).await;
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<BookUpdate> {
+        let mut bytes = text.as_bytes().to_vec();
+        let json = simd_json::to_owned_value(&mut bytes).unwrap();
+        let mut out = Vec::new();
+        parse_book_updates(&json, 42, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_parse_book_snapshot() {
+        let updates = parse(r#"[{"event_type": "book", "asset_id": "1001",
+            "bids": [{"price": "0.46", "size": "10"}, {"price": "0.48", "size": "5"}],
+            "asks": [{"price": "0.53", "size": "10"}, {"price": "0.50", "size": "5"}]}]"#);
+        assert_eq!(updates, vec![BookUpdate { token_id: "1001".to_string(), best_bid: 0.48, best_ask: 0.50, ts: 42 }]);
+    }
+
+    #[test]
+    fn test_parse_price_change() {
+        let updates = parse(r#"{"event_type": "price_change", "market": "0xabc", "price_changes": [
+            {"asset_id": "1001", "price": "0.49", "size": "120", "side": "BUY", "best_bid": "0.49", "best_ask": "0.50"},
+            {"asset_id": "1002", "price": "0.51", "size": "0", "side": "SELL", "best_bid": "0.50", "best_ask": "0.52"}]}"#);
+        assert_eq!(updates.len(), 2);
+        assert_eq!((updates[1].token_id.as_str(), updates[1].best_bid, updates[1].best_ask), ("1002", 0.50, 0.52));
+
+        // Other events carry no book state
+        assert!(parse(r#"{"event_type": "last_trade_price", "asset_id": "1001", "price": "0.5"}"#).is_empty());
+    }
+
+    fn binance(text: &str) -> Option<MarketEvent> {
+        let mut bytes = text.as_bytes().to_vec();
+        parse_binance_event(&simd_json::to_owned_value(&mut bytes).unwrap(), 42)
+    }
+
+    #[test]
+    fn test_binance_streams() {
+        assert_eq!(binance_streams("")[..2], ["btcusdt@trade".to_string(), "ethusdt@trade".to_string()]);
+        let streams = binance_streams("kline_1m, aggtrade,kline_1m,ticker");
+        assert_eq!(streams.len(), 12);
+        assert_eq!(streams[..3], ["btcusdt@trade", "btcusdt@kline_1m", "btcusdt@aggTrade"].map(String::from));
+        assert_eq!(binance_streams("bookTicker,depth")[..3], ["btcusdt@trade", "btcusdt@bookTicker", "btcusdt@depth@100ms"].map(String::from));
+    }
+
+    #[test]
+    fn test_parse_binance_trade_and_agg_trade() {
+        let Some(MarketEvent::Spot(trade)) = binance(r#"{"e": "trade", "s": "ETHUSDT", "p": "3400.25", "q": "0.2", "T": 1768478400123}"#) else {
+            panic!("expected a trade");
+        };
+        assert_eq!((trade.symbol, trade.price, trade.quantity, trade.ts), (2, 340_025, 0.2, 1768478400123));
+
+        let agg = binance(r#"{"e": "aggTrade", "E": 1768478400130, "s": "BTCUSDT", "a": 26129, "p": "97000.50",
+            "q": "0.125", "f": 100, "l": 105, "T": 1768478400125, "m": true}"#);
+        let Some(MarketEvent::AggTrade(agg)) = agg else {
+            panic!("expected an aggTrade");
+        };
+        assert_eq!(agg, AggTrade { symbol: 1, price: 9_700_050, quantity: 0.125, buyer_is_maker: true, ts: 1768478400125 });
+
+        // Unknown symbols and events are dropped
+        assert!(binance(r#"{"e": "trade", "s": "DOGEUSDT", "p": "0.3", "T": 1}"#).is_none());
+        assert!(binance(r#"{"e": "24hrTicker", "s": "BTCUSDT"}"#).is_none());
+        // Missing fields never panic (simd-json indexing would)
+        assert!(binance(r#"{"result": null, "id": 1}"#).is_none());
+        assert!(binance(r#"{"e": "aggTrade", "s": "BTCUSDT", "p": "97000.50"}"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_binance_kline() {
+        let bar = binance(r#"{"e": "kline", "E": 1768478459000, "s": "BTCUSDT", "k": {"t": 1768478400000, "T": 1768478459999,
+            "s": "BTCUSDT", "i": "1m", "o": "97000.00", "c": "97040.00", "h": "97100.00", "l": "96950.00",
+            "v": "2.000", "n": 120, "x": true, "q": "194060.00"}}"#);
+        let Some(MarketEvent::Bar(bar)) = bar else {
+            panic!("expected a bar");
+        };
+        assert_eq!((bar.symbol, bar.interval, bar.open_time, bar.ts), (1, BarInterval::OneMinute, 1768478400000, 1768478459000));
+        assert_eq!((bar.open, bar.high, bar.low, bar.close, bar.closed), (97_000.0, 97_100.0, 96_950.0, 97_040.0, true));
+        assert_eq!(bar.vwap(), Some(97_030.0));
+
+        // Intervals we don't model are dropped
+        assert!(binance(r#"{"e": "kline", "E": 1, "s": "BTCUSDT", "k": {"t": 0, "i": "5m", "o": "1", "c": "1",
+            "h": "1", "l": "1", "v": "0", "x": false, "q": "0"}}"#).is_none());
+    }
+
+    #[test]
+    fn test_parse_book_ticker_and_depth_diff() {
+        let quote = binance(r#"{"u": 400900217, "s": "BTCUSDT", "b": "97000.00", "B": "3.0", "a": "97001.00", "A": "1.0"}"#);
+        let Some(MarketEvent::Quote(quote)) = quote else {
+            panic!("expected a quote");
+        };
+        assert_eq!((quote.symbol, quote.bid, quote.ask, quote.ts), (1, 97_000.0, 97_001.0, 42));
+        assert_eq!(quote.mid(), 97_000.5);
+        assert_eq!(quote.microprice(), 97_000.75); // Heavier bid pulls towards the ask
+        assert_eq!(quote.imbalance(), Some(0.5));
+
+        let text = r#"{"e": "depthUpdate", "E": 1768478400100, "s": "ETHUSDT", "U": 157, "u": 160,
+            "b": [["3400.10", "1.5"], ["3400.00", "0"]], "a": [["3400.20", "2.0"]]}"#;
+        let mut bytes = text.as_bytes().to_vec();
+        let data = simd_json::to_owned_value(&mut bytes).unwrap();
+        let diff = parse_depth_diff(&data).unwrap();
+        assert_eq!((diff.symbol, diff.first_update_id, diff.final_update_id, diff.ts), (2, 157, 160, 1768478400100));
+        assert_eq!(diff.bids, vec![(3_400.1, 1.5), (3_400.0, 0.0)]);
+        assert_eq!(diff.asks, vec![(3_400.2, 2.0)]);
+        // Diffs never reach the strategy thread directly
+        assert!(parse_binance_event(&data, 42).is_none());
+    }
+}
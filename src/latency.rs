@@ -0,0 +1,453 @@
+//! Latency arbitrage strategy
+//!
+//! Near expiry, a decisive spot move past the window's strike (the open
+//! price for Up/Down windows) all but decides the outcome, while Polymarket
+//! odds often lag Binance by seconds. We price each live window with the
+//! fair-value model (`pricing`), compare it with the live Polymarket ask of
+//! both outcomes, and buy the side whose fair probability beats its all-in
+//! cost by at least the minimum edge. Port of the TS `latency_strategy.ts`.
+//!
+//! Runs as a strategy of the pinned strategy thread, fed by Binance trades
+//! and Polymarket top-of-book updates from the same ring buffer.
+
+use crate::execution::{Fill, Rejection, SignalKind, TradeInstruction};
+use crate::ingestion::{Bar, BarInterval, BookUpdate, MarketUpdate};
+use crate::polymarket::CachedMarket;
+use crate::spot_index::SpotSource;
+use crate::strategy::{get_asset_name, Strategy, StrategyContext};
+use crate::types::MarketType;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// Minimum edge (probability points) over the all-in cost of a share
+const DEFAULT_MIN_EDGE: f64 = 0.05;
+/// Don't trade with less than this left: fills may land after expiry
+const DEFAULT_MIN_SECS_REMAINING: f64 = 30.0;
+/// Don't trade earlier than this: the outcome isn't decided yet
+const DEFAULT_MAX_SECS_REMAINING: f64 = 300.0;
+/// Budget per trade ($)
+const DEFAULT_TRADE_SIZE: u64 = 10;
+/// Ignore books that haven't updated for this long (ms)
+const MAX_BOOK_AGE_MS: u64 = 5_000;
+/// Window opens are kept this long (ms), enough for daily windows
+const OPEN_RETENTION_MS: u64 = 2 * 86_400_000;
+/// After a refused entry, its window is left alone this long (ms)
+const REJECT_COOLDOWN_MS: u64 = 10_000;
+
+/// Best bid/ask of one outcome token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TopOfBook {
+    pub bid: f64,
+    pub ask: f64,
+    pub ts: u64,
+}
+
+/// Thresholds (`LATENCY_MIN_EDGE`, `LATENCY_MIN_SECS`, `LATENCY_MAX_SECS`,
+/// `LATENCY_TRADE_SIZE`) and the spot price fair values are computed from
+/// (`LATENCY_SPOT_SOURCE`: `binance` or `composite`)
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyConfig {
+    pub min_edge: f64,
+    pub min_secs_remaining: f64,
+    pub max_secs_remaining: f64,
+    pub trade_size: u64,
+    pub spot_source: SpotSource,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            min_edge: DEFAULT_MIN_EDGE,
+            min_secs_remaining: DEFAULT_MIN_SECS_REMAINING,
+            max_secs_remaining: DEFAULT_MAX_SECS_REMAINING,
+            trade_size: DEFAULT_TRADE_SIZE,
+            spot_source: SpotSource::Binance,
+        }
+    }
+}
+
+impl LatencyConfig {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok());
+        let defaults = Self::default();
+        Self {
+            min_edge: var("LATENCY_MIN_EDGE").unwrap_or(defaults.min_edge),
+            min_secs_remaining: var("LATENCY_MIN_SECS").unwrap_or(defaults.min_secs_remaining),
+            max_secs_remaining: var("LATENCY_MAX_SECS").unwrap_or(defaults.max_secs_remaining),
+            trade_size: var("LATENCY_TRADE_SIZE").map(|v| v as u64).unwrap_or(defaults.trade_size),
+            spot_source: std::env::var("LATENCY_SPOT_SOURCE")
+                .ok()
+                .and_then(|v| SpotSource::from_name(&v))
+                .unwrap_or(defaults.spot_source),
+        }
+    }
+}
+
+/// Fallback volatility per second until enough trades have been seen
+pub fn default_volatility(asset: &str) -> f64 {
+    match asset {
+        "BTC" => 0.00008,
+        "ETH" => 0.00011,
+        "SOL" => 0.00016,
+        _ => 0.00015,
+    }
+}
+
+/// A trade the strategy wants to make
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencySignal {
+    pub side: u8, // 0 = Up/YES, 1 = Down/NO
+    pub fair_prob: f64,
+    pub ask: f64,
+    pub edge: f64,
+    pub secs_left: f64,
+}
+
+/// Decide whether to buy either outcome of a window.
+///
+/// `fair_up`: model probability of Up; books are for the Up and Down tokens.
+/// Returns the best side, or why there is no trade.
+pub fn evaluate(
+    config: &LatencyConfig,
+    fair_up: f64,
+    secs_left: f64,
+    up: Option<TopOfBook>,
+    down: Option<TopOfBook>,
+    fee_rate: f64,
+    now_ms: u64,
+) -> Result<LatencySignal, String> {
+    if secs_left < config.min_secs_remaining {
+        return Err(format!("too late: {:.0}s left", secs_left));
+    }
+    if secs_left > config.max_secs_remaining {
+        return Err(format!("too early: {:.0}s left", secs_left));
+    }
+
+    let fresh = |book: Option<TopOfBook>| book.filter(|b| now_ms.saturating_sub(b.ts) <= MAX_BOOK_AGE_MS && b.ask > 0.0 && b.ask < 1.0);
+    let candidates = [(0u8, fair_up, fresh(up)), (1u8, 1.0 - fair_up, fresh(down))];
+    if candidates.iter().all(|(_, _, book)| book.is_none()) {
+        return Err("no live book".to_string());
+    }
+
+    let best = candidates
+        .iter()
+        .filter_map(|&(side, fair, book)| {
+            let ask = book?.ask;
+            Some(LatencySignal {
+                side,
+                fair_prob: fair,
+                ask,
+                edge: crate::prices::calculate_edge(ask, fair, fee_rate),
+                secs_left,
+            })
+        })
+        .max_by(|a, b| a.edge.total_cmp(&b.edge))
+        .expect("at least one book");
+
+    if best.edge < config.min_edge {
+        return Err(format!("no edge: best {:.1}¢ on side {}", best.edge * 100.0, best.side));
+    }
+    Ok(best)
+}
+
+/// Index of the Up/YES token among a market's outcomes
+fn up_index(market: &CachedMarket) -> usize {
+    market
+        .outcomes
+        .iter()
+        .position(|o| o.eq_ignore_ascii_case("Up") || o.eq_ignore_ascii_case("Yes"))
+        .unwrap_or(0)
+}
+
+/// Strategy state: live books, observed window opens, entries awaiting
+/// their fill and windows traded
+#[derive(Default)]
+pub struct LatencyArb {
+    pub config: LatencyConfig,
+    books: HashMap<String, TopOfBook>,
+    opens: HashMap<(u64, i64), (f64, u64)>, // (asset, window start secs) -> (open price, ts)
+    last_tick_ms: HashMap<u64, u64>,
+    pending: HashMap<u64, String>, // instruction symbol -> condition id, until filled or refused
+    cooldown: HashMap<String, u64>, // condition id -> refused entries retried from (ms)
+    traded: HashSet<String>, // condition ids entered (fill confirmed)
+}
+
+impl LatencyArb {
+    pub fn new(config: LatencyConfig) -> Self {
+        Self { config, ..Default::default() }
+    }
+
+    pub fn on_book(&mut self, update: &BookUpdate) {
+        self.books.insert(
+            update.token_id.clone(),
+            TopOfBook { bid: update.best_bid, ask: update.best_ask, ts: update.ts },
+        );
+    }
+
+    /// Record window opens: the first trade at or after a window's start,
+    /// provided we were already watching before it started
+    pub fn on_spot<'a>(&mut self, asset_symbol: u64, price: f64, ts: u64, markets: impl IntoIterator<Item = &'a CachedMarket>) {
+        if let Some(prev) = self.last_tick_ms.insert(asset_symbol, ts) {
+            for market in markets {
+                let start_ms = market.start_date.timestamp_millis().max(0) as u64;
+                if prev < start_ms && start_ms <= ts {
+                    self.opens.entry((asset_symbol, market.start_date.timestamp())).or_insert((price, ts));
+                }
+            }
+        }
+        self.opens.retain(|_, (_, at)| ts.saturating_sub(*at) < OPEN_RETENTION_MS);
+    }
+
+    /// A 1-minute kline starting at a window's start carries the exact open
+    /// the window resolves against; it replaces the first trade we saw
+    pub fn on_bar<'a>(&mut self, bar: &Bar, markets: impl IntoIterator<Item = &'a CachedMarket>) {
+        if bar.interval != BarInterval::OneMinute {
+            return;
+        }
+        for market in markets {
+            if market.start_date.timestamp_millis() == bar.open_time as i64 {
+                self.opens.insert((bar.symbol, market.start_date.timestamp()), (bar.open, bar.ts));
+            }
+        }
+    }
+
+    /// Open price of the window starting at `start`, if observed
+    pub fn window_open(&self, asset_symbol: u64, start: DateTime<Utc>) -> Option<f64> {
+        self.opens.get(&(asset_symbol, start.timestamp())).map(|(price, _)| *price)
+    }
+
+    /// Evaluate one live Up/Down window; `None` if it can't be priced (open
+    /// neither captured nor observed), was already traded, has an entry
+    /// awaiting its fill or just had one refused
+    pub fn evaluate_market(
+        &self,
+        asset_symbol: u64,
+        market: &CachedMarket,
+        spot: f64,
+        sigma_per_sec: f64,
+        now: DateTime<Utc>,
+        now_ms: u64,
+    ) -> Option<Result<LatencySignal, String>> {
+        if !matches!(market.market_type, MarketType::Fifteen | MarketType::Hourly)
+            || self.traded.contains(&market.condition_id)
+            || self.pending.values().any(|id| *id == market.condition_id)
+            || self.cooldown.get(&market.condition_id).is_some_and(|until| now_ms < *until)
+        {
+            return None;
+        }
+        // Prefer the captured kline open; fall back to the streamed kline or first trade
+        let open = market.open_price.or_else(|| self.window_open(asset_symbol, market.start_date))?;
+        let secs_left = (market.end_date - now).num_milliseconds() as f64 / 1000.0;
+        let fair_up = crate::pricing::fair_probability_up(spot, open, sigma_per_sec, secs_left);
+
+        let up = up_index(market);
+        let book = |idx: usize| market.token_ids.get(idx).and_then(|t| self.books.get(t)).copied();
+        Some(evaluate(&self.config, fair_up, secs_left, book(up), book(1 - up.min(1)), market.taker_fee_rate, now_ms))
+    }
+
+    /// An entry was sent for the window: hold off until it fills or is refused
+    pub fn mark_pending(&mut self, symbol: u64, condition_id: &str) {
+        self.pending.insert(symbol, condition_id.to_string());
+    }
+
+    /// One entry per window: a filled entry marks its window traded
+    pub fn on_fill(&mut self, fill: &Fill) {
+        if let Some(condition_id) = self.pending.remove(&fill.symbol) {
+            self.traded.insert(condition_id);
+        }
+    }
+
+    /// A refused entry's window may be tried again after a cooldown
+    pub fn on_reject(&mut self, rejection: &Rejection, now_ms: u64) {
+        self.cooldown.retain(|_, until| now_ms < *until);
+        if rejection.filled > 0.0 {
+            return; // Its fill already marked the window traded
+        }
+        if let Some(condition_id) = self.pending.remove(&rejection.symbol) {
+            self.cooldown.insert(condition_id, now_ms + REJECT_COOLDOWN_MS);
+        }
+    }
+}
+
+impl Strategy for LatencyArb {
+    fn tag(&self) -> SignalKind {
+        SignalKind::LatencyArb
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Bar) {
+        let markets = ctx.markets;
+        LatencyArb::on_bar(self, bar, markets.get(get_asset_name(bar.symbol)).into_iter().flatten());
+    }
+
+    fn on_book(&mut self, _ctx: &mut StrategyContext, book: &BookUpdate) {
+        LatencyArb::on_book(self, book);
+    }
+
+    fn on_fill(&mut self, _ctx: &mut StrategyContext, fill: &Fill) {
+        LatencyArb::on_fill(self, fill);
+    }
+
+    /// Cooldowns run on the asset's trade clock, which `evaluate_market` reads
+    fn on_reject(&mut self, ctx: &mut StrategyContext, rejection: &Rejection) {
+        let now_ms = self
+            .last_tick_ms
+            .get(&(rejection.symbol / 100))
+            .copied()
+            .unwrap_or(ctx.now.timestamp_millis().max(0) as u64);
+        LatencyArb::on_reject(self, rejection, now_ms);
+    }
+
+    /// Price the asset's live windows against the Polymarket book
+    fn on_market_update(&mut self, ctx: &mut StrategyContext, update: &MarketUpdate) {
+        let asset_symbol = update.symbol;
+        let asset_name = get_asset_name(asset_symbol);
+        let trade_price = update.price as f64 / 100.0;
+        let markets = ctx.markets;
+        let markets = markets.get(asset_name).map(|m| m.as_slice()).unwrap_or(&[]);
+        self.on_spot(asset_symbol, trade_price, update.ts, markets);
+        // Fair value from the configured source (the trade itself if the index has no fresh quote)
+        let spot = ctx.spot(asset_symbol, self.config.spot_source, update.ts).unwrap_or(trade_price);
+
+        for market_type in [MarketType::Fifteen, MarketType::Hourly] {
+            // Any live window: the strategy applies its own time-remaining limits
+            let Some(market) = markets.iter().find(|m| m.market_type == market_type && m.is_live_at(ctx.now)) else {
+                continue;
+            };
+            // Until the horizon's EWMA estimate is warm: realized, then default
+            let sigma = ctx
+                .volatility(asset_symbol, market_type)
+                .or_else(|| ctx.realized_volatility(asset_symbol))
+                .unwrap_or_else(|| default_volatility(asset_name));
+            let Some(Ok(signal)) = self.evaluate_market(asset_symbol, market, spot, sigma, ctx.now, update.ts) else {
+                continue;
+            };
+            let symbol = asset_symbol * 100 + market_type.minutes().unwrap_or(15);
+            let instruction = TradeInstruction {
+                symbol,
+                side: signal.side,
+                price_cents: (signal.ask * 100.0).round() as u64,
+                size: self.config.trade_size,
+                fair_prob: Some(signal.fair_prob),
+                kind: SignalKind::LatencyArb,
+            };
+            let side_name = if signal.side == 0 { "UP" } else { "DOWN" };
+            if let Err(reason) = ctx.submit(instruction) {
+                println!("[LATENCY] {} {}: BUY {} not sent: {}", asset_name, market_type.label(), side_name, reason);
+                continue;
+            }
+            self.mark_pending(symbol, &market.condition_id);
+            println!("[LATENCY] {} {}: BUY {} @ ${:.2} (fair {:.1}%, edge {:.1}¢, {:.0}s left)",
+                asset_name, market_type.label(), side_name, signal.ask,
+                signal.fair_prob * 100.0, signal.edge * 100.0, signal.secs_left);
+            ctx.log.activity("success", "trade",
+                &format!("{} {} latency arb: BUY {} @ {:.2} (edge {:.1}¢)", asset_name, market_type.label(), side_name, signal.ask, signal.edge * 100.0),
+                Some(format!(r#"{{"asset": "{}", "market": "{}", "side": "{}", "ask": {:.3}, "fair_prob": {:.4}, "edge": {:.4}, "secs_left": {:.0}, "spot": {:.2}}}"#,
+                    asset_name, market_type.label(), side_name, signal.ask, signal.fair_prob, signal.edge, signal.secs_left, spot)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(ask: f64) -> Option<TopOfBook> {
+        Some(TopOfBook { bid: ask - 0.01, ask, ts: 1_000 })
+    }
+
+    #[test]
+    fn test_evaluate_picks_lagging_side() {
+        let config = LatencyConfig::default();
+        // Spot decisively up: model says 95% Up, book still offers Up at 80¢
+        let signal = evaluate(&config, 0.95, 120.0, book(0.80), book(0.22), 0.0, 2_000).unwrap();
+        assert_eq!(signal.side, 0);
+        assert!((signal.edge - 0.15).abs() < 1e-9);
+
+        // Down lags instead
+        let signal = evaluate(&config, 0.10, 120.0, book(0.95), book(0.70), 0.0, 2_000).unwrap();
+        assert_eq!(signal.side, 1);
+        assert!((signal.fair_prob - 0.90).abs() < 1e-9);
+
+        // Fees eat a marginal edge
+        assert!(evaluate(&config, 0.86, 120.0, book(0.80), book(0.22), 0.0, 2_000).is_ok());
+        assert!(evaluate(&config, 0.86, 120.0, book(0.80), book(0.22), 0.10, 2_000).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_time_window_and_books() {
+        let config = LatencyConfig::default();
+        assert!(evaluate(&config, 0.95, 10.0, book(0.80), None, 0.0, 2_000).unwrap_err().contains("too late"));
+        assert!(evaluate(&config, 0.95, 600.0, book(0.80), None, 0.0, 2_000).unwrap_err().contains("too early"));
+        // Priced in: no edge
+        assert!(evaluate(&config, 0.95, 120.0, book(0.94), book(0.07), 0.0, 2_000).unwrap_err().contains("no edge"));
+        // Stale books are ignored
+        assert_eq!(
+            evaluate(&config, 0.95, 120.0, book(0.80), book(0.22), 0.0, 1_000 + MAX_BOOK_AGE_MS + 1),
+            Err("no live book".to_string())
+        );
+    }
+
+    #[test]
+    fn test_window_open_tracking_and_market_evaluation() {
+        let end = chrono::DateTime::parse_from_rfc3339("2026-01-16T12:15:00Z").unwrap().with_timezone(&Utc);
+        let market = crate::polymarket::test_market("BTC", end); // tokens 111 (Up), 222 (Down)
+        let start_ms = market.start_date.timestamp_millis() as u64;
+        let mut arb = LatencyArb::new(LatencyConfig::default());
+
+        // First tick after startup can't be the open; the tick crossing the start is
+        arb.on_spot(1, 99_900.0, start_ms - 2_000, [&market]);
+        arb.on_spot(1, 100_000.0, start_ms + 150, [&market]);
+        arb.on_spot(1, 100_200.0, start_ms + 900, [&market]);
+        assert_eq!(arb.window_open(1, market.start_date), Some(100_000.0));
+
+        // The 1-minute kline open at the window start replaces the observed trade
+        let mut bar = Bar {
+            symbol: 1, interval: BarInterval::OneMinute, open_time: start_ms, open: 99_990.0, high: 100_200.0,
+            low: 99_980.0, close: 100_200.0, volume: 1.0, quote_volume: 100_100.0, closed: false, ts: start_ms + 1_000,
+        };
+        arb.on_bar(&bar, [&market]);
+        assert_eq!(arb.window_open(1, market.start_date), Some(99_990.0));
+        bar.interval = BarInterval::OneHour;
+        bar.open = 99_000.0;
+        arb.on_bar(&bar, [&market]);
+        assert_eq!(arb.window_open(1, market.start_date), Some(99_990.0));
+
+        // Two minutes before expiry, spot 0.5% above the open
+        let now = end - chrono::Duration::seconds(120);
+        let now_ms = now.timestamp_millis() as u64;
+        for (token, ask) in [("111", 0.80), ("222", 0.22)] {
+            arb.on_book(&BookUpdate { token_id: token.to_string(), best_bid: ask - 0.01, best_ask: ask, ts: now_ms });
+        }
+        let signal = arb.evaluate_market(1, &market, 100_500.0, 0.0002, now, now_ms).unwrap().unwrap();
+        assert_eq!(signal.side, 0);
+        assert!(signal.fair_prob > 0.95);
+
+        // Unknown open (asset never watched): can't price, unless the strike was captured
+        let mut eth = crate::polymarket::test_market("ETH", end);
+        assert!(arb.evaluate_market(2, &eth, 3_400.0, 0.0002, now, now_ms).is_none());
+        eth.open_price = Some(3_380.0);
+        assert!(arb.evaluate_market(2, &eth, 3_400.0, 0.0002, now, now_ms).is_some());
+
+        // One entry per window, once its fill confirms it; an entry out on
+        // the venue holds the window however long it rests
+        let fill = Fill {
+            kind: SignalKind::LatencyArb, symbol: 115, side: 0, price: 0.80, shares: 12.5, cost: 10.0, fee: 0.0,
+            pnl: 2.5, remaining: 0.0, expires_at: end,
+        };
+        arb.mark_pending(115, &market.condition_id);
+        let later_ms = now_ms + REJECT_COOLDOWN_MS;
+        for (token, ask) in [("111", 0.80), ("222", 0.22)] {
+            arb.on_book(&BookUpdate { token_id: token.to_string(), best_bid: ask - 0.01, best_ask: ask, ts: later_ms });
+        }
+        assert!(arb.evaluate_market(1, &market, 100_500.0, 0.0002, now, later_ms).is_none());
+
+        // Refused: the window may be tried again once the cooldown is over
+        let rejection = Rejection { kind: SignalKind::LatencyArb, symbol: 115, side: 0, filled: 0.0, reason: "spread too wide".into() };
+        arb.on_reject(&rejection, now_ms);
+        assert!(arb.evaluate_market(1, &market, 100_500.0, 0.0002, now, now_ms + 1_000).is_none());
+        assert!(arb.evaluate_market(1, &market, 100_500.0, 0.0002, now, later_ms).is_some());
+        arb.mark_pending(115, &market.condition_id);
+        arb.on_fill(&fill);
+        assert!(arb.evaluate_market(1, &market, 100_500.0, 0.0002, now, later_ms + REJECT_COOLDOWN_MS).is_none());
+    }
+}
@@ -0,0 +1,873 @@
+//! Internal ledger of what we hold on the venue
+//!
+//! Rebuilt from the exchange at startup (positions, resting orders, recent
+//! fills) so a restart doesn't forget open exposure, and compared with the
+//! DB trade log to surface anything the two disagree on before trading resumes.
+//!
+//! While running, execution records the orders it places here. A background
+//! reconciler moves their fills into positions as the venue reports them,
+//! queues them as reports for the strategies that sent the orders, then
+//! compares the ledger with the exchange (manual trades from the web UI and
+//! lost responses make them drift).
+
+use crate::database::{DbLogger, TradeLogRow};
+use crate::exchange::{ExchangeClient, ExchangeResult, OpenOrder, TokenPosition, TradeFill};
+use crate::execution::{settle_exposure, ExecutionReport, OrderOrigin};
+use crate::polymarket::{CachedMarket, MarketCache, OrderSide};
+use crate::risk::RiskManager;
+use crate::types::{MarketType, Side};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Share differences below this are treated as rounding
+const SHARE_TOLERANCE: f64 = 0.5;
+/// Default interval between live reconciliations
+const DEFAULT_RECONCILE_INTERVAL_SECS: u64 = 60;
+/// Positions we traded this recently are skipped (venue APIs lag fills)
+const RECONCILE_GRACE_SECS: i64 = 30;
+/// Unmatched remainder below which an order counts as filled (shares)
+const ORDER_DUST: f64 = 1e-6;
+/// Our orders resting longer than this are cancelled: the price they were
+/// sent at no longer reflects the signal behind them
+const STALE_ORDER_SECS: i64 = 120;
+/// Trades older than this before the newest we hold are forgotten; our
+/// orders leave the book long before
+const TRADE_RETENTION_SECS: i64 = 3600;
+
+/// Shares held in a known market
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerPosition {
+    pub asset: String,
+    pub market_type: MarketType,
+    pub token_id: String,
+    pub condition_id: String,
+    pub side: Side, // Yes = Up/Yes token, No = Down/No token
+    pub shares: f64,
+    pub avg_price: f64,
+    pub expires_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>, // Last fill we recorded (or when loaded)
+}
+
+impl LedgerPosition {
+    /// Ticker as written to the trade log (e.g. "BTC-15-MIN")
+    pub fn ticker(&self) -> String {
+        format!("{}-{}", self.asset, self.market_type.label())
+    }
+}
+
+/// Our view of venue state
+#[derive(Debug, Default)]
+pub struct Ledger {
+    /// Positions in cached markets, keyed by token ID
+    pub positions: HashMap<String, LedgerPosition>,
+    /// Resting orders; `size_matched` of our own is what has reached `positions`
+    pub open_orders: Vec<OpenOrder>,
+    /// When we placed orders in `open_orders` (none for orders loaded from the venue)
+    pub placed_at: HashMap<String, DateTime<Utc>>,
+    /// Instructions behind the orders we placed, to report their fills
+    pub origins: HashMap<String, OrderOrigin>,
+    /// Fills and rejections waiting for execution to pass them on
+    pub reports: Vec<ExecutionReport>,
+    /// Recent fills, newest first; the newest is where the next fetch resumes
+    pub recent_trades: Vec<TradeFill>,
+    /// Held tokens that match no cached market (expired, unredeemed, or
+    /// outside the markets we track)
+    pub unmatched: Vec<TokenPosition>,
+}
+
+pub type SharedLedger = Arc<Mutex<Ledger>>;
+
+/// Position where the DB trade log and the exchange disagree
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub ticker: String,
+    pub side: Side,
+    pub db_shares: f64,
+    pub exchange_shares: f64,
+}
+
+/// Summary of the startup reconciliation
+#[derive(Debug, Default)]
+pub struct StartupReport {
+    pub positions: usize,
+    pub open_orders: usize,
+    pub recent_trades: usize,
+    pub unmatched: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Which side `token_id` is in `market`, if it belongs to it
+fn token_side(market: &CachedMarket, token_id: &str) -> Option<Side> {
+    let idx = market.token_ids.iter().position(|t| t == token_id)?;
+    let outcome = market.outcomes.get(idx).map(|o| o.to_lowercase());
+    Some(match outcome.as_deref() {
+        Some("up") | Some("yes") => Side::Yes,
+        Some("down") | Some("no") => Side::No,
+        _ if idx == 0 => Side::Yes,
+        _ => Side::No,
+    })
+}
+
+/// Find the cached market containing `token_id`, and which side it is
+fn locate_token<'a>(
+    markets: &'a HashMap<String, Vec<CachedMarket>>,
+    token_id: &str,
+) -> Option<(&'a CachedMarket, Side)> {
+    markets
+        .values()
+        .flatten()
+        .find_map(|market| Some((market, token_side(market, token_id)?)))
+}
+
+impl Ledger {
+    /// Build the ledger from exchange state, resolving tokens against the market cache
+    pub fn from_exchange(
+        positions: Vec<TokenPosition>,
+        open_orders: Vec<OpenOrder>,
+        recent_trades: Vec<TradeFill>,
+        markets: &HashMap<String, Vec<CachedMarket>>,
+    ) -> Self {
+        let mut ledger = Ledger {
+            open_orders,
+            recent_trades,
+            ..Default::default()
+        };
+        for position in positions {
+            match locate_token(markets, &position.token_id) {
+                Some((market, side)) => {
+                    ledger.positions.insert(position.token_id.clone(), LedgerPosition {
+                        asset: market.asset.clone(),
+                        market_type: market.market_type,
+                        token_id: position.token_id,
+                        condition_id: market.condition_id.clone(),
+                        side,
+                        shares: position.size,
+                        avg_price: position.avg_price,
+                        expires_at: market.end_date,
+                        updated_at: Utc::now(),
+                    });
+                }
+                None => ledger.unmatched.push(position),
+            }
+        }
+        ledger
+    }
+
+    /// Positions whose window has not expired yet
+    pub fn live_positions(&self, now: DateTime<Utc>) -> impl Iterator<Item = &LedgerPosition> {
+        self.positions.values().filter(move |p| p.expires_at > now)
+    }
+
+    /// Track an order we just placed for `origin`. Its shares reach
+    /// `positions` once the venue reports them matched (see `apply_fills`).
+    pub fn record_order(&mut self, order: OpenOrder, origin: OrderOrigin, now: DateTime<Utc>) {
+        self.placed_at.insert(order.order_id.clone(), now);
+        self.origins.insert(order.order_id.clone(), origin);
+        self.open_orders.push(order);
+    }
+
+    /// Where the next trade fetch resumes: the newest trade we hold
+    pub fn trades_after(&self) -> Option<DateTime<Utc>> {
+        self.recent_trades.first().map(|t| t.matched_at)
+    }
+
+    /// Add the trades we don't hold yet (fetches overlap at the cursor) and
+    /// forget those past `TRADE_RETENTION_SECS`
+    pub fn merge_trades(&mut self, trades: &[TradeFill]) {
+        for trade in trades {
+            if !self.recent_trades.iter().any(|t| t.trade_id == trade.trade_id) {
+                self.recent_trades.push(trade.clone());
+            }
+        }
+        self.recent_trades.sort_by_key(|t| std::cmp::Reverse(t.matched_at));
+        if let Some(newest) = self.trades_after() {
+            let cutoff = newest - chrono::Duration::seconds(TRADE_RETENTION_SECS);
+            self.recent_trades.retain(|t| t.matched_at >= cutoff);
+        }
+    }
+
+    /// Move what the venue matched of our orders into positions: the
+    /// `size_matched` of orders still resting, the trades of orders that
+    /// left the book (`venue` holds the trades since the last call, merged
+    /// into ours). Orders are dropped once filled, or once they have been
+    /// off the book past the grace period (cancelled or expired).
+    ///
+    /// Returns a report per order we placed: a fill for its newly matched
+    /// shares, a rejection if it left the book before filling.
+    pub fn apply_fills(
+        &mut self,
+        venue: &Ledger,
+        markets: &HashMap<String, Vec<CachedMarket>>,
+        now: DateTime<Utc>,
+    ) -> Vec<ExecutionReport> {
+        self.merge_trades(&venue.recent_trades);
+        let grace = chrono::Duration::seconds(RECONCILE_GRACE_SECS);
+        let mut kept = Vec::new();
+        let mut reports = Vec::new();
+        for mut order in std::mem::take(&mut self.open_orders) {
+            let (traded, notional) = self
+                .recent_trades
+                .iter()
+                .filter(|t| t.order_id == order.order_id)
+                .fold((0.0, 0.0), |(shares, notional), t| (shares + t.size, notional + t.price * t.size));
+            let resting = venue.open_orders.iter().find(|o| o.order_id == order.order_id);
+            let matched = resting.map_or(traded, |o| o.size_matched.max(traded)).min(order.size);
+            let origin = self.origins.get(&order.order_id).cloned();
+
+            let new_shares = matched - order.size_matched;
+            if new_shares > ORDER_DUST {
+                let price = if traded > 0.0 {
+                    notional / traded
+                } else {
+                    order.price
+                };
+                if let Some((market, _)) = locate_token(markets, &order.token_id) {
+                    self.record_fill(market, &order.token_id, order.side, new_shares, price, now);
+                }
+                order.size_matched = matched;
+                if let Some(origin) = &origin {
+                    let remaining = order.size - matched;
+                    let remaining = if remaining < ORDER_DUST { 0.0 } else { remaining };
+                    reports.push(ExecutionReport::Filled(origin.fill(price, new_shares, remaining)));
+                }
+            }
+
+            let filled = order.size - order.size_matched < ORDER_DUST;
+            let recent = self.placed_at.get(&order.order_id).is_some_and(|t| now - *t < grace);
+            if !filled && (resting.is_some() || recent) {
+                kept.push(order);
+            } else if !filled {
+                if let Some(origin) = origin {
+                    let unfilled = order.size - order.size_matched;
+                    let reason = format!("left the book with {:.2} of {:.2} shares unfilled", unfilled, order.size);
+                    reports.push(ExecutionReport::Rejected(origin.rejection(order.size_matched, reason)));
+                }
+            }
+        }
+        self.placed_at.retain(|id, _| kept.iter().any(|o| &o.order_id == id));
+        self.origins.retain(|id, _| kept.iter().any(|o| &o.order_id == id));
+        self.open_orders = kept;
+        reports
+    }
+
+    /// IDs of the orders we placed that have rested past `STALE_ORDER_SECS`
+    pub fn stale_orders(&self, now: DateTime<Utc>) -> Vec<String> {
+        let max_age = chrono::Duration::seconds(STALE_ORDER_SECS);
+        self.open_orders
+            .iter()
+            .filter(|o| self.placed_at.get(&o.order_id).is_some_and(|t| now - *t >= max_age))
+            .map(|o| o.order_id.clone())
+            .collect()
+    }
+
+    /// Reports queued since the last call, oldest first
+    pub fn take_reports(&mut self) -> Vec<ExecutionReport> {
+        std::mem::take(&mut self.reports)
+    }
+
+    /// Record one of our fills in `market`. Closed positions are kept at
+    /// zero shares until expiry so the grace period still covers them.
+    pub fn record_fill(
+        &mut self,
+        market: &CachedMarket,
+        token_id: &str,
+        order_side: OrderSide,
+        shares: f64,
+        price: f64,
+        now: DateTime<Utc>,
+    ) {
+        let side = token_side(market, token_id).unwrap_or(Side::Yes);
+        let position = self.positions.entry(token_id.to_string()).or_insert_with(|| LedgerPosition {
+            asset: market.asset.clone(),
+            market_type: market.market_type,
+            token_id: token_id.to_string(),
+            condition_id: market.condition_id.clone(),
+            side,
+            shares: 0.0,
+            avg_price: 0.0,
+            expires_at: market.end_date,
+            updated_at: now,
+        });
+        match order_side {
+            OrderSide::BUY => {
+                let total = position.shares + shares;
+                position.avg_price = (position.avg_price * position.shares + price * shares) / total;
+                position.shares = total;
+            }
+            OrderSide::SELL => position.shares = (position.shares - shares).max(0.0),
+        }
+        position.updated_at = now;
+    }
+
+    /// Compare with the exchange's view (`venue`), ignoring expired windows
+    /// and positions traded within the grace period
+    pub fn diff(&self, venue: &Ledger, now: DateTime<Utc>) -> LedgerDiff {
+        let grace = chrono::Duration::seconds(RECONCILE_GRACE_SECS);
+        let mut positions = Vec::new();
+
+        // Orders we just placed may not be on the book or in the venue's positions yet
+        let placed_recently = |order_id: &String| self.placed_at.get(order_id).is_some_and(|t| now - *t < grace);
+        let pending_tokens: std::collections::BTreeSet<&String> = self
+            .open_orders
+            .iter()
+            .filter(|o| placed_recently(&o.order_id))
+            .map(|o| &o.token_id)
+            .collect();
+
+        let tokens: std::collections::BTreeSet<&String> = self
+            .live_positions(now)
+            .chain(venue.live_positions(now))
+            .map(|p| &p.token_id)
+            .collect();
+        for token_id in tokens {
+            let ours = self.positions.get(token_id);
+            if pending_tokens.contains(token_id) || ours.is_some_and(|p| now - p.updated_at < grace) {
+                continue;
+            }
+            let theirs = venue.positions.get(token_id);
+            let internal_shares = ours.map_or(0.0, |p| p.shares);
+            let exchange_shares = theirs.map_or(0.0, |p| p.shares);
+            if (internal_shares - exchange_shares).abs() > SHARE_TOLERANCE {
+                let known = ours.or(theirs).expect("token comes from one of the ledgers");
+                positions.push(PositionDiff {
+                    ticker: known.ticker(),
+                    side: known.side,
+                    token_id: token_id.clone(),
+                    internal_shares,
+                    exchange_shares,
+                    price: if known.avg_price > 0.0 { known.avg_price } else { 0.5 },
+                });
+            }
+        }
+
+        let order_ids = |orders: &[OpenOrder]| -> std::collections::BTreeSet<String> {
+            orders.iter().map(|o| o.order_id.clone()).collect()
+        };
+        let ours = order_ids(&self.open_orders);
+        let theirs = order_ids(&venue.open_orders);
+
+        LedgerDiff {
+            positions,
+            unknown_orders: venue.open_orders.iter().filter(|o| !ours.contains(&o.order_id)).cloned().collect(),
+            missing_orders: ours.difference(&theirs).filter(|id| !placed_recently(id)).cloned().collect(),
+        }
+    }
+
+    /// Replace our view with the exchange's, keeping what we know of the
+    /// orders we placed that still rest, the reports not yet passed on and
+    /// the trades merged so far (`venue` only holds the latest)
+    pub fn adopt(&mut self, venue: Ledger) {
+        let mut origins = std::mem::take(&mut self.origins);
+        origins.retain(|id, _| venue.open_orders.iter().any(|o| &o.order_id == id));
+        let reports = std::mem::take(&mut self.reports);
+        let recent_trades = std::mem::take(&mut self.recent_trades);
+        *self = Ledger { origins, reports, ..venue };
+        self.merge_trades(&recent_trades);
+    }
+}
+
+/// Position where the ledger and the exchange disagree
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionDiff {
+    pub ticker: String,
+    pub side: Side,
+    pub token_id: String,
+    pub internal_shares: f64,
+    pub exchange_shares: f64,
+    pub price: f64, // Used to value the mismatch
+}
+
+/// Everything the ledger and the exchange disagree on
+#[derive(Debug, Default)]
+pub struct LedgerDiff {
+    pub positions: Vec<PositionDiff>,
+    /// Resting on the venue but not in the ledger (e.g. placed from the web UI)
+    pub unknown_orders: Vec<OpenOrder>,
+    /// In the ledger but no longer resting (filled or cancelled elsewhere)
+    pub missing_orders: Vec<String>,
+}
+
+impl LedgerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty() && self.unknown_orders.is_empty() && self.missing_orders.is_empty()
+    }
+
+    /// Dollar value of the mismatch: share differences plus unknown resting orders
+    pub fn mismatch_notional(&self) -> f64 {
+        let positions: f64 = self
+            .positions
+            .iter()
+            .map(|d| (d.internal_shares - d.exchange_shares).abs() * d.price)
+            .sum();
+        let orders: f64 = self
+            .unknown_orders
+            .iter()
+            .map(|o| (o.size - o.size_matched).max(0.0) * o.price)
+            .sum();
+        positions + orders
+    }
+}
+
+/// What the live reconciler does about a mismatch
+#[derive(Debug, Clone)]
+pub struct ReconcilePolicy {
+    pub interval: std::time::Duration,
+    /// Replace the ledger with exchange state after reporting a mismatch
+    pub adopt: bool,
+    /// Halt new entries while the mismatch is worth more than this ($)
+    pub halt_threshold: Option<f64>,
+}
+
+impl ReconcilePolicy {
+    /// Read `RECONCILE_INTERVAL_SECS`, `RECONCILE_ADOPT` and `RECONCILE_HALT_THRESHOLD`
+    pub fn from_env() -> Self {
+        Self {
+            interval: std::time::Duration::from_secs(
+                std::env::var("RECONCILE_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_RECONCILE_INTERVAL_SECS),
+            ),
+            adopt: std::env::var("RECONCILE_ADOPT")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            halt_threshold: std::env::var("RECONCILE_HALT_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+/// Net shares the trade log says we hold in `ticker`/`side` since `since`
+/// (`size` is logged in dollars, so shares = size / price)
+fn trade_log_shares(rows: &[TradeLogRow], ticker: &str, side: Side, since: DateTime<Utc>) -> f64 {
+    rows.iter()
+        .filter(|row| row.ticker == ticker && row.executed_at >= since && row.price > 0.0)
+        .map(|row| {
+            let shares = row.size / row.price;
+            match (row.side.as_str(), side) {
+                ("buy_yes", Side::Yes) | ("buy_no", Side::No) => shares,
+                ("sell_yes", Side::Yes) | ("sell_no", Side::No) => -shares,
+                _ => 0.0,
+            }
+        })
+        .sum()
+}
+
+/// Compare exchange positions with the trade log for every live window we trade
+pub fn compare_with_trade_log(
+    ledger: &Ledger,
+    rows: &[TradeLogRow],
+    markets: &HashMap<String, Vec<CachedMarket>>,
+    now: DateTime<Utc>,
+) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    for market in markets.values().flatten() {
+        if !market.is_live_at(now) {
+            continue;
+        }
+        let ticker = format!("{}-{}", market.asset, market.market_type.label());
+        for side in [Side::Yes, Side::No] {
+            let db_shares = trade_log_shares(rows, &ticker, side, market.start_date);
+            let exchange_shares: f64 = ledger
+                .positions
+                .values()
+                .filter(|p| p.condition_id == market.condition_id && p.side == side)
+                .map(|p| p.shares)
+                .sum();
+            if (db_shares - exchange_shares).abs() > SHARE_TOLERANCE {
+                discrepancies.push(Discrepancy {
+                    ticker: ticker.clone(),
+                    side,
+                    db_shares,
+                    exchange_shares,
+                });
+            }
+        }
+    }
+    discrepancies.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+    discrepancies
+}
+
+/// Fetch open orders, recent fills and positions from the venue, rebuild the
+/// ledger and compare it with the DB trade log
+pub async fn reconcile_startup<E: ExchangeClient>(
+    client: &E,
+    cache: &MarketCache,
+    trade_log: &[TradeLogRow],
+) -> ExchangeResult<(Ledger, StartupReport)> {
+    let open_orders = client.fetch_open_orders().await?;
+    let recent_trades = client.fetch_trades(None).await?;
+    let positions = client.fetch_positions().await?;
+
+    let snapshot = cache.load();
+    let ledger = Ledger::from_exchange(positions, open_orders, recent_trades, &snapshot.markets);
+    let discrepancies = compare_with_trade_log(&ledger, trade_log, &snapshot.markets, Utc::now());
+
+    let report = StartupReport {
+        positions: ledger.positions.len(),
+        open_orders: ledger.open_orders.len(),
+        recent_trades: ledger.recent_trades.len(),
+        unmatched: ledger.unmatched.len(),
+        discrepancies,
+    };
+    Ok((ledger, report))
+}
+
+/// Fetch the exchange's view and resolve it against the market cache, with
+/// the trades matched from `after` on
+pub(crate) async fn fetch_venue_ledger<E: ExchangeClient>(
+    client: &E,
+    cache: &MarketCache,
+    after: Option<DateTime<Utc>>,
+) -> ExchangeResult<Ledger> {
+    let open_orders = client.fetch_open_orders().await?;
+    let recent_trades = client.fetch_trades(after).await?;
+    let positions = client.fetch_positions().await?;
+    Ok(Ledger::from_exchange(positions, open_orders, recent_trades, &cache.load().markets))
+}
+
+/// Periodically compare the ledger with the exchange; warn with the diff in
+/// the activity log, and per `policy` adopt exchange state and/or halt entries
+pub async fn run_reconciler<E: ExchangeClient>(
+    client: Arc<E>,
+    cache: MarketCache,
+    ledger: SharedLedger,
+    risk_manager: Arc<RiskManager>,
+    db_logger: Arc<DbLogger>,
+    policy: ReconcilePolicy,
+) {
+    println!(
+        "🔄 Reconciler running every {}s (adopt: {}, halt above: {})",
+        policy.interval.as_secs(),
+        policy.adopt,
+        policy.halt_threshold.map_or("never".to_string(), |t| format!("${:.2}", t))
+    );
+    let mut interval = tokio::time::interval(policy.interval);
+    interval.tick().await; // Startup reconciliation just ran
+
+    loop {
+        interval.tick().await;
+
+        let after = ledger.lock().expect("ledger lock poisoned").trades_after();
+        let venue = match fetch_venue_ledger(client.as_ref(), &cache, after).await {
+            Ok(venue) => venue,
+            Err(e) => {
+                eprintln!("⚠️ Reconciler: failed to fetch exchange state: {}", e);
+                continue;
+            }
+        };
+
+        let (diff, stale) = {
+            let mut ledger = ledger.lock().expect("ledger lock poisoned");
+            let now = Utc::now();
+            let reports = ledger.apply_fills(&venue, &cache.load().markets, now);
+            for report in &reports {
+                settle_exposure(&risk_manager, report);
+            }
+            ledger.reports.extend(reports);
+            (ledger.diff(&venue, now), ledger.stale_orders(now))
+        };
+
+        // Cancelled orders leave the book: the next pass reports what they
+        // filled and releases the rest of their exposure
+        for order_id in &stale {
+            match client.cancel_order(order_id).await {
+                Ok(()) => println!("🔄 Reconciler: cancelled stale order {}", order_id),
+                Err(e) => eprintln!("⚠️ Reconciler: failed to cancel stale order {}: {}", order_id, e),
+            }
+        }
+        let notional = diff.mismatch_notional();
+
+        if !diff.is_empty() {
+            for d in &diff.positions {
+                println!("⚠️ Reconciler: {} {:?} ledger {:.2} shares, exchange {:.2} shares",
+                    d.ticker, d.side, d.internal_shares, d.exchange_shares);
+                db_logger.log_activity(
+                    "warning",
+                    "position",
+                    &format!("Position mismatch: {} {:?}", d.ticker, d.side),
+                    Some(format!(r#"{{"ticker": "{}", "side": "{:?}", "token_id": "{}", "ledger_shares": {:.4}, "exchange_shares": {:.4}}}"#,
+                        d.ticker, d.side, d.token_id, d.internal_shares, d.exchange_shares)),
+                );
+            }
+            if !diff.unknown_orders.is_empty() || !diff.missing_orders.is_empty() {
+                let unknown: Vec<&str> = diff.unknown_orders.iter().map(|o| o.order_id.as_str()).collect();
+                println!("⚠️ Reconciler: {} unknown resting orders, {} orders no longer resting",
+                    unknown.len(), diff.missing_orders.len());
+                db_logger.log_activity(
+                    "warning",
+                    "trade",
+                    "Open order mismatch",
+                    Some(format!(r#"{{"unknown_orders": {:?}, "missing_orders": {:?}}}"#, unknown, diff.missing_orders)),
+                );
+            }
+
+            if policy.adopt {
+                {
+                    let mut ledger = ledger.lock().expect("ledger lock poisoned");
+                    ledger.adopt(venue);
+                    // Exposure follows what we now hold
+                    risk_manager.set_positions(ledger.live_positions(Utc::now()));
+                }
+                println!("🔄 Reconciler: adopted exchange state");
+                db_logger.log_activity("info", "position", "Adopted exchange state after mismatch", None);
+            }
+        }
+
+        // Halt while the mismatch is above threshold; resume once it clears
+        if let Some(threshold) = policy.halt_threshold {
+            if notional > threshold {
+                let reason = format!("ledger/exchange mismatch ${:.2} exceeds ${:.2}", notional, threshold);
+                if risk_manager.halt(&reason) {
+                    db_logger.log_activity(
+                        "error",
+                        "system",
+                        &format!("Trading halted: {}", reason),
+                        Some(format!(r#"{{"mismatch": {:.2}, "threshold": {:.2}}}"#, notional, threshold)),
+                    );
+                }
+            } else if risk_manager.resume() {
+                db_logger.log_activity("info", "system", "Trading resumed: ledger matches exchange", None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::SignalKind;
+    use crate::polymarket::test_market;
+
+    fn row(ticker: &str, side: &str, price: f64, size: f64, executed_at: DateTime<Utc>) -> TradeLogRow {
+        TradeLogRow { ticker: ticker.to_string(), side: side.to_string(), price, size, executed_at }
+    }
+
+    /// 20-share bid at 40¢, as execution records it
+    fn resting_bid(order_id: &str, token_id: &str) -> OpenOrder {
+        OpenOrder {
+            order_id: order_id.to_string(),
+            token_id: token_id.to_string(),
+            side: OrderSide::BUY,
+            price: 0.40,
+            size: 20.0,
+            size_matched: 0.0,
+        }
+    }
+
+    fn origin(symbol: u64, expires_at: DateTime<Utc>) -> OrderOrigin {
+        OrderOrigin { kind: SignalKind::Momentum, symbol, side: 0, fee_rate: 0.0, cost_basis: 0.0, expires_at }
+    }
+
+    #[test]
+    fn test_ledger_resolves_tokens_against_markets() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let down_token = market.token_ids[1].clone();
+        let markets = HashMap::from([("BTC".to_string(), vec![market])]);
+
+        let positions = vec![
+            TokenPosition { token_id: down_token.clone(), condition_id: String::new(), size: 20.0, avg_price: 0.5 },
+            TokenPosition { token_id: "stale".to_string(), condition_id: String::new(), size: 5.0, avg_price: 0.9 },
+        ];
+        let ledger = Ledger::from_exchange(positions, vec![], vec![], &markets);
+
+        assert_eq!(ledger.positions[&down_token].side, Side::No);
+        assert_eq!(ledger.unmatched.len(), 1);
+        assert_eq!(ledger.live_positions(now).count(), 1);
+    }
+
+    #[test]
+    fn test_discrepancy_against_trade_log() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let ticker = format!("BTC-{}", market.market_type.label());
+        let up_token = market.token_ids[0].clone();
+        let started = market.start_date;
+        let markets = HashMap::from([("BTC".to_string(), vec![market])]);
+
+        // DB: bought $10 of Up at 0.50 = 20 shares; exchange agrees
+        let rows = vec![row(&ticker, "buy_yes", 0.50, 10.0, started + chrono::Duration::seconds(5))];
+        let positions = vec![TokenPosition { token_id: up_token, condition_id: String::new(), size: 20.0, avg_price: 0.5 }];
+        let ledger = Ledger::from_exchange(positions, vec![], vec![], &markets);
+        assert!(compare_with_trade_log(&ledger, &rows, &markets, now).is_empty());
+
+        // Exchange lost the position (e.g. sold from the web UI)
+        let empty = Ledger::from_exchange(vec![], vec![], vec![], &markets);
+        let diffs = compare_with_trade_log(&empty, &rows, &markets, now);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].side, Side::Yes);
+        assert!((diffs[0].db_shares - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_diff_flags_drift_outside_grace_period() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let up_token = market.token_ids[0].clone();
+        let markets = HashMap::from([("BTC".to_string(), vec![market.clone()])]);
+
+        // We recorded 20 shares; the venue only filled 8 (partial fill)
+        let mut ours = Ledger::default();
+        ours.record_fill(&market, &up_token, OrderSide::BUY, 20.0, 0.5, now - chrono::Duration::minutes(2));
+        let positions = vec![TokenPosition { token_id: up_token.clone(), condition_id: String::new(), size: 8.0, avg_price: 0.5 }];
+        let venue = Ledger::from_exchange(positions, vec![], vec![], &markets);
+
+        let diff = ours.diff(&venue, now);
+        assert_eq!(diff.positions.len(), 1);
+        assert!((diff.mismatch_notional() - 6.0).abs() < 1e-9);
+
+        // A fresh fill is within the grace period
+        ours.record_fill(&market, &up_token, OrderSide::BUY, 2.0, 0.5, now);
+        assert!(ours.diff(&venue, now).is_empty());
+
+        ours.adopt(venue);
+        assert!((ours.positions[&up_token].shares - 8.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_orders_reach_positions_as_they_fill() {
+        use crate::exchange::SimulatedExchange;
+        use crate::polymarket::MarketStore;
+
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let up_token = market.token_ids[0].clone();
+        let cache: MarketCache = Arc::new(MarketStore::new());
+        cache.publish(HashMap::from([("BTC".to_string(), vec![market.clone()])]));
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book(&up_token, 0.44, 0.46);
+
+        // A GTC bid below the ask rests: no shares yet, and nothing to report
+        let mut ours = Ledger::default();
+        let order_id = sim.place_order(&market, &up_token, OrderSide::BUY, 20.0, 0.40).await.unwrap();
+        ours.record_order(resting_bid(&order_id, &up_token), origin(115, market.end_date), now);
+        let venue = fetch_venue_ledger(&sim, &cache, ours.trades_after()).await.unwrap();
+        assert!(ours.apply_fills(&venue, &cache.load().markets, now).is_empty());
+        assert!(ours.positions.is_empty());
+        assert!(ours.diff(&venue, now).is_empty());
+
+        // Partially matched while resting: reported as it matches
+        sim.match_order(&order_id, 8.0).unwrap();
+        let later = now + chrono::Duration::minutes(1);
+        let venue = fetch_venue_ledger(&sim, &cache, ours.trades_after()).await.unwrap();
+        let reports = ours.apply_fills(&venue, &cache.load().markets, later);
+        let [ExecutionReport::Filled(fill)] = reports.as_slice() else { panic!("expected one fill: {:?}", reports) };
+        assert!((fill.shares - 8.0).abs() < 1e-9 && (fill.remaining - 12.0).abs() < 1e-9);
+        assert!((fill.cost - 3.2).abs() < 1e-9);
+        assert!((ours.positions[&up_token].shares - 8.0).abs() < 1e-9);
+        assert_eq!(ours.open_orders.len(), 1);
+        assert!(ours.diff(&venue, later + chrono::Duration::minutes(1)).is_empty());
+
+        // Filled: the rest comes from its trades and the order is dropped
+        sim.match_order(&order_id, 12.0).unwrap();
+        let later = later + chrono::Duration::minutes(1);
+        let venue = fetch_venue_ledger(&sim, &cache, ours.trades_after()).await.unwrap();
+        let reports = ours.apply_fills(&venue, &cache.load().markets, later);
+        let [ExecutionReport::Filled(fill)] = reports.as_slice() else { panic!("expected one fill: {:?}", reports) };
+        assert!((fill.shares - 12.0).abs() < 1e-9 && fill.remaining == 0.0);
+        assert!((ours.positions[&up_token].shares - 20.0).abs() < 1e-9);
+        assert!(ours.open_orders.is_empty() && ours.placed_at.is_empty() && ours.origins.is_empty());
+        assert!(ours.diff(&venue, later + chrono::Duration::minutes(1)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_orders_leaving_the_book_unfilled_are_rejected() {
+        use crate::exchange::SimulatedExchange;
+        use crate::polymarket::MarketStore;
+
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let up_token = market.token_ids[0].clone();
+        let cache: MarketCache = Arc::new(MarketStore::new());
+        cache.publish(HashMap::from([("BTC".to_string(), vec![market.clone()])]));
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book(&up_token, 0.44, 0.46);
+
+        let mut ours = Ledger::default();
+        let order_id = sim.place_order(&market, &up_token, OrderSide::BUY, 20.0, 0.40).await.unwrap();
+        ours.record_order(resting_bid(&order_id, &up_token), origin(115, market.end_date), now);
+        sim.match_order(&order_id, 5.0).unwrap();
+
+        // Left resting too long: cancelled
+        assert!(ours.stale_orders(now + chrono::Duration::seconds(STALE_ORDER_SECS - 1)).is_empty());
+        for stale in ours.stale_orders(now + chrono::Duration::seconds(STALE_ORDER_SECS)) {
+            sim.cancel_order(&stale).await.unwrap();
+        }
+        assert!(sim.fetch_open_orders().await.unwrap().is_empty());
+
+        // Within the grace period the venue may just not list it yet
+        let venue = fetch_venue_ledger(&sim, &cache, ours.trades_after()).await.unwrap();
+        let reports = ours.apply_fills(&venue, &cache.load().markets, now);
+        assert!(matches!(reports.as_slice(), [ExecutionReport::Filled(_)]));
+        assert_eq!(ours.open_orders.len(), 1);
+
+        // Past it, the unfilled rest is reported refused
+        let later = now + chrono::Duration::minutes(1);
+        let reports = ours.apply_fills(&venue, &cache.load().markets, later);
+        let [ExecutionReport::Rejected(rejection)] = reports.as_slice() else { panic!("expected a rejection: {:?}", reports) };
+        assert_eq!((rejection.symbol, rejection.filled), (115, 5.0));
+        assert!(ours.open_orders.is_empty() && ours.origins.is_empty());
+    }
+
+    #[test]
+    fn test_trades_fetched_incrementally_add_up() {
+        let now = Utc::now();
+        let market = test_market("BTC", now + chrono::Duration::minutes(10));
+        let up_token = market.token_ids[0].clone();
+        let markets = HashMap::from([("BTC".to_string(), vec![market])]);
+        let trade = |trade_id: &str, size: f64, matched_at: DateTime<Utc>| TradeFill {
+            trade_id: trade_id.to_string(),
+            order_id: "ours-1".to_string(),
+            token_id: up_token.clone(),
+            side: OrderSide::BUY,
+            price: 0.40,
+            size,
+            matched_at,
+        };
+
+        let mut ours = Ledger::default();
+        ours.record_order(resting_bid("ours-1", &up_token), origin(115, now + chrono::Duration::minutes(10)), now);
+        let resting = OpenOrder { size_matched: 8.0, ..resting_bid("ours-1", &up_token) };
+        let venue = Ledger::from_exchange(vec![], vec![resting], vec![trade("t1", 8.0, now)], &markets);
+        ours.apply_fills(&venue, &markets, now);
+        assert_eq!(ours.trades_after(), Some(now));
+
+        // The next fetch resumes at the last trade: it comes back alongside
+        // the new one, and the order's earlier trade still counts
+        let later = now + chrono::Duration::seconds(30);
+        let venue = Ledger::from_exchange(vec![], vec![], vec![trade("t2", 12.0, later), trade("t1", 8.0, now)], &markets);
+        let reports = ours.apply_fills(&venue, &markets, later);
+        let [ExecutionReport::Filled(fill)] = reports.as_slice() else { panic!("expected one fill: {:?}", reports) };
+        assert!((fill.shares - 12.0).abs() < 1e-9 && fill.remaining == 0.0);
+        assert!((ours.positions[&up_token].shares - 20.0).abs() < 1e-9);
+        assert_eq!(ours.recent_trades.len(), 2);
+        assert_eq!(ours.trades_after(), Some(later));
+
+        // Trades long before the newest are forgotten
+        let much_later = later + chrono::Duration::seconds(TRADE_RETENTION_SECS);
+        ours.merge_trades(&[TradeFill { order_id: "ours-2".to_string(), ..trade("t3", 5.0, much_later) }]);
+        let kept: Vec<&str> = ours.recent_trades.iter().map(|t| t.trade_id.as_str()).collect();
+        assert_eq!(kept, ["t3", "t2"]);
+    }
+
+    #[test]
+    fn test_diff_reports_unknown_orders() {
+        let now = Utc::now();
+        let order = OpenOrder {
+            order_id: "web-ui-1".to_string(),
+            token_id: "111".to_string(),
+            side: OrderSide::BUY,
+            price: 0.40,
+            size: 25.0,
+            size_matched: 0.0,
+        };
+        let venue = Ledger { open_orders: vec![order], ..Default::default() };
+
+        let diff = Ledger::default().diff(&venue, now);
+        assert_eq!(diff.unknown_orders.len(), 1);
+        assert!((diff.mismatch_notional() - 10.0).abs() < 1e-9);
+    }
+}
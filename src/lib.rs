@@ -0,0 +1,28 @@
+//! The bot's modules, shared by the `trading_bot` binary (`main.rs`), the
+//! benches and the integration tests
+
+pub mod approvals;
+pub mod auth;
+pub mod binance_book;
+pub mod contracts;
+pub mod database;
+pub mod endpoints;
+pub mod exchange;
+pub mod execution;
+pub mod ingestion;
+pub mod latency;
+pub mod ledger;
+pub mod momentum;
+pub mod polymarket;
+pub mod prices;
+pub mod pricing;
+pub mod redeem;
+pub mod risk;
+pub mod rolling;
+pub mod spot_feeds;
+pub mod spot_index;
+pub mod strategy;
+pub mod timesync;
+pub mod types;
+pub mod volatility;
+pub mod window_open;
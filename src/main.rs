@@ -1,13 +1,21 @@
-mod database;
-mod execution;
-mod ingestion;
-mod polymarket;
-mod prices;
-mod risk;
-mod strategy;
-mod types;
+use trading_bot::{
+    approvals,
+    contracts,
+    database,
+    exchange,
+    execution,
+    ingestion,
+    ledger,
+    polymarket,
+    redeem,
+    risk,
+    strategy,
+    timesync,
+    window_open,
+};
 
 use database::{upsert_heartbeat, DbLogger};
+use exchange::ExchangeClient;
 use polymarket::PolymarketClient;
 
 use risk::RiskManager;
@@ -22,6 +30,19 @@ use std::thread;
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
+
+    // One-shot commands: `check-allowances` reports, `approve` also sends
+    // the missing approval transactions
+    match std::env::args().nth(1).as_deref() {
+        Some("check-allowances") => return run_allowances_command(false).await,
+        Some("approve") => return run_allowances_command(true).await,
+        Some(other) => {
+            eprintln!("Unknown command '{}' (expected: check-allowances, approve)", other);
+            return;
+        }
+        None => {}
+    }
+
     println!("Starting Low-Latency Polymarket Arbitrage Engine...");
 
     // Initialize Database Connection Pool
@@ -41,16 +62,30 @@ async fn main() {
 
     // Initialize Risk Manager with starting balance ($58.36)
     const STARTING_BALANCE: f64 = 58.36;
-    let risk_manager = Arc::new(RiskManager::new(STARTING_BALANCE));
+    // Correlated exposure cap across assets/expiries (MAX_CORRELATED_EXPOSURE, $)
+    let correlated_cap = std::env::var("MAX_CORRELATED_EXPOSURE")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(risk::DEFAULT_CORRELATED_EXPOSURE_CAP);
+    let risk_manager = Arc::new(
+        RiskManager::new(STARTING_BALANCE).with_correlated_exposure_cap(correlated_cap),
+    );
+
+    // Exchange clock offsets, shared by ingestion, strategy and execution
+    let clock: timesync::SharedClock = Arc::new(timesync::TimeSync::new());
+    tokio::spawn(timesync::run_time_sync(
+        clock.clone(),
+        Some(db_logger.clone()),
+        timesync::SyncPolicy::from_env(),
+    ));
 
     // Initialize Market Cache (Shared between threads)
-    let market_cache: polymarket::MarketCache =
-        Arc::new(std::sync::RwLock::new(std::collections::HashMap::new()));
+    let market_cache: polymarket::MarketCache = Arc::new(polymarket::MarketStore::new());
 
     // Initialize Polymarket Client (if in live mode)
     let poly_client = if execution::LIVE_MODE {
-        match PolymarketClient::from_env() {
-            Some(client) => {
+        match PolymarketClient::from_env().await {
+            Ok(client) => {
                 println!("✅ Polymarket API client initialized");
                 let client_arc = Arc::new(client);
 
@@ -72,18 +107,13 @@ async fn main() {
                 let cache_clone = market_cache.clone();
                 let client_clone = client_arc.clone();
                 tokio::spawn(async move {
-                    PolymarketClient::start_market_cache_updater(
-                        client_clone,
-                        cache_clone,
-                        vec![], // Assets hardcoded in updater
-                    )
-                    .await;
+                    polymarket::start_market_cache_updater(client_clone, cache_clone).await;
                 });
 
                 Some(client_arc)
             }
-            None => {
-                eprintln!("❌ POLYMARKET_API_KEY, POLYMARKET_API_SECRET, or POLYMARKET_PASSPHRASE not set!");
+            Err(e) => {
+                eprintln!("❌ Polymarket client unavailable: {}", e);
                 eprintln!("   Falling back to DRY RUN mode");
                 None
             }
@@ -92,6 +122,97 @@ async fn main() {
         None
     };
 
+    // Startup reconciliation: rebuild what we hold from the exchange before
+    // trading resumes, and flag disagreements with the DB trade log
+    let mut recovered_positions = Vec::new();
+    let ledger: ledger::SharedLedger = Arc::new(std::sync::Mutex::new(ledger::Ledger::default()));
+    if let Some(ref client) = poly_client {
+        // Positions are resolved against the cache, so wait for the first refresh
+        let wait_start = std::time::Instant::now();
+        while market_cache.version() == 0 && wait_start.elapsed() < std::time::Duration::from_secs(30) {
+            tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+        }
+
+        let since = chrono::Utc::now() - chrono::Duration::days(1);
+        let trade_log = database::fetch_trade_logs_since(&db_pool, since).await.unwrap_or_else(|e| {
+            eprintln!("⚠️ Could not read trade log for reconciliation: {:?}", e);
+            Vec::new()
+        });
+
+        match ledger::reconcile_startup(client.as_ref(), &market_cache, &trade_log).await {
+            Ok((startup, report)) => {
+                println!(
+                    "🔄 Reconciled with exchange: {} positions, {} open orders, {} recent fills, {} untracked tokens",
+                    report.positions, report.open_orders, report.recent_trades, report.unmatched
+                );
+                db_logger.log_activity(
+                    "info",
+                    "system",
+                    &format!("Startup reconciliation: {} positions, {} open orders", report.positions, report.open_orders),
+                    Some(format!(
+                        r#"{{"positions": {}, "open_orders": {}, "recent_trades": {}, "untracked_tokens": {}, "discrepancies": {}}}"#,
+                        report.positions, report.open_orders, report.recent_trades, report.unmatched, report.discrepancies.len()
+                    )),
+                );
+                for d in &report.discrepancies {
+                    println!("⚠️ Reconciliation mismatch {} {:?}: DB {:.2} shares, exchange {:.2} shares",
+                        d.ticker, d.side, d.db_shares, d.exchange_shares);
+                    db_logger.log_activity(
+                        "warning",
+                        "position",
+                        &format!("Reconciliation mismatch: {} {:?}", d.ticker, d.side),
+                        Some(format!(r#"{{"ticker": "{}", "side": "{:?}", "db_shares": {:.4}, "exchange_shares": {:.4}}}"#,
+                            d.ticker, d.side, d.db_shares, d.exchange_shares)),
+                    );
+                }
+                recovered_positions = startup.live_positions(chrono::Utc::now()).cloned().collect();
+                risk_manager.set_positions(&recovered_positions);
+                *ledger.lock().expect("ledger lock poisoned") = startup;
+            }
+            Err(e) => {
+                eprintln!("❌ Startup reconciliation failed: {}", e);
+                db_logger.log_activity("error", "system", &format!("Startup reconciliation failed: {}", e), None);
+            }
+        }
+
+        // Orders fail at settlement without approvals, so check them up front
+        if std::env::var("POLYGON_RPC_URL").is_ok() {
+            match check_allowances().await {
+                Ok(report) => {
+                    report.print();
+                    if !report.is_ready() {
+                        let missing: Vec<&str> = report.missing().iter().map(|(s, _, _)| s.name).collect();
+                        db_logger.log_activity(
+                            "warning",
+                            "system",
+                            &format!("Missing approvals for {}", missing.join(", ")),
+                            Some(format!(r#"{{"holder": "{:?}", "missing": {}}}"#, report.holder, missing.len())),
+                        );
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Could not check allowances: {}", e),
+            }
+        }
+
+        // Redeem resolved winning positions (opt-in: needs an RPC endpoint)
+        if std::env::var("REDEEM_ENABLED").map(|v| v == "true" || v == "1").unwrap_or(false) {
+            match start_redeemer(db_logger.clone()).await {
+                Ok(()) => {}
+                Err(e) => eprintln!("❌ Redeemer not started: {}", e),
+            }
+        }
+
+        // Keep reconciling while running
+        tokio::spawn(ledger::run_reconciler(
+            client.clone(),
+            market_cache.clone(),
+            ledger.clone(),
+            risk_manager.clone(),
+            db_logger.clone(),
+            ledger::ReconcilePolicy::from_env(),
+        ));
+    }
+
     // PnL Tracking - use starting balance from risk manager
     let starting_balance = 58.36;
     let _total_balance: f64 = starting_balance;
@@ -100,14 +221,27 @@ async fn main() {
     let mut _last_logged_balance: f64 = starting_balance;
 
     // 1. Ingestion -> Strategy Ring Buffer (Capacity 1024)
-    let (ingestion_prod, strategy_cons) = RingBuffer::<ingestion::MarketUpdate>::new(1024);
+    let (ingestion_prod, strategy_cons) = RingBuffer::<ingestion::MarketEvent>::new(1024);
 
     // 2. Strategy -> Execution Ring Buffer (Capacity 1024)
     let (strategy_prod, execution_cons) = RingBuffer::<execution::TradeInstruction>::new(1024);
 
+    // 3. Execution -> Strategy fills and rejections (Capacity 1024)
+    let (report_prod, report_cons) = RingBuffer::<execution::ExecutionReport>::new(1024);
+
+    // Capture each Up/Down window's open price (strike) as it starts
+    tokio::spawn(window_open::run_window_open_capture(
+        market_cache.clone(),
+        clock.clone(),
+        Some(db_pool.clone()),
+        Some(db_logger.clone()),
+    ));
+
     // Spawn Ingestion Thread
+    let ingestion_clock = clock.clone();
+    let ingestion_cache = market_cache.clone();
     tokio::spawn(async move {
-        ingestion::run_ingestion(ingestion_prod).await;
+        ingestion::run_ingestion(ingestion_prod, ingestion_clock, ingestion_cache).await;
     });
 
     // Spawn Execution Thread
@@ -115,19 +249,19 @@ async fn main() {
     let exec_pool = db_pool.clone();
     let exec_risk = Arc::clone(&risk_manager);
     let exec_cache = market_cache.clone();
+    let exec_ledger = ledger.clone();
+    let exec_clock = clock.clone();
 
-    // We need to pass the client to execution if it exists
-    // Note: execution::run_execution signature needs to change to accept client and cache
+    let exec_ctx = execution::ExecutionContext {
+        client: poly_client,
+        market_cache: exec_cache,
+        ledger: exec_ledger,
+        risk_manager: exec_risk,
+        reports: report_prod,
+        clock: exec_clock,
+    };
     tokio::spawn(async move {
-        execution::run_execution(
-            execution_cons,
-            exec_logger,
-            exec_pool,
-            exec_risk,
-            poly_client,
-            exec_cache,
-        )
-        .await;
+        execution::run_execution(execution_cons, exec_ctx, exec_logger, exec_pool).await;
     });
 
     // Spawn Heartbeat Task (every 10 seconds)
@@ -159,8 +293,11 @@ async fn main() {
         strategy::run_strategy(
             strategy_cons,
             strategy_prod,
-            strategy_logger,
+            report_cons,
+            Some(strategy_logger),
             strategy_cache,
+            recovered_positions,
+            clock,
         );
     });
 
@@ -170,23 +307,81 @@ async fn main() {
     }
 }
 
+/// Transaction sender for the configured wallet (or its proxy wallet)
+async fn chain_sender() -> exchange::ExchangeResult<(contracts::TxSender, contracts::ContractConfig)> {
+    let rpc_url = std::env::var("POLYGON_RPC_URL").map_err(|_| "POLYGON_RPC_URL not set")?;
+    let private_key = std::env::var("POLYMARKET_PRIVATE_KEY").map_err(|_| "POLYMARKET_PRIVATE_KEY not set")?;
+    let config = contracts::ContractConfig::from_env()?;
+
+    let client = contracts::chain_client(&rpc_url, &private_key).await?;
+    let sender = contracts::TxSender::new(client, &config, contracts::proxy_wallet_from_env()?);
+    Ok((sender, config))
+}
+
+/// Spawn the redemption task for the configured wallet (or its proxy wallet)
+async fn start_redeemer(db_logger: Arc<DbLogger>) -> exchange::ExchangeResult<()> {
+    let (sender, config) = chain_sender().await?;
+    let redeemer = redeem::Redeemer::new(sender, &config);
+    tokio::spawn(redeem::run_redeemer(redeemer, db_logger));
+    Ok(())
+}
+
+/// Current USDC/CTF approvals of the configured wallet
+async fn check_allowances() -> exchange::ExchangeResult<approvals::AllowanceReport> {
+    let (sender, config) = chain_sender().await?;
+    approvals::check_allowances(&sender, &config).await
+}
+
+/// `check-allowances` / `approve`: report approvals and optionally send the
+/// missing ones
+async fn run_allowances_command(send: bool) {
+    let result: exchange::ExchangeResult<()> = async {
+        let (sender, config) = chain_sender().await?;
+        let report = approvals::check_allowances(&sender, &config).await?;
+        report.print();
+        if !send || report.is_ready() {
+            return Ok(());
+        }
+
+        let txs = approvals::approve_missing(&sender, &config, &report).await?;
+        for tx in &txs {
+            println!("   ✅ Mined {:?}", tx);
+        }
+        approvals::check_allowances(&sender, &config).await?.print();
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
 /// Fallback: Run without database logging
 async fn run_without_db() {
-    let (ingestion_prod, strategy_cons) = RingBuffer::<ingestion::MarketUpdate>::new(1024);
+    let (ingestion_prod, strategy_cons) = RingBuffer::<ingestion::MarketEvent>::new(1024);
     let (strategy_prod, execution_cons) = RingBuffer::<execution::TradeInstruction>::new(1024);
+    let (report_prod, report_cons) = RingBuffer::<execution::ExecutionReport>::new(1024);
+
+    let clock: timesync::SharedClock = Arc::new(timesync::TimeSync::new());
+    tokio::spawn(timesync::run_time_sync(clock.clone(), None, timesync::SyncPolicy::from_env()));
 
+    // No cache updater in fallback mode for now (empty store)
+    let cache = Arc::new(polymarket::MarketStore::new());
+    let ingestion_cache = cache.clone();
+    let ingestion_clock = clock.clone();
+    tokio::spawn(window_open::run_window_open_capture(cache.clone(), clock.clone(), None, None));
     tokio::spawn(async move {
-        ingestion::run_ingestion(ingestion_prod).await;
+        ingestion::run_ingestion(ingestion_prod, ingestion_clock, ingestion_cache).await;
     });
 
     tokio::spawn(async move {
-        execution::run_execution_no_db(execution_cons).await;
+        execution::run_execution_no_db(execution_cons, report_prod).await;
     });
 
     thread::spawn(move || {
-        // No cache in fallback mode for now, or create empty one
-        let cache = Arc::new(std::sync::RwLock::new(std::collections::HashMap::new()));
-        strategy::run_strategy_no_db(strategy_cons, strategy_prod, cache);
+        strategy::run_strategy(strategy_cons, strategy_prod, report_cons, None, cache, Vec::new(), clock);
     });
 
     loop {
@@ -0,0 +1,528 @@
+//! Momentum strategy
+//!
+//! Buys the side of a live Up/Down window that spot is trending towards
+//! (price change over the last 10 minutes for 60-min windows, 3 minutes for
+//! 15-min windows), and exits through a stop-loss when momentum reverses
+//! close to expiry.
+//!
+//! Entry and stop-loss thresholds are z-scores (`volatility.rs`): the move
+//! divided by the move expected over the time left in the window, using the
+//! asset's EWMA volatility for that horizon. Entries wait for the estimate
+//! to warm up; stop-losses fall back to the asset's default volatility.
+
+use crate::database::StrategyLogMsg;
+use crate::execution::{Fill, Rejection, SignalKind, TradeInstruction};
+use crate::ingestion::{BarInterval, MarketUpdate};
+use crate::ledger::LedgerPosition;
+use crate::polymarket::CachedMarket;
+use crate::strategy::{get_asset_name, get_asset_symbol, Strategy, StrategyContext};
+use crate::types::{MarketType, Side};
+use crate::volatility::z_score;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Configuration for the strategy (lookbacks must be in `rolling::WINDOWS_MS`)
+const MOMENTUM_WINDOW_60MIN_SECS: u64 = 600;  // 10 minutes for 60-min markets
+const MOMENTUM_WINDOW_15MIN_SECS: u64 = 180;  // 3 minutes for 15-min markets
+const MOMENTUM_Z_60MIN: f64 = 0.5;  // Half the expected move over the rest of the window
+const MOMENTUM_Z_15MIN: f64 = 0.5;
+const TRADE_SIZE_DOLLARS: u64 = 10;
+const MAX_POSITIONS: usize = 3;
+const COOLDOWN_SECS: u64 = 5;
+
+/// Stop-loss configuration
+/// Only check stop-loss in the LAST X minutes before market expiry
+const STOP_LOSS_Z_15MIN: f64 = 1.5;   // Reversal of 1.5 expected moves for 15-min
+const STOP_LOSS_Z_60MIN: f64 = 1.0;   // Reversal of 1 expected move for 60-min
+const STOP_LOSS_ACTIVE_15MIN_SECS: u64 = 180;   // Last 3 min of 15-min market (12 min safe)
+const STOP_LOSS_ACTIVE_60MIN_SECS: u64 = 900;   // Last 15 min of 60-min market (45 min safe)
+const MARKET_DURATION_15MIN_SECS: u64 = 900;    // 15 min = 900 sec
+const MARKET_DURATION_60MIN_SECS: u64 = 3600;   // 60 min = 3600 sec
+
+/// Represents an open position
+#[derive(Debug, Clone)]
+struct Position {
+    asset_symbol: u64,      // 1=BTC, 2=ETH, 3=SOL, 4=XRP
+    market_type: u64,       // 15 or 60
+    side: u8,               // 0 = YES (up), 1 = NO (down)
+    entry_momentum: f64,    // Momentum at entry
+    entry_time: Instant,
+    entry_price_cents: u64, // Signal price until the fill reports the real one
+    expires_at: Option<DateTime<Utc>>, // Expiry of the window entered (if known)
+    filled: bool,           // Entry matched on the venue (shares held to sell)
+}
+
+/// Strategy position for a position recovered from the exchange.
+/// Entry momentum is unknown, so the stop-loss measures reversal from flat.
+fn recovered_position(pos: &LedgerPosition) -> Option<Position> {
+    Some(Position {
+        asset_symbol: get_asset_symbol(&pos.asset)?,
+        market_type: pos.market_type.minutes()?,
+        side: if pos.side == Side::Yes { 0 } else { 1 },
+        entry_momentum: 0.0,
+        entry_time: Instant::now(),
+        entry_price_cents: (pos.avg_price * 100.0).round() as u64,
+        expires_at: Some(pos.expires_at),
+        filled: true,
+    })
+}
+
+/// Volatility per second of an asset for a horizon: the EWMA estimate once
+/// warmed up, else the asset's default
+fn horizon_sigma(ctx: &StrategyContext, asset_symbol: u64, horizon: MarketType) -> f64 {
+    ctx.volatility(asset_symbol, horizon)
+        .unwrap_or_else(|| crate::latency::default_volatility(get_asset_name(asset_symbol)))
+}
+
+/// Momentum over the lookback and its z-score against the time left
+#[derive(Debug, Clone, Copy)]
+struct Signal {
+    momentum: f64,
+    z: f64,
+}
+
+/// Momentum strategy state (positions and rate limiting are per asset)
+pub struct MomentumStrategy {
+    open_positions: Vec<Position>,
+    last_trade_times: HashMap<u64, Instant>,
+    tick_count: u64,
+}
+
+impl MomentumStrategy {
+    /// Seeded with positions held on the exchange from before a restart
+    pub fn new(recovered: &[LedgerPosition]) -> Self {
+        let mut open_positions: Vec<Position> = Vec::with_capacity(MAX_POSITIONS * 4);
+        open_positions.extend(recovered.iter().filter_map(recovered_position));
+        if !open_positions.is_empty() {
+            println!("[STRATEGY] Resumed {} open positions from the exchange", open_positions.len());
+        }
+        Self { open_positions, last_trade_times: HashMap::new(), tick_count: 0 }
+    }
+
+    /// Exit positions of `asset_symbol` whose momentum reversed in the last
+    /// minutes before expiry
+    fn check_stop_loss(&mut self, ctx: &mut StrategyContext, asset_symbol: u64, momentum_60: f64, momentum_15: f64) {
+        let mut positions_to_close: Vec<usize> = Vec::new();
+
+        for (idx, pos) in self.open_positions.iter().enumerate() {
+            // Only check filled positions for the current asset: an entry
+            // still resting has no shares to sell
+            if pos.asset_symbol != asset_symbol || !pos.filled {
+                continue;
+            }
+
+            let current_momentum = if pos.market_type == 60 { momentum_60 } else { momentum_15 };
+
+            // Get market duration and danger zone based on market type
+            let (market_duration, danger_zone, threshold, horizon) = if pos.market_type == 60 {
+                (Duration::from_secs(MARKET_DURATION_60MIN_SECS),
+                 Duration::from_secs(STOP_LOSS_ACTIVE_60MIN_SECS),
+                 STOP_LOSS_Z_60MIN,
+                 MarketType::Hourly)
+            } else {
+                (Duration::from_secs(MARKET_DURATION_15MIN_SECS),
+                 Duration::from_secs(STOP_LOSS_ACTIVE_15MIN_SECS),
+                 STOP_LOSS_Z_15MIN,
+                 MarketType::Fifteen)
+            };
+
+            // Only check stop-loss in the LAST X minutes (danger zone).
+            // Use the window's real expiry when known, else assume the
+            // position was opened at the start of a full window.
+            let time_until_expiry = match pos.expires_at {
+                Some(expires_at) => (expires_at - ctx.now).to_std().unwrap_or(Duration::ZERO),
+                None => market_duration.saturating_sub(pos.entry_time.elapsed()),
+            };
+            if time_until_expiry > danger_zone {
+                continue; // Still in safe zone, no stop-loss check
+            }
+
+            // Check if momentum has reversed significantly
+            let reversal = if pos.side == 0 {
+                // We bought YES (bet on UP) - check if momentum went negative
+                pos.entry_momentum - current_momentum
+            } else {
+                // We bought NO (bet on DOWN) - check if momentum went positive
+                current_momentum - pos.entry_momentum
+            };
+
+            // In expected moves over the time left
+            let reversal_z = z_score(reversal, horizon_sigma(ctx, asset_symbol, horizon), time_until_expiry.as_secs_f64());
+
+            if reversal_z >= threshold {
+                let pos_asset_name = get_asset_name(pos.asset_symbol);
+                println!("[STOP-LOSS] {} Position {} triggered! Reversal: {:.4}% (z={:.2}, {}s until expiry)",
+                    pos_asset_name, idx, reversal * 100.0, reversal_z, time_until_expiry.as_secs());
+                positions_to_close.push(idx);
+
+                // Send SELL instruction (encode asset + market type)
+                let sell_instruction = TradeInstruction {
+                    symbol: pos.asset_symbol * 100 + pos.market_type + 1000, // e.g., 1160 = BTC 60-min SELL
+                    side: if pos.side == 0 { 1 } else { 0 }, // Opposite of entry
+                    price_cents: 40, // Sell at whatever price (stop-loss)
+                    size: TRADE_SIZE_DOLLARS,
+                    fair_prob: None,
+                    kind: SignalKind::Momentum,
+                };
+
+                if ctx.submit(sell_instruction).is_ok() {
+                    println!("[STRATEGY] STOP-LOSS EXIT: {} {}-MIN {} (reversal={:.4}%, z={:.2})",
+                        pos_asset_name,
+                        pos.market_type,
+                        if pos.side == 0 { "SELL YES" } else { "SELL NO" },
+                        reversal * 100.0,
+                        reversal_z);
+
+                    // Log stop-loss to activity log
+                    ctx.log.activity("warning", "position",
+                        &format!("Stop-loss triggered: {} {}-MIN", pos_asset_name, pos.market_type),
+                        Some(format!(r#"{{"asset": "{}", "market": {}, "reversal": {:.4}, "z": {:.2}, "side": "{}", "entry_price": {:.2}}}"#,
+                            pos_asset_name, pos.market_type, reversal * 100.0, reversal_z,
+                            if pos.side == 0 { "YES" } else { "NO" },
+                            pos.entry_price_cents as f64 / 100.0)));
+                }
+            }
+        }
+
+        // Remove closed positions (reverse order to preserve indices)
+        for idx in positions_to_close.into_iter().rev() {
+            self.open_positions.remove(idx);
+        }
+    }
+
+    /// Enter a window in the direction of its momentum
+    fn try_entry(
+        &mut self,
+        ctx: &mut StrategyContext,
+        asset_symbol: u64,
+        minutes: u64,
+        signal: Signal,
+        fair_up: Option<f64>,
+        expires_at: Option<DateTime<Utc>>,
+    ) {
+        let asset_name = get_asset_name(asset_symbol);
+        let Signal { momentum, z } = signal;
+        let side = if momentum > 0.0 { 0 } else { 1 };
+        // Fair probability of the side bought (0 = Up/YES, 1 = Down/NO)
+        let fair_prob = fair_up.map(|p| if side == 0 { p } else { 1.0 - p });
+        let instruction = TradeInstruction {
+            symbol: asset_symbol * 100 + minutes, // e.g., 160 = BTC 60-min, 215 = ETH 15-min
+            side,
+            price_cents: 50,
+            size: TRADE_SIZE_DOLLARS,
+            fair_prob,
+            kind: SignalKind::Momentum,
+        };
+
+        match ctx.submit(instruction) {
+            Ok(()) => {
+                println!("[STRATEGY] {} {}-MIN ENTRY: {} (momentum={:.4}%, z={:.2})",
+                    asset_name,
+                    minutes,
+                    if side == 0 { "BUY YES (UP)" } else { "BUY NO (DOWN)" },
+                    momentum * 100.0,
+                    z);
+
+                // Log trade entry to activity log
+                ctx.log.activity("success", "trade",
+                    &format!("{} {}-MIN: {} @ momentum {:.2}%", asset_name, minutes, if side == 0 { "BUY YES" } else { "BUY NO" }, momentum * 100.0),
+                    Some(format!(r#"{{"asset": "{}", "market": {}, "momentum": {:.4}, "z": {:.2}, "side": "{}", "size": {}}}"#,
+                        asset_name, minutes, momentum * 100.0, z, if side == 0 { "YES" } else { "NO" }, TRADE_SIZE_DOLLARS)));
+
+                // Track position
+                self.open_positions.push(Position {
+                    asset_symbol,
+                    market_type: minutes,
+                    side,
+                    entry_momentum: momentum,
+                    entry_time: Instant::now(),
+                    entry_price_cents: 50,
+                    expires_at,
+                    filled: false,
+                });
+
+                self.last_trade_times.insert(asset_symbol, Instant::now());
+            }
+            Err(reason) => {
+                println!("[STRATEGY] {} {}-MIN entry not sent: {}", asset_name, minutes, reason);
+            }
+        }
+    }
+}
+
+impl Strategy for MomentumStrategy {
+    fn tag(&self) -> SignalKind {
+        SignalKind::Momentum
+    }
+
+    fn on_market_update(&mut self, ctx: &mut StrategyContext, update: &MarketUpdate) {
+        self.tick_count += 1;
+
+        let asset_symbol = update.symbol;
+        let asset_name = get_asset_name(asset_symbol);
+
+        // CHECK MARKET CACHE: Only process if we have an active market for this asset
+        // This avoids wasting CPU on assets with no markets (e.g. XRP if no market exists)
+        // Also look up the live window per horizon: entries are only taken
+        // when a tradeable window exists, and its expiry drives the stop-loss
+        let now_utc = ctx.now;
+        let (has_active_market, window_60, window_15) = match ctx.markets.get(asset_name) {
+            Some(markets) => (
+                !markets.is_empty(),
+                crate::polymarket::select_live_market(markets, MarketType::Hourly, now_utc),
+                crate::polymarket::select_live_market(markets, MarketType::Fifteen, now_utc),
+            ),
+            None => (false, None, None),
+        };
+        let expiry_60 = window_60.map(|m| m.end_date);
+        let expiry_15 = window_15.map(|m| m.end_date);
+
+        // If no active market and we don't have open positions for this asset, skip
+        let has_open_positions = self.open_positions.iter().any(|p| p.asset_symbol == asset_symbol);
+
+        if !has_active_market && !has_open_positions {
+            // Skip processing for this asset to save resources
+            return;
+        }
+
+        // Price history for this asset (kept by the host)
+        let prices = ctx.prices;
+        let Some(price_history) = prices.get(&asset_symbol) else {
+            return;
+        };
+
+        // Momentum over each lookback (kept incrementally by the host)
+        let momentum = |secs: u64| ctx.rolling(asset_symbol, secs * 1000).map_or(0.0, |w| w.momentum());
+        let momentum_60 = momentum(MOMENTUM_WINDOW_60MIN_SECS);
+        let momentum_15 = momentum(MOMENTUM_WINDOW_15MIN_SECS);
+
+        // Volatility per horizon, and each live window's momentum as a
+        // z-score against the time it has left (none until the estimate is
+        // warm: the default volatility is no regime to enter against)
+        let sigma_60 = horizon_sigma(ctx, asset_symbol, MarketType::Hourly);
+        let sigma_15 = horizon_sigma(ctx, asset_symbol, MarketType::Fifteen);
+        let secs_left = |market: &CachedMarket| (market.end_date - now_utc).num_milliseconds() as f64 / 1000.0;
+        let signal = |window: Option<&CachedMarket>, momentum: f64, horizon: MarketType| {
+            let sigma = ctx.volatility(asset_symbol, horizon)?;
+            window.map(|m| Signal { momentum, z: z_score(momentum, sigma, secs_left(m)) })
+        };
+        let signal_60 = signal(window_60, momentum_60, MarketType::Hourly);
+        let signal_15 = signal(window_15, momentum_15, MarketType::Fifteen);
+
+        // Fair probability of Up for each live window, when its open is
+        // known (captured strike, else the history covers the window start)
+        let spot = update.price as f64 / 100.0;
+        let fair_up = |window: Option<&CachedMarket>, sigma: f64| -> Option<f64> {
+            let market = window?;
+            let open = market.open_price.or_else(|| {
+                crate::pricing::window_open_price(price_history, market.start_date.timestamp_millis().max(0) as u64)
+            })?;
+            Some(crate::pricing::fair_probability_up(spot, open, sigma, secs_left(market)))
+        };
+        let fair_up_60 = fair_up(window_60, sigma_60);
+        let fair_up_15 = fair_up(window_15, sigma_15);
+        // Distance of spot from each live window's strike
+        let distance = |window: Option<&CachedMarket>| window.and_then(|m| m.distance_from_strike(spot));
+        let percent = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.2}%", v * 100.0));
+
+        // Debug: Log every 100th tick per asset
+        if self.tick_count.is_multiple_of(100) {
+            let price_dollars = update.price as f64 / 100.0;
+            // VWAP of the current 1-minute kline when that stream is
+            // subscribed, else of the last minute of trades
+            let vwap_1m = ctx.bar(asset_symbol, BarInterval::OneMinute).and_then(|b| b.vwap())
+                .or_else(|| ctx.rolling(asset_symbol, 60_000)?.vwap.map(|v| v / 100.0));
+            // Book pressure: the local L2 book when synced, else the best bid/offer
+            let (micro, imbalance) = match (ctx.depth(asset_symbol), ctx.quote(asset_symbol)) {
+                (Some(depth), _) => (Some(depth.microprice), Some(depth.imbalance)),
+                (None, Some(quote)) => (Some(quote.microprice()), quote.imbalance()),
+                (None, None) => (None, None),
+            };
+            let dollars = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("${:.2}", v));
+            let z = |s: Option<Signal>| s.map_or("-".to_string(), |s| format!("{:+.2}", s.z));
+            println!("[STRATEGY] {} Tick #{}: price=${:.2}, vwap_1m={}, micro={}, imb={}, mom_60={:.4}%, mom_15={:.4}%, z_60={}, z_15={}, dist_60={}, dist_15={}, p_up_60={}, p_up_15={}, signals={}",
+                asset_name, self.tick_count, price_dollars, dollars(vwap_1m), dollars(micro),
+                imbalance.map_or("-".to_string(), |v| format!("{:+.2}", v)),
+                momentum_60 * 100.0, momentum_15 * 100.0,
+                z(signal_60), z(signal_15),
+                percent(distance(window_60)), percent(distance(window_15)),
+                percent(fair_up_60), percent(fair_up_15),
+                self.open_positions.len());
+
+            // Log to database (log all assets, not just BTC)
+            ctx.log.strategy(StrategyLogMsg {
+                tick_number: self.tick_count as i32,
+                price: price_dollars,
+                momentum_60: momentum_60 * 100.0,
+                momentum_15: momentum_15 * 100.0,
+                open_positions: self.open_positions.len() as i32,
+            });
+
+            // Log price checkup to activity log
+            ctx.log.activity("info", "strategy",
+                &format!("{} price: ${:.2} | mom_60: {:.2}% | mom_15: {:.2}%", asset_name, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0),
+                Some(format!(r#"{{"asset": "{}", "price": {:.2}, "momentum_60": {:.4}, "momentum_15": {:.4}, "positions": {}, "tick": {}}}"#,
+                    asset_name, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0, self.open_positions.len(), self.tick_count)));
+        }
+
+        // ============================================
+        // STOP-LOSS CHECK: Exit positions if momentum reverses (only for current asset)
+        // ============================================
+        self.check_stop_loss(ctx, asset_symbol, momentum_60, momentum_15);
+
+        // ============================================
+        // ENTRY LOGIC (per-asset rate limiting)
+        // ============================================
+
+        // Check rate limiting for this asset
+        if let Some(&last_time) = self.last_trade_times.get(&asset_symbol) {
+            if last_time.elapsed() < Duration::from_secs(COOLDOWN_SECS) {
+                return;
+            }
+        }
+
+        // Entry Logic: 60-minute markets, then 15-minute markets
+        // (MAX_POSITIONS per asset)
+        let asset_positions = |positions: &[Position]| positions.iter().filter(|p| p.asset_symbol == asset_symbol).count();
+        if let Some(signal) = signal_60.filter(|s| s.z.abs() >= MOMENTUM_Z_60MIN && asset_positions(&self.open_positions) < MAX_POSITIONS) {
+            self.try_entry(ctx, asset_symbol, 60, signal, fair_up_60, expiry_60);
+        }
+        if let Some(signal) = signal_15.filter(|s| s.z.abs() >= MOMENTUM_Z_15MIN && asset_positions(&self.open_positions) < MAX_POSITIONS) {
+            self.try_entry(ctx, asset_symbol, 15, signal, fair_up_15, expiry_15);
+        }
+    }
+
+    /// Mark the most recent matching position filled at the real entry price
+    fn on_fill(&mut self, _ctx: &mut StrategyContext, fill: &Fill) {
+        if fill.symbol >= 1000 {
+            return; // Exits were already removed when sent
+        }
+        let (asset_symbol, minutes) = (fill.symbol / 100, fill.symbol % 100);
+        if let Some(pos) = self
+            .open_positions
+            .iter_mut()
+            .rev()
+            .find(|p| p.asset_symbol == asset_symbol && p.market_type == minutes && p.side == fill.side)
+        {
+            pos.entry_price_cents = (fill.price * 100.0).round() as u64;
+            pos.filled = true;
+        }
+    }
+
+    /// An entry refused or left the book unfilled never opened: forget its
+    /// position (a partly filled one is kept)
+    fn on_reject(&mut self, _ctx: &mut StrategyContext, rejection: &Rejection) {
+        if rejection.symbol >= 1000 || rejection.filled > 0.0 {
+            return;
+        }
+        let (asset_symbol, minutes) = (rejection.symbol / 100, rejection.symbol % 100);
+        if let Some(idx) = self
+            .open_positions
+            .iter()
+            .rposition(|p| !p.filled && p.asset_symbol == asset_symbol && p.market_type == minutes && p.side == rejection.side)
+        {
+            self.open_positions.remove(idx);
+        }
+    }
+
+    /// Forget positions whose window has resolved
+    fn on_timer(&mut self, ctx: &mut StrategyContext) {
+        let before = self.open_positions.len();
+        self.open_positions.retain(|p| p.expires_at.is_none_or(|e| e > ctx.now));
+        if self.open_positions.len() < before {
+            println!("[STRATEGY] {} momentum position(s) expired, {} open", before - self.open_positions.len(), self.open_positions.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polymarket::test_market;
+    use crate::strategy::TestHost;
+
+    /// $100,000 in cents
+    const BASE_PRICE: u64 = 10_000_000;
+
+    /// Momentum on a BTC 15-min window with `secs_left` to run
+    fn host(now: DateTime<Utc>, secs_left: i64) -> TestHost {
+        let market = test_market("BTC", now + chrono::Duration::seconds(secs_left));
+        TestHost::new(Box::new(MomentumStrategy::new(&[])), HashMap::from([("BTC".to_string(), vec![market])]))
+    }
+
+    /// One BTC trade a second for `secs`, alternating 0.01% around the base
+    /// price: a volatility of about 1e-4 per second and no momentum.
+    /// Returns the timestamp of the next trade.
+    fn chop(host: &mut TestHost, now: DateTime<Utc>, secs: u64) -> u64 {
+        for i in 0..secs {
+            let price = if i % 2 == 1 { BASE_PRICE + 1_000 } else { BASE_PRICE };
+            host.trade(&MarketUpdate { symbol: 1, price, quantity: 1.0, ts: i * 1_000 }, now);
+        }
+        secs * 1_000
+    }
+
+    fn trade_at(host: &mut TestHost, now: DateTime<Utc>, price: u64, ts: u64) {
+        host.trade(&MarketUpdate { symbol: 1, price, quantity: 1.0, ts }, now);
+    }
+
+    #[test]
+    fn test_enters_above_the_z_threshold() {
+        let now = Utc::now();
+        let mut host = host(now, 600);
+        let ts = chop(&mut host, now, 200);
+        assert!(host.instructions.pop().is_err());
+
+        // +0.5% over the lookback: about 2 expected moves over the 600s left
+        trade_at(&mut host, now, BASE_PRICE * 1_005 / 1_000, ts);
+        let entry = host.instructions.pop().unwrap();
+        assert_eq!((entry.symbol, entry.side, entry.kind), (115, 0, SignalKind::Momentum));
+    }
+
+    #[test]
+    fn test_no_entry_below_the_z_threshold() {
+        let now = Utc::now();
+        let mut host = host(now, 600);
+        let ts = chop(&mut host, now, 200);
+
+        // +0.02%: a fraction of the expected move
+        trade_at(&mut host, now, BASE_PRICE + 2_000, ts);
+        assert!(host.instructions.pop().is_err());
+    }
+
+    #[test]
+    fn test_no_entry_while_volatility_is_cold() {
+        let now = Utc::now();
+        let mut host = host(now, 600);
+        let ts = chop(&mut host, now, 10);
+
+        trade_at(&mut host, now, BASE_PRICE * 1_005 / 1_000, ts);
+        assert!(host.instructions.pop().is_err());
+    }
+
+    #[test]
+    fn test_stop_loss_exits_a_reversal_near_expiry() {
+        // 150s left: entries still allowed, inside the stop-loss zone
+        let now = Utc::now();
+        let mut host = host(now, 150);
+        let ts = chop(&mut host, now, 200);
+        trade_at(&mut host, now, BASE_PRICE * 1_005 / 1_000, ts);
+        let entry = host.instructions.pop().unwrap();
+        assert_eq!((entry.symbol, entry.side), (115, 0));
+
+        // Momentum reverses to -0.5% while the entry still rests: nothing to sell
+        trade_at(&mut host, now, BASE_PRICE * 995 / 1_000, ts + 1_000);
+        assert!(host.instructions.pop().is_err());
+
+        // Once filled, the same reversal exits it
+        let fill = Fill {
+            kind: SignalKind::Momentum, symbol: 115, side: 0, price: 0.55, shares: 18.18, cost: 10.0, fee: 0.0,
+            pnl: 8.18, remaining: 0.0, expires_at: now + chrono::Duration::seconds(150),
+        };
+        host.run(now, |s, ctx| s.on_fill(ctx, &fill));
+        trade_at(&mut host, now, BASE_PRICE * 995 / 1_000, ts + 2_000);
+        let exit = host.instructions.pop().unwrap();
+        assert_eq!((exit.symbol, exit.side), (1115, 1));
+        assert!(host.instructions.pop().is_err());
+    }
+}
@@ -1,32 +1,229 @@
 use alloy_signer_local::PrivateKeySigner;
-use chrono::Datelike;
-use polymarket_rs::client::{AuthenticatedClient, GammaClient, TradingClient};
+use crate::auth;
+use crate::exchange::{ExchangeClient, ExchangeResult, OpenOrder, TokenPosition, TradeFill};
+use crate::types::MarketType;
+use chrono::{DateTime, Datelike, Utc};
+use polymarket_rs::client::{AuthenticatedClient, TradingClient};
 use polymarket_rs::types::{
-    ApiCreds, CreateOrderOptions, OrderArgs, Side as PolySide, OrderType,
+    ApiCreds, CreateOrderOptions, ExtraOrderArgs, OrderArgs, Side as PolySide, OrderType,
     BalanceAllowanceParams, AssetType,
 };
 use polymarket_rs::OrderBuilder;
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
-use std::sync::{Arc, RwLock};
-use tokio::time::{interval, Duration};
+use arc_swap::ArcSwap;
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
 
 /// Cached market data for strategy and execution
 #[derive(Debug, Clone)]
 pub struct CachedMarket {
-    pub asset: String,       // "BTC", "ETH", etc.
-    pub market_type: String, // "15-MIN", "60-MIN"
+    pub asset: String,              // "BTC", "ETH", etc.
+    pub market_type: MarketType,    // Horizon: 15-min, hourly, daily
     pub condition_id: String,
     pub question_id: String,
-    pub token_ids: Vec<String>, // [YES_ID, NO_ID]
-    pub outcomes: Vec<String>,  // ["Up", "Down"]
-    pub end_date_iso: String,   // ISO timestamp for expiry
+    pub question: String,
+    pub token_ids: Vec<String>,     // [YES_ID, NO_ID]
+    pub outcomes: Vec<String>,      // ["Up", "Down"]
+    pub start_date: DateTime<Utc>,  // Window start (eventStartTime for Up/Down)
+    pub end_date: DateTime<Utc>,    // Expiry / resolution time
+    pub minimum_tick_size: f64,     // e.g. 0.01
+    pub minimum_order_size: f64,    // In shares, e.g. 5
+    pub neg_risk: bool,             // Traded through the neg-risk exchange
+    pub taker_fee_rate: f64,        // Taker fee rate (fraction, 0 = fee-free); see prices::taker_fee
+    pub open_price: Option<f64>,    // Spot at window start (the strike), once captured
 }
 
-pub type MarketCache = Arc<RwLock<HashMap<String, Vec<CachedMarket>>>>;
+impl CachedMarket {
+    /// Whether this market's window is trading at `now`
+    pub fn is_live_at(&self, now: DateTime<Utc>) -> bool {
+        self.start_date <= now && now < self.end_date
+    }
+    
+    /// Time left until expiry (zero once expired)
+    pub fn time_to_expiry(&self, now: DateTime<Utc>) -> std::time::Duration {
+        (self.end_date - now).to_std().unwrap_or(std::time::Duration::ZERO)
+    }
+    
+    /// Round a share amount down to what an order can carry
+    pub fn floor_to_lot(&self, shares: f64) -> f64 {
+        (shares / ORDER_LOT_SIZE + 1e-9).floor() * ORDER_LOT_SIZE
+    }
+    
+    /// Order signing parameters: the fee rate (`feeRateBps`, which fee-enabled
+    /// markets require), tick size and exchange
+    pub fn order_params(&self) -> ExchangeResult<(ExtraOrderArgs, CreateOrderOptions)> {
+        let tick_size = Decimal::from_f64(self.minimum_tick_size).ok_or("Invalid tick size")?.normalize();
+        let fee_rate_bps = (self.taker_fee_rate * 10_000.0).round() as u32;
+        Ok((
+            ExtraOrderArgs::new().fee_rate_bps(fee_rate_bps),
+            CreateOrderOptions::new().tick_size(tick_size).neg_risk(self.neg_risk),
+        ))
+    }
+    
+    /// Relative distance of `spot` from the window open, once captured
+    /// (+0.002 = 0.2% above the strike)
+    pub fn distance_from_strike(&self, spot: f64) -> Option<f64> {
+        let k = self.open_price.filter(|k| *k > 0.0)?;
+        Some((spot - k) / k)
+    }
+}
+
+/// Don't open positions in a window with less than this much time left
+pub fn min_time_to_expiry(market_type: MarketType) -> std::time::Duration {
+    let secs = match market_type {
+        MarketType::Fifteen => 60,
+        MarketType::Hourly => 180,
+        MarketType::Daily => 900,
+    };
+    std::time::Duration::from_secs(secs)
+}
+
+/// Pick the window of `market_type` that is trading at `now`.
+///
+/// Several windows of the same horizon can be cached at once (current plus
+/// pre-fetched upcoming ones); only the one containing `now` is tradeable, and
+/// it is skipped if it expires within `min_time_to_expiry`.
+pub fn select_live_market(
+    markets: &[CachedMarket],
+    market_type: MarketType,
+    now: DateTime<Utc>,
+) -> Option<&CachedMarket> {
+    let min_remaining = min_time_to_expiry(market_type);
+    markets
+        .iter()
+        .filter(|m| m.market_type == market_type && m.is_live_at(now))
+        .filter(|m| m.time_to_expiry(now) >= min_remaining)
+        .min_by_key(|m| m.end_date)
+}
+
+/// The window of `market_type` trading at `now`, however little time it
+/// has left (exits must still be able to find it)
+pub fn live_market(
+    markets: &[CachedMarket],
+    market_type: MarketType,
+    now: DateTime<Utc>,
+) -> Option<&CachedMarket> {
+    markets
+        .iter()
+        .filter(|m| m.market_type == market_type && m.is_live_at(now))
+        .min_by_key(|m| m.end_date)
+}
+
+/// Order sizes are signed in hundredths of a share, whatever the tick size
+const ORDER_LOT_SIZE: f64 = 0.01;
+
+/// Defaults when Gamma omits order constraints
+const DEFAULT_TICK_SIZE: f64 = 0.01;
+const DEFAULT_MIN_ORDER_SIZE: f64 = 5.0;
+
+/// Immutable view of all cached markets at one point in time
+#[derive(Debug, Default)]
+pub struct MarketSnapshot {
+    /// Incremented on every publish, so readers can detect market changes
+    pub version: u64,
+    pub markets: HashMap<String, Vec<CachedMarket>>,
+}
+
+impl MarketSnapshot {
+    /// Markets for an asset symbol ("BTC", "ETH", ...)
+    pub fn get(&self, asset: &str) -> Option<&Vec<CachedMarket>> {
+        self.markets.get(asset)
+    }
+}
+
+/// Window opens are kept for windows starting this long before the latest one
+const OPEN_RETENTION_HOURS: i64 = 48;
+
+/// Market cache shared by the updater, the pinned strategy thread and
+/// execution. Readers load the current snapshot without locking; the updater
+/// builds a new snapshot and publishes it with an atomic pointer swap, so a
+/// refresh can never block the strategy thread.
+///
+/// Captured window opens live beside the snapshots and are applied to every
+/// published snapshot, so a Gamma refresh never loses them. Writers (the
+/// updater and the open capture) serialize on the opens lock; readers never
+/// take it.
+pub struct MarketStore {
+    current: ArcSwap<MarketSnapshot>,
+    opens: Mutex<HashMap<(String, i64), f64>>, // (asset, window start secs) -> open price
+}
+
+impl MarketStore {
+    pub fn new() -> Self {
+        Self {
+            current: ArcSwap::from_pointee(MarketSnapshot::default()),
+            opens: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Current snapshot (wait-free; cheap enough for every tick)
+    pub fn load(&self) -> arc_swap::Guard<Arc<MarketSnapshot>> {
+        self.current.load()
+    }
+    
+    /// Version of the current snapshot
+    pub fn version(&self) -> u64 {
+        self.current.load().version
+    }
+    
+    /// Replace all markets; returns the new version
+    pub fn publish(&self, mut markets: HashMap<String, Vec<CachedMarket>>) -> u64 {
+        let opens = self.opens.lock().expect("opens lock poisoned");
+        apply_opens(&mut markets, &opens);
+        let version = self.current.load().version + 1;
+        self.current.store(Arc::new(MarketSnapshot { version, markets }));
+        version
+    }
+    
+    /// Record the open price of `asset`'s window starting at `start`, and
+    /// republish if a cached market gains it. Returns false if already known.
+    pub fn record_open(&self, asset: &str, start: DateTime<Utc>, price: f64) -> bool {
+        let mut opens = self.opens.lock().expect("opens lock poisoned");
+        match opens.entry((asset.to_string(), start.timestamp())) {
+            std::collections::hash_map::Entry::Occupied(_) => return false, // First capture wins
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(price);
+            }
+        }
+        let cutoff = (start - chrono::Duration::hours(OPEN_RETENTION_HOURS)).timestamp();
+        opens.retain(|(_, window_start), _| *window_start >= cutoff);
+        
+        let current = self.current.load();
+        let gains_open = current
+            .get(asset)
+            .is_some_and(|markets| markets.iter().any(|m| m.start_date == start && m.open_price.is_none()));
+        if gains_open {
+            let mut markets = current.markets.clone();
+            apply_opens(&mut markets, &opens);
+            self.current.store(Arc::new(MarketSnapshot { version: current.version + 1, markets }));
+        }
+        true
+    }
+}
+
+/// Fill in known window opens
+fn apply_opens(markets: &mut HashMap<String, Vec<CachedMarket>>, opens: &HashMap<(String, i64), f64>) {
+    for market in markets.values_mut().flatten() {
+        if market.open_price.is_none() {
+            market.open_price = opens.get(&(market.asset.clone(), market.start_date.timestamp())).copied();
+        }
+    }
+}
+
+impl Default for MarketStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type MarketCache = Arc<MarketStore>;
 
 /// Order side
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -46,43 +243,31 @@ impl OrderSide {
 
 /// Polymarket CLOB Client wrapper
 pub struct PolymarketClient {
-    gamma: GammaClient,
-    authenticated: AuthenticatedClient,
-    trading: TradingClient,
+    /// L2-authenticated clients; swapped out when credentials are rotated
+    auth: ArcSwap<AuthedClients>,
+    wallet: PrivateKeySigner,
+    http: reqwest::Client,
     pub address: String,
+    /// Proxy wallet holding funds/positions (Magic.Link), if any
+    pub funder_address: Option<String>,
 }
 
-impl PolymarketClient {
-    /// Create a new client from environment variables
-    pub fn from_env() -> Option<Self> {
-        let api_key = std::env::var("POLYMARKET_API_KEY").ok()?;
-        let api_secret = std::env::var("POLYMARKET_API_SECRET").ok()?;
-        let passphrase = std::env::var("POLYMARKET_PASSPHRASE").ok()?;
-        let private_key = std::env::var("POLYMARKET_PRIVATE_KEY").ok()?;
-
-        let wallet = PrivateKeySigner::from_str(&private_key).ok()?;
-        let address = format!("{:?}", wallet.address());
-
-        let chain_id = 137;
-        let host = "https://clob.polymarket.com";
-        let gamma_host = "https://gamma-api.polymarket.com";
-
-        let gamma = GammaClient::new(gamma_host);
+/// Clients bound to one set of L2 API credentials
+struct AuthedClients {
+    authenticated: AuthenticatedClient,
+    trading: TradingClient,
+    creds: ApiCreds,
+}
 
-        let creds = ApiCreds {
-            api_key: api_key.clone(),
-            secret: api_secret.clone(),
-            passphrase: passphrase.clone(),
-        };
+impl AuthedClients {
+    fn new(wallet: &PrivateKeySigner, funder_address: Option<&str>, creds: ApiCreds) -> Self {
+        let chain_id = auth::POLYGON_CHAIN_ID;
+        let host = crate::endpoints::clob_url();
 
         // For Magic.Link wallets, use the funder (proxy wallet) address
-        let funder_address_str = std::env::var("POLYMARKET_FUNDER_ADDRESS").ok();
-        let funder_address: Option<polymarket_rs::Address> = funder_address_str.as_ref().and_then(|s| {
+        let funder: Option<polymarket_rs::Address> = funder_address.and_then(|s| {
             polymarket_rs::Address::from_str(s).ok()
         });
-        if let Some(ref funder) = funder_address {
-            println!("📝 Using Funder (Proxy) Address: {:?}", funder);
-        }
 
         // Create authenticated client for balance queries
         let authenticated = AuthenticatedClient::new(
@@ -90,62 +275,413 @@ impl PolymarketClient {
             wallet.clone(),
             chain_id,
             Some(creds.clone()),
-            funder_address, // Funder address for Magic.Link wallets
+            funder, // Funder address for Magic.Link wallets
         );
 
         // For Magic.Link, use SignatureType::PolyProxy (type 1) 
-        let sig_type = if funder_address_str.is_some() {
+        let sig_type = if funder_address.is_some() {
             Some(polymarket_rs::types::SignatureType::PolyProxy)
         } else {
             None
         };
-        let builder = OrderBuilder::new(wallet.clone(), sig_type, funder_address);
-        let trading = TradingClient::new(host, wallet, chain_id, creds, builder);
+        let builder = OrderBuilder::new(wallet.clone(), sig_type, funder);
+        let trading = TradingClient::new(host, wallet.clone(), chain_id, creds.clone(), builder);
 
-        Some(Self {
-            gamma,
-            authenticated,
-            trading,
+        Self { authenticated, trading, creds }
+    }
+}
+
+impl PolymarketClient {
+    /// Create a new client from environment variables.
+    ///
+    /// L2 credentials come from `POLYMARKET_API_KEY` / `_API_SECRET` /
+    /// `_PASSPHRASE`, or, with `POLYMARKET_DERIVE_API_KEY=true`, are
+    /// created/derived from `POLYMARKET_PRIVATE_KEY` (see `auth`).
+    pub async fn from_env() -> ExchangeResult<Self> {
+        let private_key = std::env::var("POLYMARKET_PRIVATE_KEY")
+            .map_err(|_| "POLYMARKET_PRIVATE_KEY not set")?;
+
+        let wallet = PrivateKeySigner::from_str(&private_key)?;
+        let address = format!("{:?}", wallet.address());
+        let http = reqwest::Client::new();
+
+        let (creds, source) = auth::resolve_api_creds(&http, &wallet).await?;
+        match source {
+            auth::CredsSource::Env => println!("🔑 Using API credentials from environment"),
+            auth::CredsSource::Cache => println!("🔑 Using cached API credentials ({})", auth::creds_file().display()),
+            auth::CredsSource::Derived => println!("🔑 Derived API credentials from private key"),
+        }
+
+        let funder_address = std::env::var("POLYMARKET_FUNDER_ADDRESS").ok();
+        if let Some(ref funder) = funder_address {
+            println!("📝 Using Funder (Proxy) Address: {}", funder);
+        }
+
+        let clients = AuthedClients::new(&wallet, funder_address.as_deref(), creds);
+
+        Ok(Self {
+            auth: ArcSwap::from_pointee(clients),
+            wallet,
+            http,
             address,
+            funder_address,
         })
     }
 
+    /// Replace revoked L2 credentials: create/derive new ones via L1 auth,
+    /// cache them and rebuild the authenticated clients
+    async fn rotate_credentials(&self) -> ExchangeResult<()> {
+        if !auth::derive_enabled() {
+            return Err("API credentials rejected and POLYMARKET_DERIVE_API_KEY is not enabled".into());
+        }
+        let creds = auth::create_or_derive_api_creds(&self.http, &self.wallet, 0).await?;
+        if let Err(e) = auth::save_cached_creds(&auth::creds_file(), &self.address, &creds) {
+            eprintln!("⚠️ Failed to cache rotated API credentials: {}", e);
+        }
+        self.auth.store(Arc::new(AuthedClients::new(&self.wallet, self.funder_address.as_deref(), creds)));
+        println!("🔑 Rotated API credentials");
+        Ok(())
+    }
+
+    /// Run an L2-authenticated call; if the credentials were rejected,
+    /// rotate them and retry once
+    async fn with_reauth<T, F, Fut>(&self, call: F) -> ExchangeResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = ExchangeResult<T>>,
+    {
+        match call().await {
+            Err(e) if auth::is_unauthorized(e.as_ref()) => {
+                eprintln!("🔑 API credentials rejected ({}), rotating...", e);
+                self.rotate_credentials().await?;
+                call().await
+            }
+            result => result,
+        }
+    }
+
+    /// L2 (API key) auth headers for a CLOB request.
+    ///
+    /// Signature = urlsafe-base64(HMAC-SHA256(secret, timestamp + method + path + body))
+    fn l2_headers(&self, method: &str, path: &str, body: &str) -> ExchangeResult<reqwest::header::HeaderMap> {
+        let creds = &self.auth.load().creds;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let secret = base64::engine::general_purpose::URL_SAFE.decode(&creds.secret)?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(&secret)?;
+        mac.update(format!("{}{}{}{}", timestamp, method, path, body).as_bytes());
+        let signature = base64::engine::general_purpose::URL_SAFE.encode(mac.finalize().into_bytes());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("POLY_ADDRESS", self.address.parse()?);
+        headers.insert("POLY_SIGNATURE", signature.parse()?);
+        headers.insert("POLY_TIMESTAMP", timestamp.parse()?);
+        headers.insert("POLY_API_KEY", creds.api_key.parse()?);
+        headers.insert("POLY_PASSPHRASE", creds.passphrase.parse()?);
+        Ok(headers)
+    }
+
+    /// Every row of a paginated L2 listing (`/data/orders`, `/data/trades`)
+    /// matching `filters`, following `next_cursor` to the last page
+    async fn fetch_all_pages(&self, path: &str, filters: &[(&str, String)]) -> ExchangeResult<Vec<serde_json::Value>> {
+        let mut rows = Vec::new();
+        let mut cursor = FIRST_CURSOR.to_string();
+        for _ in 0..MAX_PAGES {
+            let page: serde_json::Value = self.with_reauth(|| {
+                let cursor = cursor.as_str();
+                async move {
+                    // The signature covers the path only, not the query
+                    let headers = self.l2_headers("GET", path, "")?;
+                    Ok(self.http
+                        .get(format!("{}{}", crate::endpoints::clob_url(), path))
+                        .query(filters)
+                        .query(&[("next_cursor", cursor)])
+                        .headers(headers)
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json()
+                        .await?)
+                }
+            }).await?;
+
+            let (page_rows, next) = page_rows(page);
+            rows.extend(page_rows);
+            match next {
+                Some(next) if next != cursor => cursor = next,
+                _ => return Ok(rows),
+            }
+        }
+        Err(format!("{} still paginating after {} pages", path, MAX_PAGES).into())
+    }
+
+    /// Address whose positions the data API should report
+    fn position_owner(&self) -> &str {
+        self.funder_address.as_deref().unwrap_or(&self.address)
+    }
+
+    /// Discover all tracked crypto markets (daily, hourly, 15-min) from the
+    /// Gamma API, keyed by asset symbol.
+    ///
+    /// A failed request is logged and its slug skipped; discovery only fails
+    /// if requests failed and nothing was found.
+    pub async fn discover_gamma_markets(
+        &self,
+    ) -> ExchangeResult<HashMap<String, Vec<CachedMarket>>> {
+        println!("🔎 Updating Market Cache with daily, hourly and 15-min crypto markets...");
+
+        let mut new_markets: HashMap<String, Vec<CachedMarket>> = HashMap::new();
+        let mut count = 0;
+        let mut failures = 0;
+
+        // Get today's date for generating slugs
+        let now = chrono::Utc::now();
+        let month = match now.month() {
+            1 => "january",
+            2 => "february",
+            3 => "march",
+            4 => "april",
+            5 => "may",
+            6 => "june",
+            7 => "july",
+            8 => "august",
+            9 => "september",
+            10 => "october",
+            11 => "november",
+            12 => "december",
+            _ => "january",
+        };
+        let day = now.day();
+
+        // Calculate tomorrow's date
+        let tomorrow = now + chrono::Duration::days(1);
+        let tomorrow_month = match tomorrow.month() {
+            1 => "january",
+            2 => "february",
+            3 => "march",
+            4 => "april",
+            5 => "may",
+            6 => "june",
+            7 => "july",
+            8 => "august",
+            9 => "september",
+            10 => "october",
+            11 => "november",
+            12 => "december",
+            _ => "january",
+        };
+        let tomorrow_day = tomorrow.day();
+
+        // Fetch daily "Up or Down" markets for BTC, ETH, SOL
+        let assets_to_fetch = vec![("bitcoin", "BTC"), ("ethereum", "ETH"), ("solana", "SOL")];
+
+        // Today's market plus tomorrow's (pre-fetched for rollover)
+        let date_options = vec![
+            (month, day, "today"),
+            (tomorrow_month, tomorrow_day, "tomorrow"),
+        ];
+
+        for (asset_slug, asset_symbol) in assets_to_fetch {
+            // Keep both today's and tomorrow's market so the next window is
+            // already cached when today's expires
+            for (m, d, label) in &date_options {
+                // Generate the slug: e.g., "bitcoin-up-or-down-on-january-16"
+                let slug = format!("{}-up-or-down-on-{}-{}", asset_slug, m, d);
+                println!(
+                    "   📊 Fetching {} daily ({}): {}",
+                    asset_symbol, label, slug
+                );
+
+                // Fetch market by slug using the Gamma API directly
+                match fetch_market_by_slug(&slug).await {
+                    Ok(Some(market)) => {
+                        // Skip closed markets
+                        if market.closed.unwrap_or(false) {
+                            println!("   ⚠️ {} daily market is closed", asset_symbol);
+                            continue;
+                        }
+
+                        if let Some(cached_market) =
+                            market.to_cached_market(asset_symbol, MarketType::Daily)
+                        {
+                            println!("   ✅ Found {} daily: {}", asset_symbol, market.id);
+                            println!(
+                                "      Token IDs: [{}, {}]",
+                                &cached_market.token_ids[0]
+                                    [..20.min(cached_market.token_ids[0].len())],
+                                &cached_market.token_ids[1]
+                                    [..20.min(cached_market.token_ids[1].len())]
+                            );
+
+                            new_markets
+                                .entry(asset_symbol.to_string())
+                                .or_default()
+                                .push(cached_market);
+                            count += 1;
+                        }
+                    }
+                    Ok(None) => {} // Market not found for this date, try next
+                    Err(e) => {
+                        failures += 1;
+                        eprintln!("   ❌ Failed to fetch {}: {}", slug, e);
+                    }
+                }
+            } // end of date_options loop
+        } // end of assets loop
+
+        // Fetch 15-minute markets: one market per window, slug keyed by the
+        // window start timestamp (e.g. "btc-updown-15m-1768500000").
+        // Fetch the current window and pre-fetch the next one.
+        let fifteen_min_assets = vec![
+            ("btc", "BTC"),
+            ("eth", "ETH"),
+            ("sol", "SOL"),
+            ("xrp", "XRP"),
+        ];
+
+        for (slug_prefix, asset) in fifteen_min_assets {
+            for window_start in fifteen_minute_window_starts(now.timestamp()) {
+                let slug = format!("{}-updown-15m-{}", slug_prefix, window_start);
+                println!("   📊 Fetching {} 15-min: {}", asset, slug);
+
+                match fetch_market_by_slug(&slug).await {
+                    Ok(Some(market)) => {
+                        if market.closed.unwrap_or(false) {
+                            continue;
+                        }
+                        if let Some(cached_market) =
+                            market.to_cached_market(asset, MarketType::Fifteen)
+                        {
+                            println!("   ✅ Found {} 15-min: {}", asset, market.id);
+                            new_markets
+                                .entry(asset.to_string())
+                                .or_default()
+                                .push(cached_market);
+                            count += 1;
+                        }
+                    }
+                    Ok(None) => {} // Window not listed (yet)
+                    Err(e) => {
+                        failures += 1;
+                        eprintln!("   ⚠️ Failed to fetch {}: {}", slug, e);
+                    }
+                }
+            }
+        }
+
+        // Fetch hourly markets using series_slug API (more reliable)
+        let hourly_series = vec![
+            ("btc-up-or-down-hourly", "BTC"),
+            ("eth-up-or-down-hourly", "ETH"),
+            ("sol-up-or-down-hourly", "SOL"),
+        ];
+
+        for (series_slug, asset) in hourly_series {
+            let url = format!(
+                "{}/events?limit=5&active=true&closed=false&series_slug={}",
+                crate::endpoints::gamma_url(),
+                series_slug
+            );
+            println!(
+                "   📊 Fetching {} hourly markets from series: {}",
+                asset, series_slug
+            );
+
+            match fetch_events(&url).await {
+                Ok(events) => {
+                    for market in events.into_iter().flat_map(|e| e.markets) {
+                        if market.closed.unwrap_or(false) {
+                            continue;
+                        }
+
+                        // FILTER: Only accept markets with "up or down" in the question
+                        // This filters out unrelated markets like "MicroStrategy" or "Trump deport"
+                        let question_lower =
+                            market.question.as_deref().unwrap_or("").to_lowercase();
+                        let is_price_market = question_lower.contains("up or down")
+                            || question_lower.contains("up/down");
+                        if !is_price_market || market.condition_id.is_empty() {
+                            // Skip non-price markets silently
+                            continue;
+                        }
+
+                        if let Some(cached_market) =
+                            market.to_cached_market(asset, MarketType::Hourly)
+                        {
+                            println!(
+                                "   ✅ Found {} hourly: {}...",
+                                asset,
+                                &cached_market.question[..50.min(cached_market.question.len())]
+                            );
+
+                            new_markets
+                                .entry(asset.to_string())
+                                .or_default()
+                                .push(cached_market);
+                            count += 1;
+                        }
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("   ⚠️ Failed to fetch {} hourly series: {}", asset, e);
+                }
+            }
+        }
+        println!("   Discovered {} markets ({} requests failed)", count, failures);
+        if count == 0 && failures > 0 {
+            return Err(format!("all {} failed Gamma discovery requests, nothing found", failures).into());
+        }
+        Ok(new_markets)
+    }
+}
+
+impl ExchangeClient for PolymarketClient {
+    async fn discover_markets(&self) -> ExchangeResult<HashMap<String, Vec<CachedMarket>>> {
+        self.discover_gamma_markets().await
+    }
+
+    async fn fetch_orderbook(&self, token_id: &str) -> Option<(f64, f64)> {
+        crate::prices::fetch_orderbook(token_id).await
+    }
+
     /// Fetch account balance using polymarket-rs AuthenticatedClient
-    pub async fn fetch_balance(&self) -> Result<f64, Box<dyn std::error::Error>> {
+    async fn fetch_balance(&self) -> ExchangeResult<f64> {
         // Use get_balance_allowance with Collateral asset type (USDC)
-        let params = BalanceAllowanceParams::new().asset_type(AssetType::Collateral);
-        
-        let response = self.authenticated.get_balance_allowance(params).await?;
+        let response = self.with_reauth(|| async move {
+            let params = BalanceAllowanceParams::new().asset_type(AssetType::Collateral);
+            Ok(self.auth.load_full().authenticated.get_balance_allowance(params).await?)
+        }).await?;
         
         // Debug: print the raw response
-        println!("📊 Balance API response: {:?}", response);;
+        println!("📊 Balance API response: {:?}", response);
         
         // Parse the balance from the JSON response
         // Response format is typically: {"balance": "...", "allowance": "..."}
-        if let Some(balance_str) = response.get("balance").and_then(|b| b.as_str()) {
-            // Balance is typically in USDC with 6 decimals
-            if let Ok(balance_raw) = balance_str.parse::<f64>() {
-                let balance = balance_raw / 1_000_000.0; // Convert from 6 decimals
-                return Ok(balance);
+        let Some(balance) = response.get("balance").and_then(usdc_amount) else {
+            println!("⚠️ Could not parse balance from response: {:?}", response);
+            return Ok(0.0);
+        };
+
+        // An allowance below the balance means orders fail at settlement
+        for (spender, allowance) in collateral_allowances(&response) {
+            if allowance < balance {
+                println!(
+                    "⚠️ USDC allowance for {} is ${:.2} (balance ${:.2}) - run `trading_bot approve`",
+                    spender, allowance, balance
+                );
             }
         }
-        
-        // Fallback: try parsing as number directly
-        if let Some(balance) = response.get("balance").and_then(|b| b.as_f64()) {
-            return Ok(balance / 1_000_000.0);
-        }
-        
-        println!("⚠️ Could not parse balance from response: {:?}", response);
-        Ok(0.0)
+        Ok(balance)
     }
 
-    pub async fn place_order(
+    async fn place_order(
         &self,
+        market: &CachedMarket,
         token_id: &str,
         side: OrderSide,
         size: f64,
         price: f64,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> ExchangeResult<String> {
         let poly_side = match side {
             OrderSide::BUY => PolySide::Buy,
             OrderSide::SELL => PolySide::Sell,
@@ -161,273 +697,734 @@ impl PolymarketClient {
             side: poly_side,
         };
 
-        let request = self
-            .trading
-            .create_order(&args, None, None, CreateOrderOptions::default())?;
-            
-        let resp = self.trading.post_order(request, OrderType::Gtc).await?;
+        let (extras, options) = market.order_params()?;
+
+        let (args, extras, options) = (&args, &extras, &options);
+        let resp = self.with_reauth(|| async move {
+            let clients = self.auth.load_full();
+            let request = clients
+                .trading
+                .create_order(args, None, Some(extras), options.clone())?;
+            Ok(clients.trading.post_order(request, OrderType::Gtc).await?)
+        }).await?;
         Ok(resp.order_id.to_string())
     }
 
-    /// Update market cache using GammaClient::get_events
-    pub async fn start_market_cache_updater(
-        client: Arc<Self>,
-        cache: MarketCache,
-        _assets: Vec<String>,
-    ) {
-        let mut interval = interval(Duration::from_secs(300));
-        loop {
-            interval.tick().await;
-            println!("🔎 Updating Market Cache with daily crypto markets...");
-            
-            let mut new_markets: HashMap<String, Vec<CachedMarket>> = HashMap::new();
-            let mut count = 0;
-            
-            // Get today's date for generating slugs
-            let now = chrono::Utc::now();
-            let month = match now.month() {
-                1 => "january",
-                2 => "february",
-                3 => "march",
-                4 => "april",
-                5 => "may",
-                6 => "june",
-                7 => "july",
-                8 => "august",
-                9 => "september",
-                10 => "october",
-                11 => "november",
-                12 => "december",
-                _ => "january"
+    async fn cancel_order(&self, order_id: &str) -> ExchangeResult<()> {
+        self.with_reauth(|| async move {
+            let path = "/order";
+            let body = serde_json::json!({ "orderID": order_id }).to_string();
+            let headers = self.l2_headers("DELETE", path, &body)?;
+            let response = self.http
+                .delete(format!("{}{}", crate::endpoints::clob_url(), path))
+                .headers(headers)
+                .header("Content-Type", "application/json")
+                .body(body)
+                .send()
+                .await?;
+            // Status errors stay typed so a 401 triggers a credential rotation
+            response.error_for_status()?;
+            Ok(())
+        }).await
+    }
+
+    async fn fetch_open_orders(&self) -> ExchangeResult<Vec<OpenOrder>> {
+        let orders: Vec<ClobOrderResponse> = serde_json::from_value(self.fetch_all_pages("/data/orders", &[]).await?.into())?;
+        Ok(orders.into_iter().filter_map(ClobOrderResponse::into_open_order).collect())
+    }
+
+    async fn fetch_trades(&self, after: Option<DateTime<Utc>>) -> ExchangeResult<Vec<TradeFill>> {
+        // `after` is in whole seconds: trades in that second come back again
+        let filters: Vec<(&str, String)> = after.map(|t| ("after", t.timestamp().to_string())).into_iter().collect();
+        let trades: Vec<ClobTradeResponse> = serde_json::from_value(self.fetch_all_pages("/data/trades", &filters).await?.into())?;
+        let mut fills: Vec<TradeFill> = trades.into_iter().flat_map(ClobTradeResponse::into_fills).collect();
+        fills.retain(|f| after.is_none_or(|t| f.matched_at >= t));
+        fills.sort_by_key(|f| std::cmp::Reverse(f.matched_at));
+        Ok(fills)
+    }
+
+    async fn fetch_positions(&self) -> ExchangeResult<Vec<TokenPosition>> {
+        let url = format!("{}/positions?user={}&sizeThreshold=0.01", crate::endpoints::data_api_url(), self.position_owner());
+        let positions: Vec<DataApiPosition> = self.http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(positions
+            .into_iter()
+            .map(|p| TokenPosition {
+                token_id: p.asset,
+                condition_id: p.condition_id,
+                size: p.size,
+                avg_price: p.avg_price,
+            })
+            .collect())
+    }
+}
+
+/// Keep the market cache in sync with market expiries.
+///
+/// Refreshes on a fixed interval, but also shortly before and after every
+/// known expiry so the next window is picked up as soon as it is listed.
+/// Expired markets are evicted at their `end_date`, between refreshes.
+pub async fn start_market_cache_updater<E: ExchangeClient>(
+    client: Arc<E>,
+    cache: MarketCache,
+) {
+    let mut next_refresh = Utc::now(); // Initial fetch on startup
+    loop {
+        let now = Utc::now();
+        
+        if now >= next_refresh {
+            next_refresh = refresh_market_cache(client.as_ref(), &cache).await;
+        }
+        
+        // Evict markets whose window has closed (publishes a new snapshot)
+        let now = Utc::now();
+        let snapshot = cache.load();
+        if snapshot.markets.values().flatten().any(|m| m.end_date <= now) {
+            let mut markets = snapshot.markets.clone();
+            let evicted = evict_expired(&mut markets, now);
+            let version = cache.publish(markets);
+            println!("🗑️ Evicted {} expired markets from cache (v{})", evicted, version);
+        }
+        let next_expiry = next_expiry_time(&cache.load().markets, now);
+        
+        // Sleep until the next refresh or expiry, whichever is first
+        let wake_at = next_expiry.map_or(next_refresh, |e| e.min(next_refresh));
+        let sleep_for = (wake_at - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+        tokio::time::sleep(sleep_for).await;
+    }
+}
+
+/// One discovery pass: publish what the venue lists and return when to
+/// refresh next. A failed pass keeps the previous snapshot and retries soon.
+async fn refresh_market_cache<E: ExchangeClient>(client: &E, cache: &MarketStore) -> DateTime<Utc> {
+    let mut new_markets = match client.discover_markets().await {
+        Ok(markets) => markets,
+        Err(e) => {
+            eprintln!("⚠️ Market discovery failed, keeping cache v{}: {}", cache.version(), e);
+            return Utc::now() + chrono::Duration::seconds(DISCOVERY_RETRY_SECS);
+        }
+    };
+    // Windows this refresh didn't return (e.g. their request failed) stay
+    // cached until they expire
+    for market in cache.load().markets.values().flatten() {
+        let known = new_markets.get(&market.asset).is_some_and(|m| m.iter().any(|m| m.condition_id == market.condition_id));
+        if !known {
+            new_markets.entry(market.asset.clone()).or_default().push(market.clone());
+        }
+    }
+    evict_expired(&mut new_markets, Utc::now());
+    
+    // Log summary
+    for (asset, markets) in &new_markets {
+        println!("   📈 {}: {} markets", asset, markets.len());
+    }
+    let next_refresh = next_refresh_time(&new_markets, Utc::now());
+    
+    // Publish the new snapshot
+    let version = cache.publish(new_markets);
+    println!("✅ Updated Market Cache (v{}), next refresh at {}", version, next_refresh.format("%H:%M:%S"));
+    next_refresh
+}
+
+/// Cursor of the first page of a CLOB listing
+const FIRST_CURSOR: &str = "MA==";
+/// `next_cursor` once the last page has been returned
+const END_CURSOR: &str = "LTE=";
+/// Pages read from one listing before giving up
+const MAX_PAGES: usize = 100;
+
+/// Rows of one page of a CLOB listing and the cursor of the next page.
+/// Paginated responses wrap rows in `{"data": [...], "next_cursor": ...}`;
+/// a bare array is a single page.
+fn page_rows(page: serde_json::Value) -> (Vec<serde_json::Value>, Option<String>) {
+    match page {
+        serde_json::Value::Array(rows) => (rows, None),
+        mut page => {
+            let rows = match page.get_mut("data").map(serde_json::Value::take) {
+                Some(serde_json::Value::Array(rows)) => rows,
+                _ => Vec::new(),
             };
-            let day = now.day();
-            
-            // Calculate tomorrow's date
-            let tomorrow = now + chrono::Duration::days(1);
-            let tomorrow_month = match tomorrow.month() {
-                1 => "january",
-                2 => "february",
-                3 => "march",
-                4 => "april",
-                5 => "may",
-                6 => "june",
-                7 => "july",
-                8 => "august",
-                9 => "september",
-                10 => "october",
-                11 => "november",
-                12 => "december",
-                _ => "january"
+            let next = page
+                .get("next_cursor")
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty() && *c != END_CURSOR)
+                .map(str::to_string);
+            (rows, next)
+        }
+    }
+}
+
+/// Open order as returned by the CLOB `/data/orders` endpoint
+#[derive(Debug, Deserialize)]
+struct ClobOrderResponse {
+    id: String,
+    asset_id: String,
+    side: String,
+    price: String,
+    original_size: String,
+    #[serde(default)]
+    size_matched: String,
+}
+
+impl ClobOrderResponse {
+    fn into_open_order(self) -> Option<OpenOrder> {
+        Some(OpenOrder {
+            side: order_side(&self.side),
+            price: self.price.parse().ok()?,
+            size: self.original_size.parse().ok()?,
+            size_matched: self.size_matched.parse().unwrap_or(0.0),
+            order_id: self.id,
+            token_id: self.asset_id,
+        })
+    }
+}
+
+/// Trade as returned by the CLOB `/data/trades` endpoint. The top-level
+/// fields are the taker's; when we were a maker our side of the trade is in
+/// `maker_orders`.
+#[derive(Debug, Deserialize)]
+struct ClobTradeResponse {
+    id: String,
+    asset_id: String,
+    side: String,
+    price: String,
+    size: String,
+    #[serde(default)]
+    match_time: String, // Unix seconds
+    #[serde(default)]
+    taker_order_id: String,
+    #[serde(default)]
+    trader_side: String, // "TAKER" or "MAKER"
+    #[serde(default)]
+    maker_address: String, // Our (funder) address
+    #[serde(default)]
+    maker_orders: Vec<ClobMakerOrder>,
+}
+
+/// Resting order matched by a trade
+#[derive(Debug, Deserialize)]
+struct ClobMakerOrder {
+    order_id: String,
+    #[serde(default)]
+    maker_address: String,
+    asset_id: String,
+    side: String,
+    price: String,
+    matched_amount: String,
+}
+
+/// Order side as the CLOB spells it ("BUY" / "SELL")
+fn order_side(side: &str) -> OrderSide {
+    if side.eq_ignore_ascii_case("BUY") { OrderSide::BUY } else { OrderSide::SELL }
+}
+
+impl ClobTradeResponse {
+    /// Our fills in this trade: the taker order, or each of our maker orders
+    fn into_fills(self) -> Vec<TradeFill> {
+        let Some(matched_at) = self.match_time.parse().ok().and_then(|secs| DateTime::from_timestamp(secs, 0)) else {
+            return Vec::new();
+        };
+        if !self.trader_side.eq_ignore_ascii_case("MAKER") {
+            let (Ok(price), Ok(size)) = (self.price.parse(), self.size.parse()) else {
+                return Vec::new();
             };
-            let tomorrow_day = tomorrow.day();
-            
-            // Fetch daily "Up or Down" markets for BTC, ETH, SOL
-            let assets_to_fetch = vec![
-                ("bitcoin", "BTC"),
-                ("ethereum", "ETH"),
-                ("solana", "SOL"),
-            ];
-            
-            // Try today first, then tomorrow
-            let date_options = vec![
-                (month, day, "today"),
-                (tomorrow_month, tomorrow_day, "tomorrow"),
-            ];
-            
-            for (asset_slug, asset_symbol) in assets_to_fetch {
-                let mut found_market = false;
-                
-                for (m, d, label) in &date_options {
-                    if found_market { break; }
-                    
-                    // Generate the slug: e.g., "bitcoin-up-or-down-on-january-16"
-                    let slug = format!("{}-up-or-down-on-{}-{}", asset_slug, m, d);
-                    println!("   📊 Fetching {} daily ({}): {}", asset_symbol, label, slug);
-                
-                // Fetch market by slug using the Gamma API directly
-                let url = format!(
-                    "https://gamma-api.polymarket.com/markets?slug={}",
-                    slug
-                );
-                
-                match reqwest::get(&url).await {
-                    Ok(response) => {
-                        if let Ok(markets) = response.json::<Vec<GammaMarketResponse>>().await {
-                            if let Some(market) = markets.first() {
-                                // Skip closed markets
-                                if market.closed.unwrap_or(false) {
-                                    println!("   ⚠️ {} daily market is closed", asset_symbol);
-                                    continue;
-                                }
-                                
-                                // Parse token IDs from JSON string
-                                let token_ids: Vec<String> = market.clob_token_ids
-                                    .as_ref()
-                                    .and_then(|s| serde_json::from_str(s).ok())
-                                    .unwrap_or_default();
-                                
-                                // Parse outcomes from JSON string
-                                let outcomes: Vec<String> = market.outcomes
-                                    .as_ref()
-                                    .and_then(|s| serde_json::from_str(s).ok())
-                                    .unwrap_or_else(|| vec!["Up".to_string(), "Down".to_string()]);
-                                
-                                if token_ids.len() == 2 {
-                                    let cached_market = CachedMarket {
-                                        asset: asset_symbol.to_string(),
-                                        market_type: "DAILY".to_string(),
-                                        condition_id: market.condition_id.clone(),
-                                        question_id: market.id.clone(),
-                                        token_ids: token_ids.clone(),
-                                        outcomes,
-                                        end_date_iso: market.end_date.clone().unwrap_or_default(),
-                                    };
-                                    
-                                    println!("   ✅ Found {} daily: {}", asset_symbol, market.id);
-                                    println!("      Token IDs: [{}, {}]", 
-                                        &token_ids[0][..20.min(token_ids[0].len())],
-                                        &token_ids[1][..20.min(token_ids[1].len())]);
-                                    
-                                    new_markets.entry(asset_symbol.to_string())
-                                        .or_insert_with(Vec::new)
-                                        .push(cached_market);
-                                    count += 1;
-                                    found_market = true; // Stop trying other dates
-                                }
-                            } else {
-                                // Market not found for this date, try next
-                            }
-                        }
-                    }
-                    Err(e) => eprintln!("   ❌ Failed to fetch {}: {}", slug, e),
-                }
-                } // end of date_options loop
-            } // end of assets loop
-            
-            // Fetch hourly markets using series_slug API (more reliable)
-            let hourly_series = vec![
-                ("btc-up-or-down-hourly", "BTC"),
-                ("eth-up-or-down-hourly", "ETH"),
-                ("sol-up-or-down-hourly", "SOL"),
-            ];
-            
-            for (series_slug, asset) in hourly_series {
-                let url = format!(
-                    "https://gamma-api.polymarket.com/events?limit=5&active=true&closed=false&series_slug={}",
-                    series_slug
-                );
-                println!("   📊 Fetching {} hourly markets from series: {}", asset, series_slug);
-                
-                match reqwest::get(&url).await {
-                    Ok(response) => {
-                        if let Ok(events) = response.json::<Vec<serde_json::Value>>().await {
-                            for event in events {
-                                if let Some(markets) = event.get("markets").and_then(|m| m.as_array()) {
-                                    for market in markets {
-                                        // Check if closed
-                                        let is_closed = market.get("closed")
-                                            .and_then(|c| c.as_bool())
-                                            .unwrap_or(false);
-                                        if is_closed {
-                                            continue;
-                                        }
-                                        
-                                        // Get market data
-                                        let condition_id = market.get("conditionId")
-                                            .and_then(|c| c.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        let question_id = market.get("id")
-                                            .and_then(|i| i.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        let question = market.get("question")
-                                            .and_then(|q| q.as_str())
-                                            .unwrap_or("");
-                                        
-                                        // Parse token IDs
-                                        let token_ids: Vec<String> = market.get("clobTokenIds")
-                                            .and_then(|t| t.as_str())
-                                            .and_then(|s| serde_json::from_str(s).ok())
-                                            .unwrap_or_default();
-                                        
-                                        // Parse outcomes
-                                        let outcomes: Vec<String> = market.get("outcomes")
-                                            .and_then(|o| o.as_str())
-                                            .and_then(|s| serde_json::from_str(s).ok())
-                                            .unwrap_or_else(|| vec!["Up".to_string(), "Down".to_string()]);
-                                        
-                                        let end_date = market.get("endDate")
-                                            .and_then(|e| e.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        
-                                        // FILTER: Only accept markets with "up or down" in the question
-                                        // This filters out unrelated markets like "MicroStrategy" or "Trump deport"
-                                        let question_lower = question.to_lowercase();
-                                        let is_price_market = question_lower.contains("up or down") || 
-                                                            question_lower.contains("up/down");
-                                        
-                                        if !is_price_market {
-                                            // Skip non-price markets silently
-                                            continue;
-                                        }
-                                        
-                                        if token_ids.len() == 2 && !condition_id.is_empty() {
-                                            let cached_market = CachedMarket {
-                                                asset: asset.to_string(),
-                                                market_type: "60-MIN".to_string(),
-                                                condition_id,
-                                                question_id: question_id.clone(),
-                                                token_ids: token_ids.clone(),
-                                                outcomes,
-                                                end_date_iso: end_date,
-                                            };
-                                            
-                                            println!("   ✅ Found {} hourly: {}...", 
-                                                asset, 
-                                                &question[..50.min(question.len())]);
-                                            
-                                            new_markets.entry(asset.to_string())
-                                                .or_insert_with(Vec::new)
-                                                .push(cached_market);
-                                            count += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => eprintln!("   ⚠️ Failed to fetch {} hourly series: {}", asset, e),
-                }
-            }
-            
-            // Update the cache
-            if let Ok(mut write_guard) = cache.write() {
-                // Log summary
-                for (asset, markets) in &new_markets {
-                    println!("   📈 {}: {} markets", asset, markets.len());
-                }
-                *write_guard = new_markets;
-                println!("✅ Updated Market Cache: {} markets found", count);
-            } else {
-                eprintln!("❌ Failed to acquire write lock on market cache");
-            }
+            return vec![TradeFill {
+                trade_id: self.id,
+                order_id: self.taker_order_id,
+                token_id: self.asset_id,
+                side: order_side(&self.side),
+                price,
+                size,
+                matched_at,
+            }];
         }
+        let (trade_id, ours) = (self.id, self.maker_address);
+        self.maker_orders
+            .into_iter()
+            .filter(|m| m.maker_address.eq_ignore_ascii_case(&ours))
+            .filter_map(|m| {
+                Some(TradeFill {
+                    trade_id: trade_id.clone(),
+                    side: order_side(&m.side),
+                    price: m.price.parse().ok()?,
+                    size: m.matched_amount.parse().ok()?,
+                    matched_at,
+                    order_id: m.order_id,
+                    token_id: m.asset_id,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Position as returned by the data API `/positions` endpoint
+#[derive(Debug, Deserialize)]
+struct DataApiPosition {
+    asset: String,
+    #[serde(rename = "conditionId", default)]
+    condition_id: String,
+    size: f64,
+    #[serde(rename = "avgPrice", default)]
+    avg_price: f64,
+}
+
+/// Fallback refresh interval when no expiry is coming up
+const CACHE_REFRESH_INTERVAL_SECS: i64 = 300;
+/// Refresh this long before a known expiry (catch early listings of the next window)
+const PRE_EXPIRY_REFRESH_SECS: i64 = 30;
+/// Refresh this long after a known expiry (next window is live by then)
+const POST_EXPIRY_REFRESH_SECS: i64 = 5;
+/// Retry this soon after a failed discovery pass
+const DISCOVERY_RETRY_SECS: i64 = 30;
+
+/// Remove markets that have expired; returns the number evicted
+fn evict_expired(markets: &mut HashMap<String, Vec<CachedMarket>>, now: DateTime<Utc>) -> usize {
+    let mut evicted = 0;
+    for asset_markets in markets.values_mut() {
+        let before = asset_markets.len();
+        asset_markets.retain(|m| m.end_date > now);
+        evicted += before - asset_markets.len();
     }
+    markets.retain(|_, m| !m.is_empty());
+    evicted
+}
+
+/// Earliest upcoming expiry across all cached markets
+fn next_expiry_time(markets: &HashMap<String, Vec<CachedMarket>>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    markets
+        .values()
+        .flatten()
+        .map(|m| m.end_date)
+        .filter(|end| *end > now)
+        .min()
+}
+
+/// When the cache should next be refreshed from Gamma: just before and just
+/// after each known expiry, and at least every `CACHE_REFRESH_INTERVAL_SECS`
+fn next_refresh_time(markets: &HashMap<String, Vec<CachedMarket>>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let fallback = now + chrono::Duration::seconds(CACHE_REFRESH_INTERVAL_SECS);
+    markets
+        .values()
+        .flatten()
+        .flat_map(|m| {
+            [
+                m.end_date - chrono::Duration::seconds(PRE_EXPIRY_REFRESH_SECS),
+                m.end_date + chrono::Duration::seconds(POST_EXPIRY_REFRESH_SECS),
+            ]
+        })
+        .filter(|t| *t > now)
+        .fold(fallback, |earliest, t| earliest.min(t))
+}
+
+/// Length of a 15-minute market window in seconds
+const FIFTEEN_MIN_WINDOW_SECS: i64 = 900;
+
+/// Start timestamps (unix seconds) of the current and next 15-minute windows
+fn fifteen_minute_window_starts(now_secs: i64) -> [i64; 2] {
+    let current = now_secs - now_secs.rem_euclid(FIFTEEN_MIN_WINDOW_SECS);
+    [current, current + FIFTEEN_MIN_WINDOW_SECS]
+}
+
+/// Fetch a single market from the Gamma API by its exact slug
+async fn fetch_market_by_slug(slug: &str) -> Result<Option<GammaMarketResponse>, reqwest::Error> {
+    let url = format!("{}/markets?slug={}", crate::endpoints::gamma_url(), slug);
+    let markets = reqwest::get(&url)
+        .await?
+        .error_for_status()?
+        .json::<Vec<GammaMarketResponse>>()
+        .await?;
+    Ok(markets.into_iter().next())
+}
+
+/// Fetch Gamma events from a fully built `/events` URL
+async fn fetch_events(url: &str) -> Result<Vec<GammaEventResponse>, reqwest::Error> {
+    reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<Vec<GammaEventResponse>>()
+        .await
+}
+
+/// Parse a Gamma ISO-8601 timestamp (e.g. "2026-01-16T17:00:00Z")
+fn parse_gamma_date(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// USDC amount in dollars from a 6-decimal string or number
+fn usdc_amount(value: &serde_json::Value) -> Option<f64> {
+    let raw = match value {
+        serde_json::Value::String(s) => s.parse::<f64>().ok()?,
+        v => v.as_f64()?,
+    };
+    Some(raw / 1_000_000.0)
+}
+
+/// Collateral allowances from a `/balance-allowance` response, in dollars:
+/// either a single `allowance` or an `allowances` map keyed by spender
+fn collateral_allowances(response: &serde_json::Value) -> Vec<(String, f64)> {
+    if let Some(map) = response.get("allowances").and_then(|a| a.as_object()) {
+        return map
+            .iter()
+            .filter_map(|(spender, v)| Some((spender.clone(), usdc_amount(v)?)))
+            .collect();
+    }
+    response
+        .get("allowance")
+        .and_then(usdc_amount)
+        .map(|a| vec![("exchange".to_string(), a)])
+        .unwrap_or_default()
+}
+
+/// Response type for Gamma API event queries
+#[derive(Debug, Deserialize)]
+struct GammaEventResponse {
+    #[serde(default)]
+    markets: Vec<GammaMarketResponse>,
 }
 
 /// Response type for Gamma API market queries
 #[derive(Debug, Deserialize)]
 struct GammaMarketResponse {
     id: String,
-    #[serde(rename = "conditionId")]
+    question: Option<String>,
+    #[serde(rename = "conditionId", default)]
     condition_id: String,
     #[serde(rename = "clobTokenIds")]
     clob_token_ids: Option<String>,
     outcomes: Option<String>,
+    #[serde(rename = "eventStartTime")]
+    event_start_time: Option<String>,
     #[serde(rename = "endDate")]
     end_date: Option<String>,
     closed: Option<bool>,
+    #[serde(rename = "orderPriceMinTickSize")]
+    order_price_min_tick_size: Option<f64>,
+    #[serde(rename = "orderMinSize")]
+    order_min_size: Option<f64>,
+    #[serde(rename = "negRisk")]
+    neg_risk: Option<bool>,
+    /// Taker fee in basis points (only set on fee-enabled markets)
+    #[serde(rename = "takerBaseFee")]
+    taker_base_fee: Option<f64>,
+}
+
+impl GammaMarketResponse {
+    /// Convert to a cache entry; `None` if the market has no Up/Down token
+    /// pair or no parseable expiry
+    fn to_cached_market(&self, asset: &str, market_type: MarketType) -> Option<CachedMarket> {
+        let token_ids: Vec<String> = self.clob_token_ids
+            .as_ref()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default();
+        if token_ids.len() != 2 {
+            return None;
+        }
+        
+        let outcomes: Vec<String> = self.outcomes
+            .as_ref()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_else(|| vec!["Up".to_string(), "Down".to_string()]);
+        
+        let end_date = parse_gamma_date(self.end_date.as_deref())?;
+        // Up/Down windows start at eventStartTime; fall back to the nominal
+        // window length
+        let start_date = parse_gamma_date(self.event_start_time.as_deref())
+            .unwrap_or_else(|| end_date - chrono::Duration::seconds(market_type.window_secs()));
+        
+        Some(CachedMarket {
+            asset: asset.to_string(),
+            market_type,
+            condition_id: self.condition_id.clone(),
+            question_id: self.id.clone(),
+            question: self.question.clone().unwrap_or_default(),
+            token_ids,
+            outcomes,
+            start_date,
+            end_date,
+            minimum_tick_size: self.order_price_min_tick_size.unwrap_or(DEFAULT_TICK_SIZE),
+            minimum_order_size: self.order_min_size.unwrap_or(DEFAULT_MIN_ORDER_SIZE),
+            neg_risk: self.neg_risk.unwrap_or(false),
+            taker_fee_rate: self.taker_base_fee.unwrap_or(0.0) / 10_000.0,
+            open_price: None,
+        })
+    }
+}
+
+/// Minimal 15-minute Up/Down market expiring at `end_date` (for tests)
+#[cfg(test)]
+pub(crate) fn test_market(asset: &str, end_date: DateTime<Utc>) -> CachedMarket {
+    CachedMarket {
+        asset: asset.to_string(),
+        market_type: MarketType::Fifteen,
+        condition_id: "0xabc".to_string(),
+        question_id: "1".to_string(),
+        question: String::new(),
+        token_ids: vec!["111".to_string(), "222".to_string()],
+        outcomes: vec!["Up".to_string(), "Down".to_string()],
+        start_date: end_date - chrono::Duration::seconds(900),
+        end_date,
+        minimum_tick_size: DEFAULT_TICK_SIZE,
+        minimum_order_size: DEFAULT_MIN_ORDER_SIZE,
+        neg_risk: false,
+        taker_fee_rate: 0.0,
+        open_price: None,
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_fifteen_minute_window_starts() {
+        // 2026-01-15 12:07:30 UTC -> window started at 12:00:00
+        let now = 1768478850;
+        assert_eq!(fifteen_minute_window_starts(now), [1768478400, 1768479300]);
+        
+        // Exactly on a boundary belongs to the new window
+        assert_eq!(fifteen_minute_window_starts(1768479300), [1768479300, 1768480200]);
+    }
+    
+    #[test]
+    fn test_collateral_allowances() {
+        let single = serde_json::json!({"balance": "58360000", "allowance": "0"});
+        assert_eq!(usdc_amount(&single["balance"]), Some(58.36));
+        assert_eq!(collateral_allowances(&single), vec![("exchange".to_string(), 0.0)]);
+
+        let per_spender = serde_json::json!({
+            "balance": "1000000",
+            "allowances": {"0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E": "5000000"}
+        });
+        assert_eq!(
+            collateral_allowances(&per_spender),
+            vec![("0x4bFb41d5B3570DeFd03C39a9A4D8dE6Bd8B8982E".to_string(), 5.0)]
+        );
+        assert!(collateral_allowances(&serde_json::json!({"balance": "1"})).is_empty());
+    }
+    
+    #[test]
+    fn test_page_rows_follows_cursor() {
+        let page = serde_json::json!({"data": [{"id": "a"}, {"id": "b"}], "next_cursor": "MTAw", "count": 2});
+        let (rows, next) = page_rows(page);
+        assert_eq!((rows.len(), next.as_deref()), (2, Some("MTAw")));
+
+        // Last page
+        let page = serde_json::json!({"data": [{"id": "c"}], "next_cursor": END_CURSOR});
+        assert_eq!(page_rows(page).1, None);
+
+        // Unpaginated
+        let (rows, next) = page_rows(serde_json::json!([{"id": "a"}]));
+        assert_eq!((rows.len(), next), (1, None));
+    }
+
+    #[test]
+    fn test_trade_fills_are_ours() {
+        let trade = |trader_side: &str| -> ClobTradeResponse {
+            serde_json::from_value(serde_json::json!({
+                "id": "t1", "taker_order_id": "0xtaker", "asset_id": "111", "side": "BUY",
+                "price": "0.55", "size": "30", "match_time": "1768478850",
+                "trader_side": trader_side, "maker_address": "0xMe",
+                "maker_orders": [
+                    {"order_id": "0xother", "maker_address": "0xsomeone", "asset_id": "111", "side": "SELL", "price": "0.55", "matched_amount": "20"},
+                    {"order_id": "0xmine", "maker_address": "0xme", "asset_id": "222", "side": "BUY", "price": "0.45", "matched_amount": "10"}
+                ]
+            }))
+            .unwrap()
+        };
+
+        let taker = trade("TAKER").into_fills();
+        assert_eq!(taker.len(), 1);
+        assert_eq!((taker[0].order_id.as_str(), taker[0].size), ("0xtaker", 30.0));
+
+        // As maker only our resting order's leg counts
+        let maker = trade("MAKER").into_fills();
+        assert_eq!(maker.len(), 1);
+        assert_eq!((maker[0].order_id.as_str(), maker[0].token_id.as_str(), maker[0].size), ("0xmine", "222", 10.0));
+        assert_eq!(maker[0].side, OrderSide::BUY);
+    }
+
+    #[test]
+    fn test_gamma_market_to_cached_market() {
+        let json = r#"{
+            "id": "123",
+            "question": "Bitcoin Up or Down - January 16, 3PM ET",
+            "conditionId": "0xabc",
+            "clobTokenIds": "[\"111\", \"222\"]",
+            "outcomes": "[\"Up\", \"Down\"]",
+            "eventStartTime": "2026-01-16T20:00:00Z",
+            "endDate": "2026-01-16T21:00:00Z",
+            "orderPriceMinTickSize": 0.001,
+            "orderMinSize": 5,
+            "negRisk": false,
+            "takerBaseFee": 300
+        }"#;
+        let market: GammaMarketResponse = serde_json::from_str(json).unwrap();
+        let cached = market.to_cached_market("BTC", MarketType::Hourly).unwrap();
+        
+        assert_eq!(cached.token_ids, vec!["111", "222"]);
+        assert_eq!(cached.start_date, parse_gamma_date(Some("2026-01-16T20:00:00Z")).unwrap());
+        assert_eq!((cached.end_date - cached.start_date).num_seconds(), 3_600);
+        assert!((cached.minimum_tick_size - 0.001).abs() < 1e-12);
+        assert!((cached.minimum_order_size - 5.0).abs() < 1e-12);
+        assert!(!cached.neg_risk);
+        assert!((cached.taker_fee_rate - 0.03).abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_orders_are_signed_with_the_fee_rate() {
+        let mut market = test_market("BTC", Utc::now());
+        (market.taker_fee_rate, market.minimum_tick_size) = (0.03, 0.001);
+        let (extras, options) = market.order_params().unwrap();
+        
+        let args = OrderArgs {
+            token_id: market.token_ids[0].clone(),
+            price: Decimal::new(456, 3),
+            size: Decimal::new(10, 0),
+            side: PolySide::Buy,
+        };
+        let builder = OrderBuilder::new(PrivateKeySigner::random(), None, None);
+        let order = builder.create_order(137, &args, 0, &extras, options).unwrap();
+        assert_eq!(order.fee_rate_bps, "300");
+    }
+    
+    #[tokio::test]
+    async fn test_failed_discovery_keeps_previous_snapshot() {
+        let sim = crate::exchange::SimulatedExchange::new(100.0);
+        sim.add_market(test_market("BTC", Utc::now() + chrono::Duration::minutes(10)));
+        let cache = MarketStore::new();
+        
+        refresh_market_cache(&sim, &cache).await;
+        assert_eq!((cache.version(), cache.load().markets["BTC"].len()), (1, 1));
+        
+        // Gamma unreachable: nothing published, retried well before the usual refresh
+        sim.set_discovery_down(true);
+        let retry_at = refresh_market_cache(&sim, &cache).await;
+        assert_eq!((cache.version(), cache.load().markets["BTC"].len()), (1, 1));
+        assert!(retry_at <= Utc::now() + chrono::Duration::seconds(DISCOVERY_RETRY_SECS));
+    }
+    
+    #[tokio::test]
+    async fn test_partial_discovery_keeps_missing_windows() {
+        let cache = MarketStore::new();
+        let btc = crate::exchange::SimulatedExchange::new(100.0);
+        btc.add_market(test_market("BTC", Utc::now() + chrono::Duration::minutes(10)));
+        refresh_market_cache(&btc, &cache).await;
+        
+        // BTC's request failed this time: ETH is published alongside the cached BTC window
+        let mut eth_market = test_market("ETH", Utc::now() + chrono::Duration::minutes(10));
+        eth_market.condition_id = "0xeth".to_string();
+        let eth = crate::exchange::SimulatedExchange::new(100.0);
+        eth.add_market(eth_market);
+        refresh_market_cache(&eth, &cache).await;
+        let snapshot = cache.load();
+        assert_eq!(snapshot.version, 2);
+        assert_eq!((snapshot.markets["BTC"].len(), snapshot.markets["ETH"].len()), (1, 1));
+    }
+    
+    #[test]
+    fn test_evict_expired_at_end_date() {
+        let now = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let mut markets = HashMap::new();
+        markets.insert("BTC".to_string(), vec![
+            test_market("BTC", now),                                  // expires exactly now
+            test_market("BTC", now + chrono::Duration::seconds(900)), // next window
+        ]);
+        markets.insert("ETH".to_string(), vec![test_market("ETH", now - chrono::Duration::seconds(1))]);
+        
+        assert_eq!(evict_expired(&mut markets, now), 2);
+        assert_eq!(markets["BTC"].len(), 1);
+        assert!(!markets.contains_key("ETH"));
+    }
+    
+    #[test]
+    fn test_next_refresh_time_tracks_expiries() {
+        let now = parse_gamma_date(Some("2026-01-16T12:10:00Z")).unwrap();
+        let expiry = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let mut markets = HashMap::new();
+        markets.insert("BTC".to_string(), vec![test_market("BTC", expiry)]);
+        
+        // Refresh shortly before the expiry rather than after the 5-min interval
+        assert_eq!(next_refresh_time(&markets, now), expiry - chrono::Duration::seconds(PRE_EXPIRY_REFRESH_SECS));
+        
+        // Once past the pre-expiry refresh, refresh just after the expiry
+        let later = expiry - chrono::Duration::seconds(10);
+        assert_eq!(next_refresh_time(&markets, later), expiry + chrono::Duration::seconds(POST_EXPIRY_REFRESH_SECS));
+        
+        // Nothing cached: fixed interval
+        assert_eq!(next_refresh_time(&HashMap::new(), now), now + chrono::Duration::seconds(CACHE_REFRESH_INTERVAL_SECS));
+        assert_eq!(next_expiry_time(&markets, now), Some(expiry));
+    }
+    
+    #[test]
+    fn test_select_live_market_picks_current_window() {
+        let now = parse_gamma_date(Some("2026-01-16T12:07:00Z")).unwrap();
+        let current_end = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let markets = vec![
+            test_market("BTC", current_end + chrono::Duration::seconds(900)), // next window first
+            test_market("BTC", current_end),
+        ];
+        
+        let selected = select_live_market(&markets, MarketType::Fifteen, now).unwrap();
+        assert_eq!(selected.end_date, current_end);
+        assert_eq!(selected.time_to_expiry(now).as_secs(), 480);
+        assert!(select_live_market(&markets, MarketType::Hourly, now).is_none());
+    }
+    
+    #[test]
+    fn test_select_live_market_skips_window_near_expiry() {
+        let current_end = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let now = current_end - chrono::Duration::seconds(30);
+        let markets = vec![test_market("BTC", current_end)];
+        
+        assert!(select_live_market(&markets, MarketType::Fifteen, now).is_none());
+    }
+    
+    #[test]
+    fn test_market_store_publish_bumps_version() {
+        let store = MarketStore::new();
+        let before = store.load();
+        assert_eq!(before.version, 0);
+        
+        let end = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let mut markets = HashMap::new();
+        markets.insert("BTC".to_string(), vec![test_market("BTC", end)]);
+        assert_eq!(store.publish(markets), 1);
+        
+        // Readers holding an old snapshot keep an unchanged view
+        assert!(before.get("BTC").is_none());
+        assert_eq!(store.load().get("BTC").map(|m| m.len()), Some(1));
+        assert_eq!(store.version(), 1);
+    }
+    
+    #[test]
+    fn test_recorded_opens_survive_refresh() {
+        let store = MarketStore::new();
+        let end = parse_gamma_date(Some("2026-01-16T12:15:00Z")).unwrap();
+        let market = test_market("BTC", end);
+        let start = market.start_date;
+        let mut markets = HashMap::new();
+        markets.insert("BTC".to_string(), vec![market.clone()]);
+        store.publish(markets.clone());
+        assert_eq!(market.distance_from_strike(97_000.0), None);
+        
+        // Capturing the open republishes with the strike filled in
+        assert!(store.record_open("BTC", start, 97_000.0));
+        assert!(!store.record_open("BTC", start, 97_001.0));
+        assert_eq!(store.version(), 2);
+        let snapshot = store.load();
+        let live = &snapshot.get("BTC").unwrap()[0];
+        assert_eq!(live.open_price, Some(97_000.0));
+        assert!((live.distance_from_strike(97_970.0).unwrap() - 0.01).abs() < 1e-9);
+        
+        // A Gamma refresh (no opens) keeps it
+        store.publish(markets);
+        assert_eq!(store.load().get("BTC").unwrap()[0].open_price, Some(97_000.0));
+    }
+}
@@ -10,8 +10,8 @@ use std::time::{Duration, Instant};
 pub const MAX_ENTRY_PRICE: f64 = 0.65;      // Don't buy shares above $0.65
 pub const MIN_UPSIDE: f64 = 0.30;           // Require at least 30% potential upside
 pub const MAX_SPREAD: f64 = 0.10;           // Max bid-ask spread to accept
+pub const MIN_EDGE: f64 = 0.05;             // Fair probability must beat cost by 5¢
 const PRICE_CACHE_TTL_SECS: u64 = 5;        // Cache prices for 5 seconds
-const CLOB_BASE_URL: &str = "https://clob.polymarket.com";
 
 /// Market prices for a single market (Up and Down tokens)
 #[derive(Debug, Clone)]
@@ -53,7 +53,7 @@ pub fn new_price_cache() -> PriceCache {
 
 /// Fetch the orderbook for a token and return best bid/ask
 pub async fn fetch_orderbook(token_id: &str) -> Option<(f64, f64)> {
-    let url = format!("{}/book?token_id={}", CLOB_BASE_URL, token_id);
+    let url = format!("{}/book?token_id={}", crate::endpoints::clob_url(), token_id);
     
     match reqwest::get(&url).await {
         Ok(response) => {
@@ -148,11 +148,46 @@ pub fn calculate_upside(price: f64) -> f64 {
     (1.0 - price) / price
 }
 
-/// Check if a trade opportunity passes value filters
+/// Fee per share at `price`: Polymarket charges `rate × min(p, 1 - p)`,
+/// so fees are largest around 50¢ and vanish near 0 and 1
+pub fn fee_per_share(price: f64, fee_rate: f64) -> f64 {
+    fee_rate * price.min(1.0 - price).max(0.0)
+}
+
+/// Fee (USDC) for trading `shares` at `price`
+pub fn taker_fee(price: f64, shares: f64, fee_rate: f64) -> f64 {
+    fee_per_share(price, fee_rate) * shares
+}
+
+/// Shares a `budget` buys at `price` once fees are paid out of it
+pub fn shares_for_budget(budget: f64, price: f64, fee_rate: f64) -> f64 {
+    let cost = price + fee_per_share(price, fee_rate);
+    if cost <= 0.0 {
+        return 0.0;
+    }
+    budget / cost
+}
+
+/// Realized PnL of selling `shares` bought at `entry` for `exit`, net of
+/// the fees of both legs
+pub fn exit_pnl(entry: f64, exit: f64, shares: f64, fee_rate: f64) -> f64 {
+    (exit - entry) * shares - taker_fee(entry, shares, fee_rate) - taker_fee(exit, shares, fee_rate)
+}
+
+/// Upside after fees: a share costs `price` plus its fee and pays out $1
+pub fn calculate_net_upside(price: f64, fee_rate: f64) -> f64 {
+    if price <= 0.0 || price >= 1.0 {
+        return 0.0;
+    }
+    calculate_upside(price + fee_per_share(price, fee_rate))
+}
+
+/// Check if a trade opportunity passes value filters (upside net of fees)
 pub fn passes_value_filters(
     entry_price: f64,
     bid: f64,
     ask: f64,
+    fee_rate: f64,
 ) -> Result<(f64, f64), &'static str> {
     // Check entry price
     if entry_price > MAX_ENTRY_PRICE {
@@ -160,7 +195,7 @@ pub fn passes_value_filters(
     }
     
     // Check upside
-    let upside = calculate_upside(entry_price);
+    let upside = calculate_net_upside(entry_price, fee_rate);
     if upside < MIN_UPSIDE {
         return Err("upside too low");
     }
@@ -174,6 +209,44 @@ pub fn passes_value_filters(
     Ok((upside, spread))
 }
 
+/// Edge of buying at `entry_price` given a model probability for that side
+/// (see `pricing`): fair value minus the all-in cost of one share
+pub fn calculate_edge(entry_price: f64, fair_prob: f64, fee_rate: f64) -> f64 {
+    fair_prob - (entry_price + fee_per_share(entry_price, fee_rate))
+}
+
+/// Check that the model's fair probability justifies the price after fees
+pub fn passes_edge_filter(entry_price: f64, fair_prob: f64, fee_rate: f64) -> Result<f64, &'static str> {
+    let edge = calculate_edge(entry_price, fair_prob, fee_rate);
+    if edge < MIN_EDGE {
+        return Err("edge too low");
+    }
+    Ok(edge)
+}
+
+/// Filters for model-driven (latency arbitrage) entries: these buy near-
+/// certain outcomes, so the price cap and upside floor don't apply. The
+/// strategy already required its minimum edge on the book it saw; here the
+/// edge must merely survive the current ask.
+pub fn passes_latency_filters(
+    entry_price: f64,
+    bid: f64,
+    ask: f64,
+    fair_prob: f64,
+    fee_rate: f64,
+) -> Result<(f64, f64), &'static str> {
+    if calculate_edge(entry_price, fair_prob, fee_rate) <= 0.0 {
+        return Err("edge gone");
+    }
+    
+    let spread = calculate_spread(bid, ask);
+    if spread > MAX_SPREAD {
+        return Err("spread too wide");
+    }
+    
+    Ok((calculate_net_upside(entry_price, fee_rate), spread))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,12 +267,56 @@ mod tests {
     #[test]
     fn test_passes_value_filters() {
         // Good opportunity
-        assert!(passes_value_filters(0.40, 0.38, 0.42).is_ok());
+        assert!(passes_value_filters(0.40, 0.38, 0.42, 0.0).is_ok());
         
         // Price too high
-        assert!(passes_value_filters(0.70, 0.68, 0.72).is_err());
+        assert!(passes_value_filters(0.70, 0.68, 0.72, 0.0).is_err());
         
         // Spread too wide
-        assert!(passes_value_filters(0.40, 0.30, 0.50).is_err());
+        assert!(passes_value_filters(0.40, 0.30, 0.50, 0.0).is_err());
+    }
+    
+    #[test]
+    fn test_fees() {
+        // 2% of min(p, 1-p): 0.8¢ per share at 40¢, 0.6¢ at 70¢
+        assert!((fee_per_share(0.40, 0.02) - 0.008).abs() < 1e-9);
+        assert!((fee_per_share(0.70, 0.02) - 0.006).abs() < 1e-9);
+        assert!((taker_fee(0.40, 25.0, 0.02) - 0.20).abs() < 1e-9);
+        
+        // $10 at 40¢ + 0.8¢ fee buys 24.51 shares instead of 25
+        assert!((shares_for_budget(10.0, 0.40, 0.02) - 24.5098).abs() < 1e-3);
+        assert_eq!(shares_for_budget(10.0, 0.40, 0.0), 25.0);
+        
+        // Fees eat into upside: 150% -> 145.1%
+        assert!((calculate_net_upside(0.40, 0.02) - 1.4510).abs() < 1e-3);
+        assert_eq!(calculate_net_upside(0.40, 0.0), calculate_upside(0.40));
+        
+        // The filter reports upside net of fees
+        let (upside, _) = passes_value_filters(0.40, 0.39, 0.40, 0.02).unwrap();
+        assert!((upside - 1.4510).abs() < 1e-3);
+
+        // Exits pay their own fee: 25 shares 40¢ -> 70¢ nets $7.50 - $0.20 - $0.15
+        assert!((exit_pnl(0.40, 0.70, 25.0, 0.02) - 7.15).abs() < 1e-9);
+        assert!((exit_pnl(0.40, 0.30, 25.0, 0.0) + 2.50).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_passes_edge_filter() {
+        // 55% fair vs 40¢ + 0.8¢ fee: 14.2¢ edge
+        assert!((passes_edge_filter(0.40, 0.55, 0.02).unwrap() - 0.142).abs() < 1e-9);
+        // 44% fair: edge 4¢ before fees, below MIN_EDGE
+        assert!(passes_edge_filter(0.40, 0.44, 0.0).is_err());
+        // Fees can push a marginal edge below the threshold
+        assert!(passes_edge_filter(0.40, 0.455, 0.0).is_ok());
+        assert!(passes_edge_filter(0.40, 0.455, 0.02).is_err());
+    }
+    
+    #[test]
+    fn test_passes_latency_filters() {
+        // 92¢ is far above MAX_ENTRY_PRICE but fine with a 97% fair value
+        let (upside, _) = passes_latency_filters(0.92, 0.91, 0.92, 0.97, 0.0).unwrap();
+        assert!((upside - 0.0870).abs() < 1e-3);
+        assert_eq!(passes_latency_filters(0.92, 0.91, 0.92, 0.90, 0.0), Err("edge gone"));
+        assert_eq!(passes_latency_filters(0.92, 0.70, 0.92, 0.97, 0.0), Err("spread too wide"));
     }
 }
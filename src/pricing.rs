@@ -0,0 +1,277 @@
+//! Fair-value model for Up/Down markets
+//!
+//! An Up/Down market is a digital option struck at the window's open price:
+//! it pays $1 if spot at expiry is at or above the open. Assuming driftless
+//! log-normal spot moves over the (short) remaining time, the fair
+//! probability of Up is `Φ(d2)` with
+//!
+//!   d2 = (ln(S / K) - σ²τ / 2) / (σ √τ)
+//!
+//! where `S` is spot, `K` the open (strike), `σ` the realized volatility per
+//! second and `τ` the seconds to expiry. This is the Rust counterpart of
+//! `estimateProbabilityUp` in the TS `latency_strategy.ts`.
+
+use crate::types::PriceSnapshot;
+use std::collections::VecDeque;
+
+/// Probabilities are clamped away from certainty: a model this simple
+/// should never justify paying 100¢
+pub const MIN_PROBABILITY: f64 = 0.001;
+pub const MAX_PROBABILITY: f64 = 1.0 - MIN_PROBABILITY;
+
+/// Bucket size used to sample prices for realized volatility
+pub const VOL_SAMPLE_MS: u64 = 1_000;
+
+/// Minimum number of returns before a realized volatility is trusted
+const MIN_VOL_RETURNS: usize = 10;
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26, |error| < 1.5e-7)
+pub fn normal_cdf(z: f64) -> f64 {
+    const A1: f64 = 0.254829592;
+    const A2: f64 = -0.284496736;
+    const A3: f64 = 1.421413741;
+    const A4: f64 = -1.453152027;
+    const A5: f64 = 1.061405429;
+    const P: f64 = 0.3275911;
+
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + P * x);
+    let erf = 1.0 - (((((A5 * t + A4) * t) + A3) * t + A2) * t + A1) * t * (-x * x).exp();
+    0.5 * (1.0 + z.signum() * erf)
+}
+
+/// Probability that spot finishes at or above `strike` after `secs_to_expiry`
+pub fn probability_above(spot: f64, strike: f64, sigma_per_sec: f64, secs_to_expiry: f64) -> f64 {
+    if spot <= 0.0 || strike <= 0.0 {
+        return 0.5;
+    }
+    let spread = sigma_per_sec * secs_to_expiry.max(0.0).sqrt();
+    let p = if spread < 1e-9 {
+        // No time or no volatility left: the outcome is already decided
+        if spot >= strike { 1.0 } else { 0.0 }
+    } else {
+        let d2 = ((spot / strike).ln() - 0.5 * spread * spread) / spread;
+        normal_cdf(d2)
+    };
+    p.clamp(MIN_PROBABILITY, MAX_PROBABILITY)
+}
+
+/// Fair probability that an Up/Down window resolves Up
+pub fn fair_probability_up(spot: f64, window_open: f64, sigma_per_sec: f64, secs_to_expiry: f64) -> f64 {
+    probability_above(spot, window_open, sigma_per_sec, secs_to_expiry)
+}
+
+/// Trailing window realized volatility is measured over
+pub const REALIZED_VOL_WINDOW_MS: u64 = 900_000; // 15 minutes
+
+/// Realized volatility per second over a trailing window of trades: prices
+/// are sampled at the end of each `VOL_SAMPLE_MS` bucket, and the variance
+/// rate is the sum of squared log returns over the elapsed time (robust to
+/// gaps). The sums are kept as trades arrive and buckets leave the window,
+/// so an update is amortized O(1) and reading the estimate is O(1).
+#[derive(Debug, Clone)]
+pub struct RealizedVol {
+    window_ms: u64,
+    samples: VecDeque<VolSample>, // Completed buckets, oldest first
+    open: Option<VolSample>,      // Bucket still taking trades
+    sum_sq: f64,                  // Over returns between completed samples
+    elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct VolSample {
+    bucket: u64,
+    price: f64,
+    last_ts: u64, // Latest trade in the bucket: the sample leaves the window with it
+}
+
+impl VolSample {
+    /// Squared log return and elapsed ms from `self` to `next`
+    fn return_to(&self, next: &VolSample) -> (f64, u64) {
+        let r = (next.price / self.price).ln();
+        (r * r, (next.bucket - self.bucket) * VOL_SAMPLE_MS)
+    }
+}
+
+impl Default for RealizedVol {
+    fn default() -> Self {
+        Self::new(REALIZED_VOL_WINDOW_MS)
+    }
+}
+
+impl RealizedVol {
+    pub fn new(window_ms: u64) -> Self {
+        Self { window_ms, samples: VecDeque::new(), open: None, sum_sq: 0.0, elapsed_ms: 0 }
+    }
+
+    /// Add a trade (price in cents, ts in ms)
+    pub fn update(&mut self, price_cents: u64, ts: u64) {
+        if price_cents == 0 {
+            return;
+        }
+        let sample = VolSample { bucket: ts / VOL_SAMPLE_MS, price: price_cents as f64, last_ts: ts };
+        match self.open.as_mut() {
+            // Same bucket (or out of order): keep the latest price
+            Some(open) if sample.bucket <= open.bucket => {
+                open.price = sample.price;
+                open.last_ts = open.last_ts.max(ts);
+            }
+            _ => {
+                if let Some(completed) = self.open.replace(sample) {
+                    if let Some(prev) = self.samples.back() {
+                        let (r2, dt) = prev.return_to(&completed);
+                        self.sum_sq += r2;
+                        self.elapsed_ms += dt;
+                    }
+                    self.samples.push_back(completed);
+                }
+            }
+        }
+
+        // Buckets whose trades have all left the window
+        let cutoff = ts.saturating_sub(self.window_ms);
+        while self.samples.front().is_some_and(|s| s.last_ts < cutoff) {
+            let oldest = self.samples.pop_front().expect("front checked");
+            if let Some(next) = self.samples.front() {
+                let (r2, dt) = oldest.return_to(next);
+                self.sum_sq -= r2;
+                self.elapsed_ms -= dt;
+            }
+        }
+        // No returns left: restart the sums so rounding can't accumulate
+        if self.samples.len() < 2 {
+            self.sum_sq = 0.0;
+            self.elapsed_ms = 0;
+        }
+    }
+
+    /// Volatility per second, counting the bucket still open; `None` until
+    /// there are enough returns to be meaningful
+    pub fn sigma(&self) -> Option<f64> {
+        let open = self.open?;
+        if self.samples.len() < MIN_VOL_RETURNS {
+            return None;
+        }
+        let (mut sum_sq, mut elapsed_ms) = (self.sum_sq, self.elapsed_ms);
+        if let Some(last) = self.samples.back() {
+            let (r2, dt) = last.return_to(&open);
+            sum_sq += r2;
+            elapsed_ms += dt;
+        }
+        (elapsed_ms > 0).then(|| (sum_sq / (elapsed_ms as f64 / 1000.0)).sqrt())
+    }
+}
+
+/// Price at the start of a window (first price at or after `start_ms`),
+/// provided the history reaches back to the window start. The history is in
+/// time order, so this is a binary search.
+pub fn window_open_price(history: &VecDeque<PriceSnapshot>, start_ms: u64) -> Option<f64> {
+    if history.front()?.timestamp_ms > start_ms {
+        return None;
+    }
+    let first = history.partition_point(|s| s.timestamp_ms < start_ms);
+    history.get(first).map(|s| s.price_cents as f64 / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.0) - 0.841345).abs() < 1e-5);
+        assert!((normal_cdf(-1.96) - 0.024998).abs() < 1e-5);
+        assert!((normal_cdf(1.5) + normal_cdf(-1.5) - 1.0).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_fair_probability_up() {
+        // At the open the window is a coin flip (slightly below: σ²τ/2 drag)
+        let p = fair_probability_up(100_000.0, 100_000.0, 0.0002, 600.0);
+        assert!(p < 0.5 && p > 0.49);
+
+        // 0.5% up with 0.02%/√s vol and 5 min left: z ≈ 1.44
+        let p = fair_probability_up(100_500.0, 100_000.0, 0.0002, 300.0);
+        assert!((p - 0.925).abs() < 0.005, "p = {}", p);
+        assert!((fair_probability_up(99_500.0, 100_000.0, 0.0002, 300.0) - (1.0 - p)).abs() < 0.01);
+
+        // Less time left means more certainty
+        assert!(fair_probability_up(100_500.0, 100_000.0, 0.0002, 30.0) > p);
+
+        // Expired: decided, but clamped away from certainty
+        assert_eq!(fair_probability_up(100_001.0, 100_000.0, 0.0002, 0.0), MAX_PROBABILITY);
+        assert_eq!(fair_probability_up(99_999.0, 100_000.0, 0.0002, 0.0), MIN_PROBABILITY);
+        assert_eq!(fair_probability_up(100_000.0, 100_000.0, 0.0, 60.0), MAX_PROBABILITY); // ties resolve Up
+    }
+
+    /// The estimate as a scan of the trailing window's history
+    fn scan_volatility(history: &[(u64, u64)], window_ms: u64) -> Option<f64> {
+        let cutoff = history.last()?.1.saturating_sub(window_ms);
+        let mut samples: Vec<(u64, f64)> = Vec::new();
+        for &(price, ts) in history.iter().filter(|t| t.1 >= cutoff) {
+            let bucket = ts / VOL_SAMPLE_MS;
+            match samples.last_mut() {
+                Some(last) if last.0 == bucket => last.1 = price as f64,
+                _ => samples.push((bucket, price as f64)),
+            }
+        }
+        if samples.len() <= MIN_VOL_RETURNS {
+            return None;
+        }
+        let (mut sum_sq, mut elapsed_ms) = (0.0, 0);
+        for pair in samples.windows(2) {
+            sum_sq += (pair[1].1 / pair[0].1).ln().powi(2);
+            elapsed_ms += (pair[1].0 - pair[0].0) * VOL_SAMPLE_MS;
+        }
+        Some((sum_sq / (elapsed_ms as f64 / 1000.0)).sqrt())
+    }
+
+    #[test]
+    fn test_realized_volatility() {
+        // Alternating ±0.1% every second: per-second vol ≈ 0.001
+        let mut vol = RealizedVol::default();
+        for i in 0..60u64 {
+            let price = if i % 2 == 0 { 10_000_000 } else { 10_010_000 };
+            vol.update(price, 1_000_000 + i * 1_000);
+            // Intra-second ticks only keep the last price of each bucket
+            vol.update(price, 1_000_000 + i * 1_000 + 500);
+            if i < MIN_VOL_RETURNS as u64 {
+                assert_eq!(vol.sigma(), None); // Not enough data
+            }
+        }
+        let sigma = vol.sigma().unwrap();
+        assert!((sigma - 0.001).abs() < 1e-5, "vol = {}", sigma);
+    }
+
+    #[test]
+    fn test_realized_volatility_matches_scan() {
+        // Bursty trades with gaps, against a scan of the trailing window
+        let window_ms = 20_000;
+        let mut vol = RealizedVol::new(window_ms);
+        let mut history = Vec::new();
+        let (mut price, mut ts, mut seed) = (10_000_000u64, 0u64, 11u64);
+        for _ in 0..5_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            price = price + (seed >> 58) - 31;
+            ts += ((seed >> 50) & 1023).pow(2) / 1024; // Mostly under 1s, sometimes several
+            vol.update(price, ts);
+            history.push((price, ts));
+            match (vol.sigma(), scan_volatility(&history, window_ms)) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9 * b.max(1e-9), "{} vs {}", a, b),
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+
+    #[test]
+    fn test_window_open_price() {
+        let history: VecDeque<_> = [(9_990_000, 999_000), (10_000_000, 1_000_000), (10_050_000, 1_001_000)]
+            .iter()
+            .map(|&(p, t)| PriceSnapshot::new(p, t))
+            .collect();
+        assert_eq!(window_open_price(&history, 1_000_000), Some(100_000.0));
+        // History starts after the window opened: unknown
+        assert_eq!(window_open_price(&history, 998_000), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! Redemption of resolved winning positions
+//!
+//! Winning outcome tokens only turn back into USDC once they are redeemed
+//! through the Conditional Tokens (CTF) contract, or through the NegRisk
+//! adapter for neg-risk markets. Positions held by a Polymarket proxy wallet
+//! are redeemed through the proxy wallet factory (see `contracts`).
+
+use crate::contracts::{ChainClient, ConditionalTokens, ContractConfig, NegRiskAdapter, TxSender};
+use crate::exchange::ExchangeResult;
+use alloy_primitives::{Address, Bytes, TxHash, B256, U256};
+use alloy_sol_types::SolCall;
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Index sets covering both outcomes of a binary condition (0b01, 0b10)
+const BINARY_INDEX_SETS: [u64; 2] = [1, 2];
+
+/// Default interval between redemption sweeps
+const DEFAULT_REDEEM_INTERVAL_SECS: u64 = 600;
+
+/// A held position that may be redeemable
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedeemCandidate {
+    pub condition_id: B256,
+    pub token_id: U256,
+    pub outcome_index: usize,
+    pub neg_risk: bool,
+}
+
+/// Redeemable position as returned by the data API `/positions?redeemable=true`
+#[derive(Debug, Deserialize)]
+struct DataApiRedeemable {
+    asset: String,
+    #[serde(rename = "conditionId")]
+    condition_id: String,
+    #[serde(rename = "outcomeIndex", default)]
+    outcome_index: usize,
+    #[serde(rename = "negativeRisk", default)]
+    negative_risk: bool,
+}
+
+impl DataApiRedeemable {
+    fn into_candidate(self) -> Option<RedeemCandidate> {
+        Some(RedeemCandidate {
+            condition_id: B256::from_str(&self.condition_id).ok()?,
+            token_id: U256::from_str_radix(&self.asset, 10).ok()?,
+            outcome_index: self.outcome_index,
+            neg_risk: self.negative_risk,
+        })
+    }
+}
+
+/// Positions the data API reports as redeemable for `holder`
+pub async fn fetch_redeem_candidates(http: &reqwest::Client, holder: Address) -> ExchangeResult<Vec<RedeemCandidate>> {
+    let url = format!(
+        "{}/positions?user={:?}&redeemable=true&sizeThreshold=0",
+        crate::endpoints::data_api_url(),
+        holder
+    );
+    let positions: Vec<DataApiRedeemable> = http.get(&url).send().await?.error_for_status()?.json().await?;
+    Ok(positions.into_iter().filter_map(DataApiRedeemable::into_candidate).collect())
+}
+
+/// Submits redemptions for one holder (EOA or proxy wallet)
+pub struct Redeemer {
+    client: Arc<ChainClient>,
+    ctf: Address,
+    neg_risk_adapter: Address,
+    collateral: Address,
+    sender: TxSender,
+}
+
+impl Redeemer {
+    pub fn new(sender: TxSender, config: &ContractConfig) -> Self {
+        Self {
+            client: sender.client(),
+            ctf: config.ctf,
+            neg_risk_adapter: config.neg_risk_adapter,
+            collateral: config.collateral,
+            sender,
+        }
+    }
+
+    /// Address whose positions are redeemed
+    pub fn holder(&self) -> Address {
+        self.sender.holder()
+    }
+
+    /// Whether the condition has been resolved on chain
+    pub async fn is_resolved(&self, condition_id: B256) -> ExchangeResult<bool> {
+        let call = ConditionalTokens::payoutDenominatorCall { conditionId: condition_id };
+        Ok(!self.client.call(self.ctf, &call).await?._0.is_zero())
+    }
+
+    /// Outcome token balance of the holder
+    pub async fn balance(&self, token_id: U256) -> ExchangeResult<U256> {
+        let call = ConditionalTokens::balanceOfCall { owner: self.holder(), id: token_id };
+        Ok(self.client.call(self.ctf, &call).await?._0)
+    }
+
+    /// Target contract and calldata for redeeming `candidate` (holding `balance`)
+    fn redeem_call(&self, candidate: &RedeemCandidate, balance: U256) -> (Address, Bytes) {
+        if candidate.neg_risk {
+            let amounts = neg_risk_amounts(candidate.outcome_index, balance);
+            let call = NegRiskAdapter::redeemPositionsCall { conditionId: candidate.condition_id, amounts };
+            (self.neg_risk_adapter, call.abi_encode().into())
+        } else {
+            let call = ConditionalTokens::redeemPositionsCall {
+                collateralToken: self.collateral,
+                parentCollectionId: B256::ZERO,
+                conditionId: candidate.condition_id,
+                indexSets: BINARY_INDEX_SETS.iter().map(|&i| U256::from(i)).collect(),
+            };
+            (self.ctf, call.abi_encode().into())
+        }
+    }
+
+    /// Redeem one position if its condition has resolved and the holder has
+    /// a balance; returns the transaction hash, or `None` if there was
+    /// nothing to redeem
+    pub async fn redeem(&self, candidate: &RedeemCandidate) -> ExchangeResult<Option<TxHash>> {
+        if !self.is_resolved(candidate.condition_id).await? {
+            return Ok(None);
+        }
+        let balance = self.balance(candidate.token_id).await?;
+        if balance.is_zero() {
+            return Ok(None);
+        }
+
+        let (to, calldata) = self.redeem_call(candidate, balance);
+        Ok(Some(self.sender.send(to, calldata).await?))
+    }
+}
+
+/// NegRisk adapter amounts: `[yes, no]`, with the held balance at the outcome index
+fn neg_risk_amounts(outcome_index: usize, balance: U256) -> Vec<U256> {
+    let mut amounts = vec![U256::ZERO; 2];
+    amounts[outcome_index.min(1)] = balance;
+    amounts
+}
+
+/// Periodically redeem resolved positions (`REDEEM_INTERVAL_SECS`)
+pub async fn run_redeemer(redeemer: Redeemer, db_logger: Arc<crate::database::DbLogger>) {
+    let interval_secs = std::env::var("REDEEM_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_REDEEM_INTERVAL_SECS);
+    println!("💸 Redeemer running every {}s for {:?}", interval_secs, redeemer.holder());
+
+    let http = reqwest::Client::new();
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+
+        let candidates = match fetch_redeem_candidates(&http, redeemer.holder()).await {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                eprintln!("⚠️ Redeemer: failed to fetch redeemable positions: {}", e);
+                continue;
+            }
+        };
+
+        for candidate in candidates {
+            match redeemer.redeem(&candidate).await {
+                Ok(Some(tx)) => {
+                    println!("💸 Redeemed {:?} (tx {:?})", candidate.condition_id, tx);
+                    db_logger.log_activity(
+                        "success",
+                        "position",
+                        &format!("Redeemed resolved position {:?}", candidate.condition_id),
+                        Some(format!(r#"{{"condition_id": "{:?}", "neg_risk": {}, "tx": "{:?}"}}"#,
+                            candidate.condition_id, candidate.neg_risk, tx)),
+                    );
+                }
+                Ok(None) => {} // Not resolved on chain yet, or already redeemed
+                Err(e) => {
+                    eprintln!("❌ Redeem failed for {:?}: {}", candidate.condition_id, e);
+                    db_logger.log_activity(
+                        "error",
+                        "position",
+                        &format!("Redeem failed for {:?}: {}", candidate.condition_id, e),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::mock_chain::{position_id, MockChain};
+    use crate::contracts::Erc20;
+    use alloy_signer_local::PrivateKeySigner;
+
+    fn offline_redeemer(proxy_wallet: Option<Address>) -> Redeemer {
+        let client = Arc::new(ChainClient::new("http://127.0.0.1:1", PrivateKeySigner::random(), 137));
+        let config = ContractConfig {
+            ctf: Address::repeat_byte(0xc7),
+            neg_risk_adapter: Address::repeat_byte(0xad),
+            proxy_factory: Address::repeat_byte(0xfa),
+            collateral: Address::repeat_byte(0x05),
+            ctf_exchange: Address::repeat_byte(0xe1),
+            neg_risk_exchange: Address::repeat_byte(0xe2),
+        };
+        let sender = TxSender::new(client, &config, proxy_wallet);
+        Redeemer::new(sender, &config)
+    }
+
+    fn candidate(neg_risk: bool) -> RedeemCandidate {
+        RedeemCandidate {
+            condition_id: B256::repeat_byte(0xcd),
+            token_id: U256::from(42),
+            outcome_index: 1,
+            neg_risk,
+        }
+    }
+
+    #[test]
+    fn test_ctf_redeem_call_covers_both_outcomes() {
+        let redeemer = offline_redeemer(None);
+        let (to, data) = redeemer.redeem_call(&candidate(false), U256::from(10));
+
+        assert_eq!(to, Address::repeat_byte(0xc7));
+        let call = ConditionalTokens::redeemPositionsCall::abi_decode(&data, true).unwrap();
+        assert_eq!(call.collateralToken, Address::repeat_byte(0x05));
+        assert_eq!(call.parentCollectionId, B256::ZERO);
+        assert_eq!(call.indexSets, vec![U256::from(1), U256::from(2)]);
+    }
+
+    #[test]
+    fn test_neg_risk_redeem_call_uses_adapter_amounts() {
+        let redeemer = offline_redeemer(Some(Address::repeat_byte(0x99)));
+        let (to, data) = redeemer.redeem_call(&candidate(true), U256::from(10));
+
+        assert_eq!(to, Address::repeat_byte(0xad));
+        let call = NegRiskAdapter::redeemPositionsCall::abi_decode(&data, true).unwrap();
+        assert_eq!(call.amounts, vec![U256::ZERO, U256::from(10)]);
+        // Proxy wallets hold the positions
+        assert_eq!(redeemer.holder(), Address::repeat_byte(0x99));
+    }
+
+    #[test]
+    fn test_data_api_candidate_parsing() {
+        let json = r#"[{"asset": "123", "conditionId": "0xcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd", "outcomeIndex": 0, "negativeRisk": true},
+                       {"asset": "not-a-number", "conditionId": "0x00"}]"#;
+        let parsed: Vec<DataApiRedeemable> = serde_json::from_str(json).unwrap();
+        let candidates: Vec<_> = parsed.into_iter().filter_map(DataApiRedeemable::into_candidate).collect();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].token_id, U256::from(123));
+        assert!(candidates[0].neg_risk);
+    }
+
+    /// Needs `anvil` (or `ANVIL_BIN`); skipped without it
+    #[tokio::test]
+    async fn test_redeem_on_mock_chain() {
+        let Some(chain) = MockChain::start().await else { return };
+        let condition_id = B256::repeat_byte(0xcd);
+        let token_id = position_id(condition_id, BINARY_INDEX_SETS[1]);
+        chain.mint_position(token_id, U256::from(10_000_000)).await;
+
+        let redeemer = Redeemer::new(chain.sender(), &chain.config);
+        let candidate = RedeemCandidate { condition_id, token_id, outcome_index: 1, neg_risk: false };
+
+        // Unresolved: nothing is sent
+        assert_eq!(redeemer.redeem(&candidate).await.unwrap(), None);
+
+        chain.resolve(condition_id).await;
+        assert!(redeemer.redeem(&candidate).await.unwrap().is_some());
+        assert_eq!(redeemer.balance(token_id).await.unwrap(), U256::ZERO);
+        let usdc = chain.client.call(chain.config.collateral, &Erc20::balanceOfCall { owner: chain.signer() }).await.unwrap();
+        assert_eq!(usdc._0, U256::from(10_000_000));
+
+        // Already redeemed
+        assert_eq!(redeemer.redeem(&candidate).await.unwrap(), None);
+    }
+}
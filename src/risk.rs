@@ -2,9 +2,19 @@
 //! 
 //! Implements adaptive risk tiers based on session P&L.
 //! Tiers: Conservative, Moderate, Aggressive
+//!
+//! Also tracks net directional exposure across assets and expiries.
+//! BTC, ETH and SOL move together, so an "Up" on each is the same bet
+//! three times; the correlated exposure cap limits that stacking.
+//!
+//! New entries can be halted (e.g. by the reconciler when our view of the
+//! exchange can't be trusted); exits are still allowed.
 
+use crate::ledger::LedgerPosition;
+use crate::types::Side;
+use chrono::{DateTime, Utc};
 use std::sync::atomic::{AtomicI64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 /// Risk tier levels
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -56,6 +66,29 @@ impl RiskTier {
 const PROFIT_THRESHOLD_CENTS: i64 = 1000;  // $10 profit -> Aggressive
 const LOSS_THRESHOLD_CENTS: i64 = -1000;   // $10 loss -> Conservative
 
+/// Default cap on net directional exposure across all assets ($)
+pub const DEFAULT_CORRELATED_EXPOSURE_CAP: f64 = 20.0;
+
+/// A single open directional bet counted towards correlated exposure
+#[derive(Debug, Clone)]
+struct ExposureEntry {
+    asset: String,
+    market_type: String,
+    side: Side,
+    size_cents: i64,
+    expires_at: DateTime<Utc>,
+}
+
+impl ExposureEntry {
+    /// Signed exposure: Up (YES) is positive, Down (NO) is negative
+    fn signed_cents(&self) -> i64 {
+        match self.side {
+            Side::Yes => self.size_cents,
+            Side::No => -self.size_cents,
+        }
+    }
+}
+
 /// Thread-safe risk manager for tracking session P&L and tier
 #[derive(Clone)]
 pub struct RiskManager {
@@ -63,6 +96,12 @@ pub struct RiskManager {
     session_pnl_cents: Arc<AtomicI64>,
     /// Starting balance in dollars
     starting_balance: f64,
+    /// Open directional bets (expired entries are pruned lazily)
+    exposures: Arc<Mutex<Vec<ExposureEntry>>>,
+    /// Max absolute net directional exposure in cents
+    correlated_cap_cents: i64,
+    /// Why new entries are halted (None = trading normally)
+    halt_reason: Arc<Mutex<Option<String>>>,
 }
 
 impl RiskManager {
@@ -72,8 +111,17 @@ impl RiskManager {
         Self {
             session_pnl_cents: Arc::new(AtomicI64::new(0)),
             starting_balance,
+            exposures: Arc::new(Mutex::new(Vec::new())),
+            correlated_cap_cents: (DEFAULT_CORRELATED_EXPOSURE_CAP * 100.0) as i64,
+            halt_reason: Arc::new(Mutex::new(None)),
         }
     }
+
+    /// Override the correlated exposure cap (in dollars)
+    pub fn with_correlated_exposure_cap(mut self, cap_dollars: f64) -> Self {
+        self.correlated_cap_cents = (cap_dollars * 100.0) as i64;
+        self
+    }
     
     /// Update session P&L and return the new tier
     pub fn update_pnl(&self, pnl_dollars: f64) -> RiskTier {
@@ -121,4 +169,196 @@ impl RiskManager {
     pub fn get_max_exposure(&self) -> f64 {
         self.starting_balance * self.current_tier().exposure_percent()
     }
+    
+    /// Get the correlated exposure cap in dollars
+    pub fn get_correlated_exposure_cap(&self) -> f64 {
+        self.correlated_cap_cents as f64 / 100.0
+    }
+    
+    /// Reserve directional exposure for a new position.
+    ///
+    /// Fails if the trade would push the absolute net exposure (Up minus Down,
+    /// summed over every asset and expiry) above the cap. Trades that reduce
+    /// the net exposure are always allowed.
+    pub fn try_add_exposure(
+        &self,
+        asset: &str,
+        market_type: &str,
+        side: Side,
+        size_dollars: f64,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let entry = ExposureEntry {
+            asset: asset.to_string(),
+            market_type: market_type.to_string(),
+            side,
+            size_cents: (size_dollars * 100.0) as i64,
+            expires_at,
+        };
+        
+        let mut exposures = self.exposures.lock().expect("exposure lock poisoned");
+        prune_expired(&mut exposures, Utc::now());
+        
+        let net: i64 = exposures.iter().map(|e| e.signed_cents()).sum();
+        let projected = net + entry.signed_cents();
+        if projected.abs() > self.correlated_cap_cents && projected.abs() > net.abs() {
+            return Err(format!(
+                "correlated exposure cap: net ${:.2} -> ${:.2} exceeds ${:.2}",
+                net as f64 / 100.0,
+                projected as f64 / 100.0,
+                self.correlated_cap_cents as f64 / 100.0
+            ));
+        }
+        
+        exposures.push(entry);
+        Ok(())
+    }
+    
+    /// Release exposure when a position is closed (or its order failed).
+    ///
+    /// Removes the oldest matching entry for the asset, market type and side.
+    pub fn release_exposure(&self, asset: &str, market_type: &str, side: Side) {
+        let mut exposures = self.exposures.lock().expect("exposure lock poisoned");
+        if let Some(idx) = exposures
+            .iter()
+            .position(|e| e.asset == asset && e.market_type == market_type && e.side == side)
+        {
+            exposures.remove(idx);
+        }
+    }
+    
+    /// Replace tracked exposure with positions already held on the venue
+    /// (recovered at startup, or adopted by the reconciler). The cap isn't
+    /// checked: the positions exist whether it allows them or not.
+    pub fn set_positions<'a>(&self, positions: impl IntoIterator<Item = &'a LedgerPosition>) {
+        let mut exposures = self.exposures.lock().expect("exposure lock poisoned");
+        *exposures = positions
+            .into_iter()
+            .filter(|p| p.shares > 0.0)
+            .map(|p| ExposureEntry {
+                asset: p.asset.clone(),
+                market_type: p.market_type.label().to_string(),
+                side: p.side,
+                size_cents: (p.shares * p.avg_price * 100.0) as i64,
+                expires_at: p.expires_at,
+            })
+            .collect();
+        prune_expired(&mut exposures, Utc::now());
+    }
+    
+    /// Net directional exposure in dollars (positive = net Up)
+    pub fn get_net_exposure(&self) -> f64 {
+        let mut exposures = self.exposures.lock().expect("exposure lock poisoned");
+        prune_expired(&mut exposures, Utc::now());
+        exposures.iter().map(|e| e.signed_cents()).sum::<i64>() as f64 / 100.0
+    }
+    
+    /// Halt new entries; returns false if already halted
+    pub fn halt(&self, reason: &str) -> bool {
+        let mut halt = self.halt_reason.lock().expect("halt lock poisoned");
+        let newly_halted = halt.is_none();
+        *halt = Some(reason.to_string());
+        if newly_halted {
+            println!("🛑 Trading HALTED: {}", reason);
+        }
+        newly_halted
+    }
+    
+    /// Resume new entries; returns false if not halted
+    pub fn resume(&self) -> bool {
+        let was_halted = self.halt_reason.lock().expect("halt lock poisoned").take().is_some();
+        if was_halted {
+            println!("✅ Trading resumed");
+        }
+        was_halted
+    }
+    
+    /// Reason new entries are halted, if they are
+    pub fn halt_reason(&self) -> Option<String> {
+        self.halt_reason.lock().expect("halt lock poisoned").clone()
+    }
+}
+
+/// Drop exposure entries whose market has already resolved
+fn prune_expired(exposures: &mut Vec<ExposureEntry>, now: DateTime<Utc>) {
+    exposures.retain(|e| e.expires_at > now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MarketType;
+    
+    fn in_one_hour() -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::hours(1)
+    }
+    
+    #[test]
+    fn test_correlated_cap_blocks_stacking_across_assets() {
+        let rm = RiskManager::new(100.0).with_correlated_exposure_cap(20.0);
+        assert!(rm.try_add_exposure("BTC", "60-MIN", Side::Yes, 10.0, in_one_hour()).is_ok());
+        assert!(rm.try_add_exposure("ETH", "60-MIN", Side::Yes, 10.0, in_one_hour()).is_ok());
+        // Third "Up" through a different ticker is the same bet
+        assert!(rm.try_add_exposure("SOL", "15-MIN", Side::Yes, 10.0, in_one_hour()).is_err());
+        assert!((rm.get_net_exposure() - 20.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_opposite_direction_reduces_exposure() {
+        let rm = RiskManager::new(100.0).with_correlated_exposure_cap(20.0);
+        rm.try_add_exposure("BTC", "60-MIN", Side::Yes, 10.0, in_one_hour()).unwrap();
+        rm.try_add_exposure("ETH", "60-MIN", Side::Yes, 10.0, in_one_hour()).unwrap();
+        assert!(rm.try_add_exposure("SOL", "60-MIN", Side::No, 10.0, in_one_hour()).is_ok());
+        assert!((rm.get_net_exposure() - 10.0).abs() < 1e-9);
+        
+        rm.release_exposure("BTC", "60-MIN", Side::Yes);
+        assert!(rm.get_net_exposure().abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_expired_exposure_is_pruned() {
+        let rm = RiskManager::new(100.0).with_correlated_exposure_cap(10.0);
+        let past = Utc::now() - chrono::Duration::minutes(1);
+        rm.try_add_exposure("BTC", "15-MIN", Side::Yes, 10.0, past).unwrap();
+        assert!(rm.try_add_exposure("ETH", "15-MIN", Side::Yes, 10.0, in_one_hour()).is_ok());
+    }
+    
+    #[test]
+    fn test_held_positions_count_towards_cap() {
+        let rm = RiskManager::new(100.0).with_correlated_exposure_cap(20.0);
+        let position = |asset: &str, side, shares| LedgerPosition {
+            asset: asset.to_string(),
+            market_type: MarketType::Fifteen,
+            token_id: format!("{}-{:?}", asset, side),
+            condition_id: "0xabc".to_string(),
+            side,
+            shares,
+            avg_price: 0.5,
+            expires_at: in_one_hour(),
+            updated_at: Utc::now(),
+        };
+        // Recovered at startup: $15 Up, plus a closed position that doesn't count
+        rm.set_positions(&[position("BTC", Side::Yes, 30.0), position("ETH", Side::No, 0.0)]);
+        assert!((rm.get_net_exposure() - 15.0).abs() < 1e-9);
+        assert!(rm.try_add_exposure("SOL", "15-MIN", Side::Yes, 10.0, in_one_hour()).is_err());
+        
+        // Adopting exchange state replaces what was tracked rather than adding to it
+        rm.set_positions(&[position("BTC", Side::Yes, 30.0), position("ETH", Side::No, 10.0)]);
+        assert!((rm.get_net_exposure() - 10.0).abs() < 1e-9);
+        rm.release_exposure("ETH", "15-MIN", Side::No);
+        assert!((rm.get_net_exposure() - 15.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn test_halt_is_shared_between_clones() {
+        let rm = RiskManager::new(100.0);
+        let execution_view = rm.clone();
+        
+        assert!(rm.halt("mismatch"));
+        assert!(!rm.halt("still mismatched"));
+        assert_eq!(execution_view.halt_reason().as_deref(), Some("still mismatched"));
+        
+        assert!(rm.resume());
+        assert!(execution_view.halt_reason().is_none());
+    }
 }
@@ -0,0 +1,194 @@
+//! Incremental statistics over rolling time windows
+//!
+//! `RollingWindows` keeps an asset's trades for its longest window in one
+//! `VecDeque`, plus a start cursor and running price × quantity / quantity
+//! sums per window. Each trade is pushed once and every cursor advances past
+//! the trades that left its window, so a tick costs amortized O(windows)
+//! rather than a scan of the history. The start price, VWAP and return of
+//! every window are then read in O(1).
+//!
+//! A window starts at its first trade at or after `now - window_ms`, the
+//! same rule the price history is trimmed by. Depends on `std` only, so
+//! `benches/momentum.rs` can include it.
+
+use std::collections::VecDeque;
+
+/// Lookbacks kept per asset (ms): 1, 3, 5 and 10 minutes
+pub const WINDOWS_MS: [u64; 4] = [60_000, 180_000, 300_000, 600_000];
+/// Running quantities below this are rounding residue (Binance lots are >= 1e-8)
+const MIN_QUANTITY: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy)]
+struct Trade {
+    price: u64, // Cents
+    quantity: f64,
+    ts: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    window_ms: u64,
+    start: u64, // Sequence number of the window's first trade
+    notional: f64,
+    quantity: f64,
+}
+
+/// One window as of the latest trade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStats {
+    pub start_price: u64, // Cents, first trade in the window
+    pub last_price: u64,  // Cents
+    pub vwap: Option<f64>, // Cents; `None` without volume
+    pub trades: u64,
+}
+
+impl WindowStats {
+    /// Return since the window start (fraction)
+    pub fn momentum(&self) -> f64 {
+        if self.start_price == 0 {
+            return 0.0;
+        }
+        (self.last_price as f64 - self.start_price as f64) / self.start_price as f64
+    }
+}
+
+#[derive(Debug)]
+pub struct RollingWindows {
+    trades: VecDeque<Trade>,
+    dropped: u64, // Trades popped from the front: the sequence number of trades[0]
+    cursors: Vec<Cursor>,
+}
+
+impl Default for RollingWindows {
+    fn default() -> Self {
+        Self::new(&WINDOWS_MS)
+    }
+}
+
+impl RollingWindows {
+    pub fn new(windows_ms: &[u64]) -> Self {
+        Self {
+            trades: VecDeque::with_capacity(1000),
+            dropped: 0,
+            cursors: windows_ms.iter().map(|&window_ms| Cursor { window_ms, start: 0, notional: 0.0, quantity: 0.0 }).collect(),
+        }
+    }
+
+    /// Add a trade (price in cents, ts in ms)
+    pub fn push(&mut self, price: u64, quantity: f64, ts: u64) {
+        self.trades.push_back(Trade { price, quantity, ts });
+        let newest = self.dropped + self.trades.len() as u64 - 1;
+
+        for cursor in self.cursors.iter_mut() {
+            cursor.notional += price as f64 * quantity;
+            cursor.quantity += quantity;
+            let cutoff = ts.saturating_sub(cursor.window_ms);
+            while cursor.start < newest {
+                let oldest = self.trades[(cursor.start - self.dropped) as usize];
+                if oldest.ts >= cutoff {
+                    break;
+                }
+                cursor.notional -= oldest.price as f64 * oldest.quantity;
+                cursor.quantity -= oldest.quantity;
+                cursor.start += 1;
+            }
+            // Only the new trade left: restart the sums so rounding can't accumulate
+            if cursor.start == newest {
+                cursor.notional = price as f64 * quantity;
+                cursor.quantity = quantity;
+            }
+        }
+
+        // Trades behind every cursor are no longer needed
+        let keep_from = self.cursors.iter().map(|c| c.start).min().unwrap_or(newest);
+        while self.dropped < keep_from {
+            self.trades.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// Statistics of the window of length `window_ms`; `None` before the
+    /// first trade or for a length not tracked
+    pub fn stats(&self, window_ms: u64) -> Option<WindowStats> {
+        let cursor = self.cursors.iter().find(|c| c.window_ms == window_ms)?;
+        let start = self.trades.get((cursor.start - self.dropped) as usize)?;
+        let last = self.trades.back()?;
+        Some(WindowStats {
+            start_price: start.price,
+            last_price: last.price,
+            vwap: (cursor.quantity > MIN_QUANTITY).then(|| cursor.notional / cursor.quantity),
+            trades: self.dropped + self.trades.len() as u64 - cursor.start,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_start_and_momentum() {
+        let mut windows = RollingWindows::new(&[120_000, 180_000]);
+        assert_eq!(windows.stats(180_000), None);
+        for (price, ts) in [(10_000_000, 1_000), (10_050_000, 61_000), (10_100_000, 181_000)] {
+            windows.push(price, 1.0, ts);
+        }
+        // 3-minute window starts at the first price: +1%
+        let three = windows.stats(180_000).unwrap();
+        assert_eq!((three.start_price, three.trades), (10_000_000, 3));
+        assert!((three.momentum() - 0.01).abs() < 1e-9);
+        // 2-minute window starts at the second price
+        let two = windows.stats(120_000).unwrap();
+        assert!((two.momentum() - 50_000.0 / 10_050_000.0).abs() < 1e-9);
+        assert_eq!(windows.stats(60_000), None);
+    }
+
+    #[test]
+    fn test_vwap_drops_expired_trades() {
+        let mut windows = RollingWindows::new(&[10_000, 60_000]);
+        windows.push(100, 1.0, 0);
+        windows.push(200, 3.0, 5_000);
+        assert_eq!(windows.stats(10_000).unwrap().vwap, Some(175.0));
+
+        // 12s: the first trade left the 10s window but not the 60s one
+        windows.push(400, 1.0, 12_000);
+        let short = windows.stats(10_000).unwrap();
+        assert_eq!((short.start_price, short.trades, short.vwap), (200, 2, Some(250.0)));
+        assert_eq!(windows.stats(60_000).unwrap().vwap, Some(1_100.0 / 5.0));
+
+        // No volume: no VWAP
+        windows.push(500, 0.0, 100_000);
+        assert_eq!(windows.stats(60_000).unwrap().vwap, None);
+        assert_eq!(windows.trades.len(), 1);
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        // Bursty trades with a slow random walk, against a scan of the history
+        let windows_ms = [3_000, 20_000];
+        let mut windows = RollingWindows::new(&windows_ms);
+        let mut history: Vec<(u64, f64, u64)> = Vec::new();
+        let (mut price, mut ts, mut seed) = (10_000_000u64, 0u64, 7u64);
+        for _ in 0..5_000 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            price = price + (seed >> 60) - 7; // Steps of -7..=8 cents
+            ts += (seed >> 54) & 63;
+            let quantity = ((seed >> 40) & 0xff) as f64 / 100.0;
+            windows.push(price, quantity, ts);
+            history.push((price, quantity, ts));
+
+            for window_ms in windows_ms {
+                let cutoff = ts.saturating_sub(window_ms);
+                let in_window: Vec<_> = history.iter().filter(|t| t.2 >= cutoff).collect();
+                let stats = windows.stats(window_ms).unwrap();
+                assert_eq!((stats.start_price, stats.trades), (in_window[0].0, in_window.len() as u64));
+                let quantity: f64 = in_window.iter().map(|t| t.1).sum();
+                let vwap = (quantity > 0.0).then(|| in_window.iter().map(|t| t.0 as f64 * t.1).sum::<f64>() / quantity);
+                match (stats.vwap, vwap) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 1e-6 * b, "{} vs {}", a, b),
+                    (a, b) => assert_eq!(a.is_some(), b.is_some()),
+                }
+            }
+        }
+    }
+}
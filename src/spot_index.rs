@@ -0,0 +1,166 @@
+//! Composite spot index
+//!
+//! Combines the latest price of each venue (Binance trades plus the feeds
+//! in `spot_feeds.rs`) into one robust spot price per asset:
+//!
+//! 1. Quotes older than `stale_ms` are ignored.
+//! 2. Quotes further than `max_deviation` from the median are rejected as
+//!    outliers (a venue glitch or a stuck feed can't move the index).
+//! 3. The survivors are averaged, each weighted by its freshness, falling
+//!    linearly from 1 for a new quote to 0 at `stale_ms`.
+//!
+//! With only Binance connected the index is Binance's last trade.
+
+use crate::spot_feeds::{SpotVenue, VenueTick};
+use std::collections::HashMap;
+
+/// Quotes older than this carry no weight (ms)
+const DEFAULT_STALE_MS: u64 = 5_000;
+/// Maximum distance from the median before a venue is rejected (fraction)
+const DEFAULT_MAX_DEVIATION: f64 = 0.005;
+
+/// Which spot price a strategy prices against (`binance` or `composite`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpotSource {
+    #[default]
+    Binance,
+    Composite,
+}
+
+impl SpotSource {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "binance" => Some(SpotSource::Binance),
+            "composite" | "index" => Some(SpotSource::Composite),
+            _ => None,
+        }
+    }
+}
+
+/// `INDEX_STALE_MS`, `INDEX_MAX_DEVIATION`
+#[derive(Debug, Clone, Copy)]
+pub struct IndexConfig {
+    pub stale_ms: u64,
+    pub max_deviation: f64,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self { stale_ms: DEFAULT_STALE_MS, max_deviation: DEFAULT_MAX_DEVIATION }
+    }
+}
+
+impl IndexConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            stale_ms: std::env::var("INDEX_STALE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(defaults.stale_ms),
+            max_deviation: std::env::var("INDEX_MAX_DEVIATION").ok().and_then(|v| v.parse().ok()).unwrap_or(defaults.max_deviation),
+        }
+    }
+}
+
+/// Index value with the venues behind it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexPrice {
+    pub price: f64,
+    pub venues: usize,   // Fresh venues used
+    pub rejected: usize, // Fresh venues rejected as outliers
+}
+
+#[derive(Debug, Default)]
+pub struct CompositeIndex {
+    config: IndexConfig,
+    latest: HashMap<(u64, SpotVenue), (f64, u64)>, // (asset, venue) -> (price, ts)
+}
+
+impl CompositeIndex {
+    pub fn new(config: IndexConfig) -> Self {
+        Self { config, latest: HashMap::new() }
+    }
+
+    pub fn update(&mut self, tick: &VenueTick) {
+        self.latest.insert((tick.symbol, tick.venue), (tick.price, tick.ts));
+    }
+
+    /// Index for an asset at `now_ms` (Binance clock); `None` without a
+    /// fresh quote
+    pub fn price(&self, symbol: u64, now_ms: u64) -> Option<IndexPrice> {
+        // (price, weight) of every fresh quote
+        let mut quotes: Vec<(f64, f64)> = self
+            .latest
+            .iter()
+            .filter(|((s, _), _)| *s == symbol)
+            .filter_map(|(_, &(price, ts))| {
+                let age = now_ms.saturating_sub(ts);
+                (age < self.config.stale_ms).then(|| (price, 1.0 - age as f64 / self.config.stale_ms as f64))
+            })
+            .collect();
+        if quotes.is_empty() {
+            return None;
+        }
+
+        quotes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let n = quotes.len();
+        let median = (quotes[(n - 1) / 2].0 + quotes[n / 2].0) / 2.0;
+
+        quotes.retain(|(price, _)| ((price - median) / median).abs() <= self.config.max_deviation);
+        let weight: f64 = quotes.iter().map(|(_, w)| w).sum();
+        if weight <= 0.0 {
+            return None;
+        }
+        Some(IndexPrice {
+            price: quotes.iter().map(|(p, w)| p * w).sum::<f64>() / weight,
+            venues: quotes.len(),
+            rejected: n - quotes.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(venue: SpotVenue, price: f64, ts: u64) -> VenueTick {
+        VenueTick { venue, symbol: 1, price, ts }
+    }
+
+    #[test]
+    fn test_index_rejects_outliers_and_stale_quotes() {
+        let mut index = CompositeIndex::new(IndexConfig::default());
+        assert_eq!(index.price(1, 10_000), None);
+
+        index.update(&tick(SpotVenue::Binance, 100_000.0, 10_000));
+        assert_eq!(index.price(1, 10_000), Some(IndexPrice { price: 100_000.0, venues: 1, rejected: 0 }));
+
+        index.update(&tick(SpotVenue::Coinbase, 100_020.0, 10_000));
+        index.update(&tick(SpotVenue::Kraken, 100_010.0, 10_000));
+        index.update(&tick(SpotVenue::Okx, 101_500.0, 10_000)); // 1.5% off: glitch
+        index.update(&tick(SpotVenue::Bybit, 99_000.0, 1_000)); // Stale
+        let p = index.price(1, 10_000).unwrap();
+        assert_eq!((p.venues, p.rejected), (3, 1));
+        assert!((p.price - 100_010.0).abs() < 1e-6);
+
+        // Other assets are separate
+        assert_eq!(index.price(2, 10_000), None);
+    }
+
+    #[test]
+    fn test_index_weights_by_freshness() {
+        let mut index = CompositeIndex::new(IndexConfig { stale_ms: 1_000, max_deviation: 0.01 });
+        index.update(&tick(SpotVenue::Binance, 100.0, 10_000)); // Weight 1
+        index.update(&tick(SpotVenue::Coinbase, 100.4, 9_250)); // Weight 0.25
+        let p = index.price(1, 10_000).unwrap();
+        assert!((p.price - (100.0 + 0.25 * 100.4) / 1.25).abs() < 1e-9);
+
+        // Everything expired
+        assert_eq!(index.price(1, 11_000), None);
+    }
+
+    #[test]
+    fn test_spot_source_from_name() {
+        assert_eq!(SpotSource::from_name("Composite"), Some(SpotSource::Composite));
+        assert_eq!(SpotSource::from_name("binance"), Some(SpotSource::Binance));
+        assert_eq!(SpotSource::from_name("chainlink"), None);
+    }
+}
@@ -1,14 +1,44 @@
+//! Strategy host
+//!
+//! The pinned strategy thread owns a set of registered strategies and
+//! dispatches every event to each of them: Binance trades
+//! (`on_market_update`), klines (`on_bar`) and aggregated trades
+//! (`on_agg_trade`), best bid/offer (`on_quote`) and local-book depth
+//! (`on_depth`) when subscribed, Polymarket top-of-book changes
+//! (`on_book`), fills and rejections reported back by execution
+//! (`on_fill`, `on_reject`) and a periodic timer (`on_timer`). Prices from
+//! other spot venues (`SPOT_FEEDS`) only update the composite index, read
+//! through `StrategyContext::spot`.
+//!
+//! Each strategy is identified by its tag (`SignalKind`), which is stamped
+//! on every instruction it sends so execution and fills can be attributed.
+//! Each gets its own capital allocation (`STRATEGY_CAPITAL_<NAME>`, $),
+//! reserved as entries are sent, and its own activity-log prefix. Which
+//! strategies run is set by `STRATEGIES`.
+
 use rtrb::{Consumer, Producer};
-use crate::ingestion::MarketUpdate;
-use crate::execution::TradeInstruction;
-use crate::types::PriceSnapshot;
+use crate::binance_book::DepthSummary;
+use crate::ingestion::{AggTrade, Bar, BarInterval, BookUpdate, MarketEvent, MarketUpdate, Quote};
+use crate::execution::{ExecutionReport, Fill, Rejection, SignalKind, TradeInstruction};
+use crate::latency::{LatencyArb, LatencyConfig};
+use crate::ledger::LedgerPosition;
+use crate::momentum::MomentumStrategy;
+use crate::polymarket::MarketSnapshot;
+use crate::pricing::RealizedVol;
+use crate::rolling::{RollingWindows, WindowStats};
+use crate::spot_feeds::{SpotVenue, VenueTick};
+use crate::spot_index::{CompositeIndex, IndexConfig, SpotSource};
+use crate::types::{MarketType, PriceSnapshot, Side};
+use crate::volatility::VolEstimator;
 use crate::database::{DbLogger, StrategyLogMsg};
+use crate::timesync::{SharedClock, Venue};
 use std::time::{Instant, Duration};
 use std::collections::{VecDeque, HashMap};
 use std::sync::Arc;
+use chrono::{DateTime, Utc};
 
 /// Asset names for logging
-fn get_asset_name(symbol: u64) -> &'static str {
+pub fn get_asset_name(symbol: u64) -> &'static str {
     match symbol {
         1 => "BTC",
         2 => "ETH",
@@ -18,65 +48,487 @@ fn get_asset_name(symbol: u64) -> &'static str {
     }
 }
 
-/// Configuration for the strategy
-const MOMENTUM_WINDOW_60MIN_SECS: u64 = 600;  // 10 minutes for 60-min markets
-const MOMENTUM_WINDOW_15MIN_SECS: u64 = 180;  // 3 minutes for 15-min markets
-// TESTING: Lowered thresholds to trigger trades in stable markets
-// PRODUCTION: 0.003 (0.3%) and 0.005 (0.5%)
-const MOMENTUM_THRESHOLD_60MIN: f64 = 0.0005;  // 0.05% move (TESTING)
-const MOMENTUM_THRESHOLD_15MIN: f64 = 0.001;   // 0.10% move (TESTING)
-const TRADE_SIZE_DOLLARS: u64 = 10;
-const MAX_POSITIONS: usize = 3;
-const COOLDOWN_SECS: u64 = 5;
-
-/// Stop-loss configuration
-/// Only check stop-loss in the LAST X minutes before market expiry
-const STOP_LOSS_THRESHOLD_15MIN: f64 = 0.002;   // 0.2% reversal for 15-min
-const STOP_LOSS_THRESHOLD_60MIN: f64 = 0.003;   // 0.3% reversal for 60-min
-const STOP_LOSS_ACTIVE_15MIN_SECS: u64 = 180;   // Last 3 min of 15-min market (12 min safe)
-const STOP_LOSS_ACTIVE_60MIN_SECS: u64 = 900;   // Last 15 min of 60-min market (45 min safe)
-const MARKET_DURATION_15MIN_SECS: u64 = 900;    // 15 min = 900 sec
-const MARKET_DURATION_60MIN_SECS: u64 = 3600;   // 60 min = 3600 sec
-
-/// Represents an open position
+/// Asset symbol IDs (inverse of `get_asset_name`)
+pub fn get_asset_symbol(name: &str) -> Option<u64> {
+    match name {
+        "BTC" => Some(1),
+        "ETH" => Some(2),
+        "SOL" => Some(3),
+        "XRP" => Some(4),
+        _ => None,
+    }
+}
+
+/// Price history kept per asset (shared by all strategies): long enough to
+/// still hold the open of the current hourly window
+const PRICE_HISTORY_MS: u64 = 3_600_000; // 60 minutes
+/// Interval between `on_timer` calls
+const TIMER_INTERVAL: Duration = Duration::from_secs(1);
+/// Capital per strategy when `STRATEGY_CAPITAL_<NAME>` is not set ($)
+const DEFAULT_STRATEGY_CAPITAL: f64 = 100.0;
+
+pub type PriceHistories = HashMap<u64, VecDeque<PriceSnapshot>>;
+
+/// Latest state of the optional Binance streams, the composite index, the
+/// volatility estimates and the rolling trade windows, per asset
+#[derive(Default)]
+pub struct SpotFeeds {
+    /// Latest bar per (asset, interval), including the one still forming
+    pub bars: HashMap<(u64, BarInterval), Bar>,
+    pub quotes: HashMap<u64, Quote>,
+    pub depth: HashMap<u64, DepthSummary>,
+    pub index: CompositeIndex,
+    pub vol: VolEstimator,
+    pub realized: HashMap<u64, RealizedVol>,
+    pub rolling: HashMap<u64, RollingWindows>,
+}
+
+/// A trading strategy run by the host. Every hook has a no-op default.
+pub trait Strategy: Send {
+    /// Tag stamped on instructions, fills and log lines
+    fn tag(&self) -> SignalKind;
+
+    /// A Binance trade (the asset's price history already includes it)
+    fn on_market_update(&mut self, _ctx: &mut StrategyContext, _update: &MarketUpdate) {}
+
+    /// A Binance kline update (the latest bar is already in `ctx.bar`)
+    fn on_bar(&mut self, _ctx: &mut StrategyContext, _bar: &Bar) {}
+
+    /// A Binance aggregated trade
+    fn on_agg_trade(&mut self, _ctx: &mut StrategyContext, _trade: &AggTrade) {}
+
+    /// A Binance best bid/offer change (already in `ctx.quote`)
+    fn on_quote(&mut self, _ctx: &mut StrategyContext, _quote: &Quote) {}
+
+    /// The local Binance L2 book changed (already in `ctx.depth`)
+    fn on_depth(&mut self, _ctx: &mut StrategyContext, _depth: &DepthSummary) {}
+
+    /// A Polymarket top-of-book change
+    fn on_book(&mut self, _ctx: &mut StrategyContext, _book: &BookUpdate) {}
+
+    /// Shares of one of this strategy's orders matched on the venue (an order
+    /// may fill over several reports)
+    fn on_fill(&mut self, _ctx: &mut StrategyContext, _fill: &Fill) {}
+
+    /// One of this strategy's instructions was refused, or its order left the
+    /// book short of filling (`filled` shares had matched)
+    fn on_reject(&mut self, _ctx: &mut StrategyContext, _rejection: &Rejection) {}
+
+    /// Called every `TIMER_INTERVAL`
+    fn on_timer(&mut self, _ctx: &mut StrategyContext) {}
+}
+
+/// Strategies to run, from `STRATEGIES` (comma-separated `momentum`,
+/// `latency`; default `momentum`)
+fn enabled_strategies(value: &str) -> Vec<SignalKind> {
+    let mut kinds = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        match SignalKind::from_name(name) {
+            Some(kind) if !kinds.contains(&kind) => kinds.push(kind),
+            Some(_) => {}
+            None => eprintln!("⚠️ Unknown strategy '{}' in STRATEGIES, ignored", name),
+        }
+    }
+    kinds
+}
+
+/// Capital a strategy may keep committed to open positions. Sending an
+/// entry reserves its budget; fills settle it at the cost traded (held
+/// until the window expires or the position is sold) and a rejection
+/// releases what is left of it.
 #[derive(Debug, Clone)]
-struct Position {
-    asset_symbol: u64,      // 1=BTC, 2=ETH, 3=SOL, 4=XRP
-    market_type: u64,       // 15 or 60
-    side: u8,               // 0 = YES (up), 1 = NO (down)
-    entry_momentum: f64,    // Momentum at entry
-    entry_time: Instant,
-    entry_price_cents: u64,
+pub struct CapitalBudget {
+    capital: f64,
+    open: Vec<Commitment>,
+}
+
+#[derive(Debug, Clone)]
+struct Commitment {
+    symbol: u64,
+    side: u8,
+    cost: f64,
+    shares: f64,
+    expires_at: DateTime<Utc>,
+    pending: bool, // Reserved for an entry sent but not yet (fully) filled
+}
+
+impl CapitalBudget {
+    pub fn new(capital: f64) -> Self {
+        Self { capital, open: Vec::new() }
+    }
+
+    /// `STRATEGY_CAPITAL_<NAME>`, e.g. `STRATEGY_CAPITAL_MOMENTUM=50`
+    fn from_env(kind: SignalKind) -> Self {
+        let var = format!("STRATEGY_CAPITAL_{}", kind.name().to_uppercase());
+        Self::new(std::env::var(var).ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_STRATEGY_CAPITAL))
+    }
+
+    /// Commit the cost of positions held from before a restart
+    pub fn with_positions(mut self, positions: &[LedgerPosition]) -> Self {
+        for pos in positions {
+            let (Some(asset_symbol), Some(minutes)) = (get_asset_symbol(&pos.asset), pos.market_type.minutes()) else {
+                continue;
+            };
+            self.open.push(Commitment {
+                symbol: asset_symbol * 100 + minutes,
+                side: if pos.side == Side::Yes { 0 } else { 1 },
+                cost: pos.shares * pos.avg_price,
+                shares: pos.shares,
+                expires_at: pos.expires_at,
+                pending: false,
+            });
+        }
+        self
+    }
+
+    pub fn capital(&self) -> f64 {
+        self.capital
+    }
+
+    /// Capital committed to positions still open at `now`
+    pub fn committed(&mut self, now: DateTime<Utc>) -> f64 {
+        self.open.retain(|c| c.expires_at > now);
+        self.open.iter().map(|c| c.cost).sum()
+    }
+
+    /// Whether a new entry of `size` dollars fits in the allocation
+    pub fn check(&mut self, size: f64, now: DateTime<Utc>) -> Result<(), String> {
+        let committed = self.committed(now);
+        if committed + size > self.capital + 1e-9 {
+            return Err(format!(
+                "capital allocation: ${:.2} committed + ${:.2} exceeds ${:.2}",
+                committed, size, self.capital
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reserve the budget of an entry just sent. Until execution reports
+    /// back it is held for the window's length (symbol's minutes).
+    pub fn reserve(&mut self, symbol: u64, side: u8, size: f64, now: DateTime<Utc>) {
+        let expires_at = now + chrono::Duration::minutes((symbol % 100) as i64);
+        self.open.push(Commitment { symbol, side, cost: size, shares: 0.0, expires_at, pending: true });
+    }
+
+    fn pending(&mut self, symbol: u64, side: u8) -> Option<usize> {
+        self.open.iter().position(|c| c.pending && c.symbol == symbol && c.side == side)
+    }
+
+    /// Entry fills move their cost from the reservation to the position,
+    /// and the last one releases what the order didn't spend. Sells release
+    /// the shares they close (SELL instructions carry the opposite side of
+    /// the entry).
+    pub fn record_fill(&mut self, fill: &Fill) {
+        if fill.symbol >= 1000 {
+            let (symbol, side) = (fill.symbol - 1000, 1 - fill.side.min(1));
+            let mut shares = fill.shares;
+            for c in self.open.iter_mut().filter(|c| !c.pending && c.symbol == symbol && c.side == side) {
+                let closed = shares.min(c.shares);
+                c.cost -= c.cost * closed / c.shares;
+                c.shares -= closed;
+                shares -= closed;
+                if shares <= 1e-9 {
+                    break;
+                }
+            }
+            self.open.retain(|c| c.pending || c.shares > 1e-9);
+        } else {
+            if let Some(idx) = self.pending(fill.symbol, fill.side) {
+                let reserved = &mut self.open[idx];
+                reserved.cost -= fill.cost;
+                if fill.remaining <= 0.0 || reserved.cost <= 0.0 {
+                    self.open.remove(idx);
+                }
+            }
+            self.open.push(Commitment {
+                symbol: fill.symbol,
+                side: fill.side,
+                cost: fill.cost,
+                shares: fill.shares,
+                expires_at: fill.expires_at,
+                pending: false,
+            });
+        }
+    }
+
+    /// A refused entry, or one that left the book, releases what is left
+    /// of its reservation
+    pub fn record_rejection(&mut self, rejection: &Rejection) {
+        if rejection.symbol < 1000 {
+            if let Some(idx) = self.pending(rejection.symbol, rejection.side) {
+                self.open.remove(idx);
+            }
+        }
+    }
+}
+
+/// Per-strategy logging: activity-log messages are prefixed with the tag
+pub struct StrategyLog {
+    tag: SignalKind,
+    db_logger: Option<Arc<DbLogger>>,
+}
+
+impl StrategyLog {
+    pub fn activity(&self, level: &str, category: &str, message: &str, details: Option<String>) {
+        if let Some(ref logger) = self.db_logger {
+            logger.log_activity(level, category, &format!("[{}] {}", self.tag.name(), message), details);
+        }
+    }
+
+    pub fn strategy(&self, msg: StrategyLogMsg) {
+        if let Some(ref logger) = self.db_logger {
+            logger.log_strategy(msg);
+        }
+    }
+}
+
+/// What a strategy sees while handling an event
+pub struct StrategyContext<'a> {
+    /// Current time on the CLOB's clock (expiries are judged against it)
+    pub now: DateTime<Utc>,
+    pub markets: &'a MarketSnapshot,
+    pub prices: &'a PriceHistories,
+    pub feeds: &'a SpotFeeds,
+    pub log: &'a StrategyLog,
+    budget: &'a mut CapitalBudget,
+    producer: &'a mut Producer<TradeInstruction>,
+}
+
+impl StrategyContext<'_> {
+    pub fn history(&self, asset_symbol: u64) -> Option<&VecDeque<PriceSnapshot>> {
+        self.prices.get(&asset_symbol)
+    }
+
+    /// Spot price of an asset from the chosen source: Binance's last trade,
+    /// or the composite index across venues as of `now_ms` (Binance clock)
+    pub fn spot(&self, asset_symbol: u64, source: SpotSource, now_ms: u64) -> Option<f64> {
+        match source {
+            SpotSource::Binance => self.history(asset_symbol)?.back().map(|p| p.price_cents as f64 / 100.0),
+            SpotSource::Composite => self.feeds.index.price(asset_symbol, now_ms).map(|p| p.price),
+        }
+    }
+
+    /// EWMA volatility per second for a horizon (`Fifteen` or `Hourly`);
+    /// `None` until enough trades have been seen
+    pub fn volatility(&self, asset_symbol: u64, horizon: MarketType) -> Option<f64> {
+        self.feeds.vol.sigma(asset_symbol, horizon)
+    }
+
+    /// Realized volatility per second over the last
+    /// `pricing::REALIZED_VOL_WINDOW_MS`; `None` until enough trades
+    pub fn realized_volatility(&self, asset_symbol: u64) -> Option<f64> {
+        self.feeds.realized.get(&asset_symbol)?.sigma()
+    }
+
+    /// Start price, VWAP and return of an asset's trades over one of
+    /// `rolling::WINDOWS_MS`
+    pub fn rolling(&self, asset_symbol: u64, window_ms: u64) -> Option<WindowStats> {
+        self.feeds.rolling.get(&asset_symbol)?.stats(window_ms)
+    }
+
+    /// Latest kline of an asset (`None` unless its stream is subscribed)
+    pub fn bar(&self, asset_symbol: u64, interval: BarInterval) -> Option<&Bar> {
+        self.feeds.bars.get(&(asset_symbol, interval))
+    }
+
+    /// Latest best bid/offer (`None` unless `@bookTicker` is subscribed)
+    pub fn quote(&self, asset_symbol: u64) -> Option<&Quote> {
+        self.feeds.quotes.get(&asset_symbol)
+    }
+
+    /// Microprice and imbalance of the local L2 book (`None` unless
+    /// `@depth` is subscribed and the book is synced)
+    pub fn depth(&self, asset_symbol: u64) -> Option<&DepthSummary> {
+        self.feeds.depth.get(&asset_symbol)
+    }
+
+    /// Send an instruction to execution, tagged with this strategy.
+    /// Entries must fit in the strategy's capital allocation, and reserve
+    /// their budget until execution reports back.
+    pub fn submit(&mut self, mut instruction: TradeInstruction) -> Result<(), String> {
+        instruction.kind = self.log.tag;
+        let (symbol, side, size) = (instruction.symbol, instruction.side, instruction.size as f64);
+        let is_entry = symbol < 1000;
+        if is_entry {
+            self.budget.check(size, self.now)?;
+        }
+        self.producer.push(instruction).map_err(|_| "execution queue full".to_string())?;
+        if is_entry {
+            self.budget.reserve(symbol, side, size, self.now);
+        }
+        Ok(())
+    }
+}
+
+/// A registered strategy with its allocation and logger
+struct Slot {
+    strategy: Box<dyn Strategy>,
+    budget: CapitalBudget,
+    log: StrategyLog,
 }
 
+fn build_strategies(kinds: &[SignalKind], recovered: &[LedgerPosition], db_logger: &Option<Arc<DbLogger>>) -> Vec<Slot> {
+    kinds
+        .iter()
+        .map(|&kind| {
+            // Momentum resumes (and exits) the positions held before a restart
+            let (strategy, resumed): (Box<dyn Strategy>, &[LedgerPosition]) = match kind {
+                SignalKind::Momentum => (Box::new(MomentumStrategy::new(recovered)), recovered),
+                SignalKind::LatencyArb => (Box::new(LatencyArb::new(LatencyConfig::from_env())), &[]),
+            };
+            let tag = strategy.tag();
+            Slot {
+                strategy,
+                budget: CapitalBudget::from_env(tag).with_positions(resumed),
+                log: StrategyLog { tag, db_logger: db_logger.clone() },
+            }
+        })
+        .collect()
+}
+
+/// Run `f` for every strategy with its own context
+fn dispatch(
+    slots: &mut [Slot],
+    producer: &mut Producer<TradeInstruction>,
+    markets: &MarketSnapshot,
+    prices: &PriceHistories,
+    feeds: &SpotFeeds,
+    now: DateTime<Utc>,
+    mut f: impl FnMut(&mut dyn Strategy, &mut StrategyContext),
+) {
+    for slot in slots.iter_mut() {
+        let mut ctx = StrategyContext {
+            now,
+            markets,
+            prices,
+            feeds,
+            log: &slot.log,
+            budget: &mut slot.budget,
+            producer: &mut *producer,
+        };
+        f(slot.strategy.as_mut(), &mut ctx);
+    }
+}
+
+/// Add a trade to its asset's history and drop prices older than the window
+fn record_price(prices: &mut PriceHistories, update: &MarketUpdate) -> bool {
+    let Some(history) = prices.get_mut(&update.symbol) else {
+        return false; // Unknown symbol
+    };
+    history.push_back(PriceSnapshot::new(update.price, update.ts));
+    let cutoff_ms = update.ts.saturating_sub(PRICE_HISTORY_MS);
+    while history.front().is_some_and(|p| p.timestamp_ms < cutoff_ms) {
+        history.pop_front();
+    }
+    true
+}
+
+/// Record a Binance trade in its asset's history and in the feeds
+/// strategies read (volatility, rolling windows, composite index)
+fn record_trade(prices: &mut PriceHistories, feeds: &mut SpotFeeds, update: &MarketUpdate) -> bool {
+    if !record_price(prices, update) {
+        return false;
+    }
+    feeds.vol.update(update.symbol, update.price, update.ts);
+    feeds.realized.entry(update.symbol).or_default().update(update.price, update.ts);
+    feeds.rolling.entry(update.symbol).or_default().push(update.price, update.quantity, update.ts);
+    feeds.index.update(&VenueTick {
+        venue: SpotVenue::Binance,
+        symbol: update.symbol,
+        price: update.price as f64 / 100.0,
+        ts: update.ts,
+    });
+    true
+}
+
+/// One strategy hosted as the strategy thread would, for its tests: trades
+/// go through the same feeds, instructions come out of `instructions`
+#[cfg(test)]
+pub(crate) struct TestHost {
+    pub instructions: Consumer<TradeInstruction>,
+    markets: MarketSnapshot,
+    slot: Slot,
+    producer: Producer<TradeInstruction>,
+    prices: PriceHistories,
+    feeds: SpotFeeds,
+}
+
+#[cfg(test)]
+impl TestHost {
+    pub(crate) fn new(strategy: Box<dyn Strategy>, markets: HashMap<String, Vec<crate::polymarket::CachedMarket>>) -> Self {
+        let tag = strategy.tag();
+        let (producer, instructions) = rtrb::RingBuffer::new(16);
+        Self {
+            markets: MarketSnapshot { version: 1, markets },
+            instructions,
+            slot: Slot {
+                strategy,
+                budget: CapitalBudget::new(DEFAULT_STRATEGY_CAPITAL),
+                log: StrategyLog { tag, db_logger: None },
+            },
+            producer,
+            prices: (1..=4).map(|symbol| (symbol, VecDeque::new())).collect(),
+            feeds: SpotFeeds::default(),
+        }
+    }
+
+    /// Record a trade and pass it to the strategy
+    pub(crate) fn trade(&mut self, update: &MarketUpdate, now: DateTime<Utc>) {
+        assert!(record_trade(&mut self.prices, &mut self.feeds, update));
+        self.run(now, |s, ctx| s.on_market_update(ctx, update));
+    }
+
+    /// Run `f` against the strategy with a context as of `now`
+    pub(crate) fn run(&mut self, now: DateTime<Utc>, f: impl FnMut(&mut dyn Strategy, &mut StrategyContext)) {
+        let slots = std::slice::from_mut(&mut self.slot);
+        dispatch(slots, &mut self.producer, &self.markets, &self.prices, &self.feeds, now, f);
+    }
+}
+
+/// Strategy thread: dispatch market events, execution reports and timer ticks to every
+/// enabled strategy. Without a database, activity logging is skipped.
 pub fn run_strategy(
-    mut consumer: Consumer<MarketUpdate>, 
-    mut producer: Producer<TradeInstruction>, 
-    db_logger: Arc<DbLogger>,
-    market_cache: crate::polymarket::MarketCache
+    mut consumer: Consumer<MarketEvent>,
+    mut producer: Producer<TradeInstruction>,
+    mut reports: Consumer<ExecutionReport>,
+    db_logger: Option<Arc<DbLogger>>,
+    market_cache: crate::polymarket::MarketCache,
+    recovered: Vec<LedgerPosition>,
+    clock: SharedClock,
 ) {
-    println!("Starting Strategy Engine (Multi-Asset Mode + Stop-Loss)...");
-    db_logger.log_activity("info", "system", "Strategy Engine started", Some(r#"{"mode": "multi-asset", "assets": ["BTC", "ETH", "SOL", "XRP"]}"#.to_string()));    
-    // Price history for momentum calculation (per-asset rolling windows)
-    let mut price_histories: HashMap<u64, VecDeque<PriceSnapshot>> = HashMap::new();
-    price_histories.insert(1, VecDeque::with_capacity(1000)); // BTC
-    price_histories.insert(2, VecDeque::with_capacity(1000)); // ETH
-    price_histories.insert(3, VecDeque::with_capacity(1000)); // SOL
-    price_histories.insert(4, VecDeque::with_capacity(1000)); // XRP
-    
-    // Position tracking (per-asset)
-    let mut open_positions: Vec<Position> = Vec::with_capacity(MAX_POSITIONS * 4);
-    
-    // Rate limiting (per-asset)
-    let cooldown = Duration::from_secs(COOLDOWN_SECS);
-    let mut last_trade_times: HashMap<u64, Instant> = HashMap::new();
-    let mut tick_count: u64 = 0;
-    
+    let kinds = enabled_strategies(std::env::var("STRATEGIES").as_deref().unwrap_or("momentum"));
+    let mut slots = build_strategies(&kinds, &recovered, &db_logger);
+
+    println!("Starting Strategy Engine (Multi-Asset Mode, {} strategies)...", slots.len());
+    for slot in &slots {
+        println!("  - {} (capital ${:.2})", slot.log.tag.name(), slot.budget.capital());
+    }
+    if let Some(ref logger) = db_logger {
+        let names: Vec<String> = kinds.iter().map(|k| format!(r#""{}""#, k.name())).collect();
+        logger.log_activity("info", "system", "Strategy Engine started", Some(format!(
+            r#"{{"mode": "multi-asset", "assets": ["BTC", "ETH", "SOL", "XRP"], "strategies": [{}]}}"#,
+            names.join(", "))));
+    }
+
+    // Price history (per-asset rolling windows)
+    let mut prices: PriceHistories = HashMap::new();
+    for symbol in 1..=4 {
+        prices.insert(symbol, VecDeque::with_capacity(1000)); // BTC, ETH, SOL, XRP
+    }
+
+    let mut feeds = SpotFeeds { index: CompositeIndex::new(IndexConfig::from_env()), ..Default::default() };
+
+    // Market cache version last seen (to detect market rollovers)
+    let mut last_cache_version: u64 = 0;
+    let mut last_timer = Instant::now();
+
     // Performance monitoring
     let mut perf_tick_count: u64 = 0;
+    let mut perf_agg_count: u64 = 0;
+    let mut perf_venue_count: u64 = 0;
     let mut perf_last_report = Instant::now();
     const PERF_REPORT_INTERVAL_SECS: u64 = 10;
-    
+
     // Core Affinity (Pin to Core 2)
     if let Some(core_ids) = core_affinity::get_core_ids() {
         if core_ids.len() > 2 {
@@ -86,361 +538,316 @@ pub fn run_strategy(
     }
 
     loop {
-        if let Ok(update) = consumer.pop() {
-            tick_count += 1;
-            perf_tick_count += 1;
-            
-            // Performance report every 10 seconds
-            if perf_last_report.elapsed() >= Duration::from_secs(PERF_REPORT_INTERVAL_SECS) {
-                let elapsed = perf_last_report.elapsed().as_secs_f64();
-                let tps = perf_tick_count as f64 / elapsed;
-                println!("[PERF] Last {:.0}s: {} ticks ({:.1}/sec), Positions: {}",
-                    elapsed, perf_tick_count, tps, open_positions.len());
-                perf_tick_count = 0;
-                perf_last_report = Instant::now();
-            }
-            
-            // Get asset name
-            let asset_symbol = update.symbol;
-            let asset_name = get_asset_name(asset_symbol);
-            
-            // CHECK MARKET CACHE: Only process if we have an active market for this asset
-            // This avoids wasting CPU on assets with no markets (e.g. XRP if no market exists)
-            let has_active_market = if let Ok(cache) = market_cache.read() {
-                cache.get(asset_name).map(|m| !m.is_empty()).unwrap_or(false)
-            } else {
-                false // Lock failed, safe default
-            };
-            
-            // If no active market and we don't have open positions for this asset, skip
-            let has_open_positions = open_positions.iter().any(|p| p.asset_symbol == asset_symbol);
-            
-            if !has_active_market && !has_open_positions {
-                // Skip processing for this asset to save resources
+        let event = consumer.pop().ok();
+        let timer_due = last_timer.elapsed() >= TIMER_INTERVAL;
+        if event.is_none() && !timer_due && reports.is_empty() {
+            continue;
+        }
+        
+        // Expiries are judged on the CLOB's clock, not the local one
+        // (lock-free snapshot load: the cache updater can never block this thread)
+        let now_utc = clock.now(Venue::Polymarket);
+        let snapshot = market_cache.load();
+        if snapshot.version != last_cache_version {
+            println!("[STRATEGY] Market cache changed: v{} -> v{}", last_cache_version, snapshot.version);
+            last_cache_version = snapshot.version;
+        }
+
+        // Placements, fills and rejections go back to the strategy that sent the instruction
+        while let Ok(report) = reports.pop() {
+            let Some(slot) = slots.iter_mut().find(|s| s.log.tag == report.kind()) else {
                 continue;
-            }
-            
-            // Get price history for this asset
-            let price_history = match price_histories.get_mut(&asset_symbol) {
-                Some(h) => h,
-                None => continue, // Unknown symbol, skip
             };
-            
-            // Add to price history
-            let snapshot = PriceSnapshot::new(update.price, update.ts);
-            price_history.push_back(snapshot);
-            
-            // Prune old prices (keep last 15 minutes)
-            let now_ms = update.ts;
-            let cutoff_ms = now_ms.saturating_sub(900_000); // 15 min in ms
-            while let Some(front) = price_history.front() {
-                if front.timestamp_ms < cutoff_ms {
-                    price_history.pop_front();
-                } else {
-                    break;
+            match &report {
+                ExecutionReport::Placed(placement) => {
+                    println!("[STRATEGY] {} order placed: #{} side {} {:.2} shares @ ${:.3} ({})",
+                        placement.kind.name(), placement.symbol, placement.side, placement.shares, placement.price,
+                        placement.order_id);
                 }
-            }
-            
-            // Calculate momentum for different windows
-            let momentum_60 = calculate_momentum(price_history, now_ms, MOMENTUM_WINDOW_60MIN_SECS * 1000);
-            let momentum_15 = calculate_momentum(price_history, now_ms, MOMENTUM_WINDOW_15MIN_SECS * 1000);
-            
-            let asset_name = get_asset_name(asset_symbol);
-            
-            // Debug: Log every 100th tick per asset
-            if tick_count % 100 == 0 {
-                let price_dollars = update.price as f64 / 100.0;
-                println!("[STRATEGY] {} Tick #{}: price=${:.2}, mom_60={:.4}%, mom_15={:.4}%, signals={}", 
-                    asset_name, tick_count, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0, open_positions.len());
-                
-                // Log to database (log all assets, not just BTC)
-                db_logger.log_strategy(StrategyLogMsg {
-                    tick_number: tick_count as i32,
-                    price: price_dollars,
-                    momentum_60: momentum_60 * 100.0,
-                    momentum_15: momentum_15 * 100.0,
-                    open_positions: open_positions.len() as i32,
-                });
-                
-                // Log price checkup to activity log
-                db_logger.log_activity("info", "strategy", 
-                    &format!("{} price: ${:.2} | mom_60: {:.2}% | mom_15: {:.2}%", asset_name, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0),
-                    Some(format!(r#"{{"asset": "{}", "price": {:.2}, "momentum_60": {:.4}, "momentum_15": {:.4}, "positions": {}, "tick": {}}}"#,
-                        asset_name, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0, open_positions.len(), tick_count)));
-            }
-            
-            // ============================================
-            // STOP-LOSS CHECK: Exit positions if momentum reverses (only for current asset)
-            // ============================================
-            let mut positions_to_close: Vec<usize> = Vec::new();
-            
-            for (idx, pos) in open_positions.iter().enumerate() {
-                // Only check positions for the current asset
-                if pos.asset_symbol != asset_symbol {
-                    continue;
+                ExecutionReport::Filled(fill) => {
+                    slot.budget.record_fill(fill);
+                    println!("[STRATEGY] {} fill: #{} side {} {:.2} shares @ ${:.3} (fee ${:.4}, committed ${:.2}/${:.2})",
+                        fill.kind.name(), fill.symbol, fill.side, fill.shares, fill.price, fill.fee,
+                        slot.budget.committed(now_utc), slot.budget.capital());
+                    let slot = std::slice::from_mut(slot);
+                    dispatch(slot, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_fill(ctx, fill));
                 }
-                
-                let current_momentum = if pos.market_type == 60 { momentum_60 } else { momentum_15 };
-                
-                // Get market duration and danger zone based on market type
-                let (market_duration, danger_zone, threshold) = if pos.market_type == 60 {
-                    (Duration::from_secs(MARKET_DURATION_60MIN_SECS), 
-                     Duration::from_secs(STOP_LOSS_ACTIVE_60MIN_SECS), 
-                     STOP_LOSS_THRESHOLD_60MIN)
-                } else {
-                    (Duration::from_secs(MARKET_DURATION_15MIN_SECS), 
-                     Duration::from_secs(STOP_LOSS_ACTIVE_15MIN_SECS), 
-                     STOP_LOSS_THRESHOLD_15MIN)
-                };
-                
-                // Only check stop-loss in the LAST X minutes (danger zone)
-                let time_until_expiry = market_duration.saturating_sub(pos.entry_time.elapsed());
-                if time_until_expiry > danger_zone {
-                    continue; // Still in safe zone, no stop-loss check
-                }
-                
-                // Check if momentum has reversed significantly
-                let reversal = if pos.side == 0 {
-                    // We bought YES (bet on UP) - check if momentum went negative
-                    pos.entry_momentum - current_momentum
-                } else {
-                    // We bought NO (bet on DOWN) - check if momentum went positive
-                    current_momentum - pos.entry_momentum
-                };
-                
-                if reversal >= threshold {
-                    let pos_asset_name = get_asset_name(pos.asset_symbol);
-                    println!("[STOP-LOSS] {} Position {} triggered! Reversal: {:.4}% ({}s until expiry)",
-                        pos_asset_name, idx, reversal * 100.0, time_until_expiry.as_secs());
-                    positions_to_close.push(idx);
-                    
-                    // Send SELL instruction (encode asset + market type)
-                    let sell_instruction = TradeInstruction {
-                        symbol: pos.asset_symbol * 100 + pos.market_type + 1000, // e.g., 1160 = BTC 60-min SELL
-                        side: if pos.side == 0 { 1 } else { 0 }, // Opposite of entry
-                        price_cents: 40, // Sell at whatever price (stop-loss)
-                        size: TRADE_SIZE_DOLLARS,
-                    };
-                    
-                    if producer.push(sell_instruction).is_ok() {
-                        println!("[STRATEGY] STOP-LOSS EXIT: {} {}-MIN {} (reversal={:.4}%)",
-                            pos_asset_name,
-                            pos.market_type,
-                            if pos.side == 0 { "SELL YES" } else { "SELL NO" },
-                            reversal * 100.0);
-                        
-                        // Log stop-loss to activity log
-                        db_logger.log_activity("warning", "position", 
-                            &format!("Stop-loss triggered: {} {}-MIN", pos_asset_name, pos.market_type),
-                            Some(format!(r#"{{"asset": "{}", "market": {}, "reversal": {:.4}, "side": "{}"}}"#,
-                                pos_asset_name, pos.market_type, reversal * 100.0,
-                                if pos.side == 0 { "YES" } else { "NO" })));
-                    }
+                ExecutionReport::Rejected(rejection) => {
+                    slot.budget.record_rejection(rejection);
+                    println!("[STRATEGY] {} rejected: #{} side {}: {} (committed ${:.2}/${:.2})",
+                        rejection.kind.name(), rejection.symbol, rejection.side, rejection.reason,
+                        slot.budget.committed(now_utc), slot.budget.capital());
+                    let slot = std::slice::from_mut(slot);
+                    dispatch(slot, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_reject(ctx, rejection));
                 }
             }
-            
-            // Remove closed positions (reverse order to preserve indices)
-            for idx in positions_to_close.into_iter().rev() {
-                open_positions.remove(idx);
+        }
+
+        if timer_due {
+            last_timer = Instant::now();
+            dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_timer(ctx));
+        }
+
+        let Some(event) = event else {
+            continue;
+        };
+        match event {
+            MarketEvent::Book(book) => {
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_book(ctx, &book));
             }
-            
-            // ============================================
-            // ENTRY LOGIC (per-asset rate limiting)
-            // ============================================
-            
-            // Check rate limiting for this asset
-            if let Some(&last_time) = last_trade_times.get(&asset_symbol) {
-                if last_time.elapsed() < cooldown {
-                    continue;
+            MarketEvent::Bar(bar) => {
+                if bar.closed {
+                    println!("[BAR] {} {} {}: O ${:.2} H ${:.2} L ${:.2} C ${:.2}, vol {:.4}, vwap {}",
+                        get_asset_name(bar.symbol), bar.interval.code(),
+                        DateTime::from_timestamp_millis(bar.open_time as i64).map_or("-".to_string(), |t| t.format("%H:%M").to_string()),
+                        bar.open, bar.high, bar.low, bar.close, bar.volume,
+                        bar.vwap().map_or("-".to_string(), |v| format!("${:.2}", v)));
                 }
+                feeds.bars.insert((bar.symbol, bar.interval), bar.clone());
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_bar(ctx, &bar));
             }
-            
-            // Count positions for this specific asset
-            let asset_positions = open_positions.iter().filter(|p| p.asset_symbol == asset_symbol).count();
-            
-            // Check position limit per asset (MAX_POSITIONS per asset)
-            if asset_positions >= MAX_POSITIONS {
-                continue;
+            MarketEvent::Quote(quote) => {
+                feeds.quotes.insert(quote.symbol, quote);
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_quote(ctx, &quote));
             }
-            
-            // Entry Logic: 60-minute markets
-            if momentum_60.abs() >= MOMENTUM_THRESHOLD_60MIN {
-                let side = if momentum_60 > 0.0 { 0 } else { 1 };
-                let instruction = TradeInstruction {
-                    symbol: asset_symbol * 100 + 60, // e.g., 160 = BTC 60-min, 260 = ETH 60-min
-                    side,
-                    price_cents: 50,
-                    size: TRADE_SIZE_DOLLARS,
-                };
-                
-                if let Ok(()) = producer.push(instruction) {
-                    println!("[STRATEGY] {} 60-MIN ENTRY: {} (momentum={:.4}%)", 
-                        asset_name,
-                        if side == 0 { "BUY YES (UP)" } else { "BUY NO (DOWN)" },
-                        momentum_60 * 100.0);
-                    
-                    // Log trade entry to activity log
-                    db_logger.log_activity("success", "trade", 
-                        &format!("{} 60-MIN: {} @ momentum {:.2}%", asset_name, if side == 0 { "BUY YES" } else { "BUY NO" }, momentum_60 * 100.0),
-                        Some(format!(r#"{{"asset": "{}", "market": 60, "momentum": {:.4}, "side": "{}", "size": {}}}"#,
-                            asset_name, momentum_60 * 100.0, if side == 0 { "YES" } else { "NO" }, TRADE_SIZE_DOLLARS)));
-                    
-                    // Track position
-                    open_positions.push(Position {
-                        asset_symbol,
-                        market_type: 60,
-                        side,
-                        entry_momentum: momentum_60,
-                        entry_time: Instant::now(),
-                        entry_price_cents: 50,
-                    });
-                    
-                    last_trade_times.insert(asset_symbol, Instant::now());
-                }
+            MarketEvent::Depth(depth) => {
+                feeds.depth.insert(depth.symbol, depth);
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_depth(ctx, &depth));
             }
-            
-            // Entry Logic: 15-minute markets
-            let asset_positions = open_positions.iter().filter(|p| p.asset_symbol == asset_symbol).count();
-            if momentum_15.abs() >= MOMENTUM_THRESHOLD_15MIN && asset_positions < MAX_POSITIONS {
-                let side = if momentum_15 > 0.0 { 0 } else { 1 };
-                let instruction = TradeInstruction {
-                    symbol: asset_symbol * 100 + 15, // e.g., 115 = BTC 15-min, 215 = ETH 15-min
-                    side,
-                    price_cents: 50,
-                    size: TRADE_SIZE_DOLLARS,
-                };
-                
-                if let Ok(()) = producer.push(instruction) {
-                    println!("[STRATEGY] {} 15-MIN ENTRY: {} (momentum={:.4}%)", 
-                        asset_name,
-                        if side == 0 { "BUY YES (UP)" } else { "BUY NO (DOWN)" },
-                        momentum_15 * 100.0);
-                    
-                    // Log trade entry to activity log
-                    db_logger.log_activity("success", "trade", 
-                        &format!("{} 15-MIN: {} @ momentum {:.2}%", asset_name, if side == 0 { "BUY YES" } else { "BUY NO" }, momentum_15 * 100.0),
-                        Some(format!(r#"{{"asset": "{}", "market": 15, "momentum": {:.4}, "side": "{}", "size": {}}}"#,
-                            asset_name, momentum_15 * 100.0, if side == 0 { "YES" } else { "NO" }, TRADE_SIZE_DOLLARS)));
-                    
-                    // Track position
-                    open_positions.push(Position {
-                        asset_symbol,
-                        market_type: 15,
-                        side,
-                        entry_momentum: momentum_15,
-                        entry_time: Instant::now(),
-                        entry_price_cents: 50,
-                    });
-                    
-                    last_trade_times.insert(asset_symbol, Instant::now());
+            MarketEvent::VenuePrice(tick) => {
+                perf_venue_count += 1;
+                feeds.index.update(&tick);
+            }
+            MarketEvent::AggTrade(trade) => {
+                perf_agg_count += 1;
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_agg_trade(ctx, &trade));
+            }
+            MarketEvent::Spot(update) => {
+                perf_tick_count += 1;
+
+                // Performance report every 10 seconds
+                if perf_last_report.elapsed() >= Duration::from_secs(PERF_REPORT_INTERVAL_SECS) {
+                    let elapsed = perf_last_report.elapsed().as_secs_f64();
+                    let tps = perf_tick_count as f64 / elapsed;
+                    println!("[PERF] Last {:.0}s: {} ticks ({:.1}/sec), {} aggTrades, {} other-venue prices",
+                        elapsed, perf_tick_count, tps, perf_agg_count, perf_venue_count);
+                    if perf_venue_count > 0 {
+                        for symbol in 1..=4 {
+                            if let Some(index) = feeds.index.price(symbol, update.ts) {
+                                println!("[INDEX] {} ${:.2} ({} venues, {} rejected)",
+                                    get_asset_name(symbol), index.price, index.venues, index.rejected);
+                            }
+                        }
+                    }
+                    perf_tick_count = 0;
+                    perf_agg_count = 0;
+                    perf_venue_count = 0;
+                    perf_last_report = Instant::now();
+                }
+
+                if !record_trade(&mut prices, &mut feeds, &update) {
+                    continue;
                 }
+                dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now_utc, |s, ctx| s.on_market_update(ctx, &update));
             }
         }
     }
 }
 
-/// Calculate price momentum over a given window
-fn calculate_momentum(history: &VecDeque<PriceSnapshot>, now_ms: u64, window_ms: u64) -> f64 {
-    if history.is_empty() {
-        return 0.0;
-    }
-    
-    let cutoff = now_ms.saturating_sub(window_ms);
-    
-    let oldest = history.iter()
-        .find(|p| p.timestamp_ms >= cutoff)
-        .map(|p| p.price_cents);
-    
-    let current = history.back().map(|p| p.price_cents);
-    
-    match (oldest, current) {
-        (Some(old), Some(cur)) if old > 0 => {
-            (cur as f64 - old as f64) / old as f64
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(symbol: u64, side: u8, cost: f64, expires_at: DateTime<Utc>) -> Fill {
+        Fill {
+            kind: SignalKind::Momentum, symbol, side, price: 0.5, shares: cost / 0.5, cost, fee: 0.0,
+            pnl: 0.0, remaining: 0.0, expires_at,
         }
-        _ => 0.0,
     }
-}
 
-/// Fallback: Run strategy without database logging (multi-asset)
-pub fn run_strategy_no_db(
-    mut consumer: Consumer<MarketUpdate>, 
-    mut producer: Producer<TradeInstruction>,
-    _market_cache: crate::polymarket::MarketCache
-) {
-    println!("Starting Strategy Engine (NO DB MODE - Multi-Asset)...");
-    
-    // Price history for momentum calculation (per-asset rolling windows)
-    let mut price_histories: HashMap<u64, VecDeque<PriceSnapshot>> = HashMap::new();
-    price_histories.insert(1, VecDeque::with_capacity(1000)); // BTC
-    price_histories.insert(2, VecDeque::with_capacity(1000)); // ETH
-    price_histories.insert(3, VecDeque::with_capacity(1000)); // SOL
-    price_histories.insert(4, VecDeque::with_capacity(1000)); // XRP
-    
-    let mut open_positions: Vec<Position> = Vec::with_capacity(MAX_POSITIONS * 4);
-    let cooldown = Duration::from_secs(COOLDOWN_SECS);
-    let mut last_trade_times: HashMap<u64, Instant> = HashMap::new();
-    let mut tick_count: u64 = 0;
+    #[test]
+    fn test_enabled_strategies() {
+        assert_eq!(enabled_strategies("momentum"), vec![SignalKind::Momentum]);
+        assert_eq!(enabled_strategies("Momentum, latency"), vec![SignalKind::Momentum, SignalKind::LatencyArb]);
+        assert_eq!(enabled_strategies("latency,latency,bogus"), vec![SignalKind::LatencyArb]);
+    }
 
-    loop {
-        if let Ok(update) = consumer.pop() {
-            tick_count += 1;
-            
-            let asset_symbol = update.symbol;
-            let price_history = match price_histories.get_mut(&asset_symbol) {
-                Some(h) => h,
-                None => continue,
-            };
-            
-            let snapshot = PriceSnapshot::new(update.price, update.ts);
-            price_history.push_back(snapshot);
-            
-            let now_ms = update.ts;
-            let cutoff_ms = now_ms.saturating_sub(900_000);
-            while let Some(front) = price_history.front() {
-                if front.timestamp_ms < cutoff_ms {
-                    price_history.pop_front();
-                } else { break; }
-            }
-            
-            let momentum_60 = calculate_momentum(price_history, now_ms, MOMENTUM_WINDOW_60MIN_SECS * 1000);
-            let momentum_15 = calculate_momentum(price_history, now_ms, MOMENTUM_WINDOW_15MIN_SECS * 1000);
-            
-            let asset_name = get_asset_name(asset_symbol);
-            
-            if tick_count % 100 == 0 {
-                let price_dollars = update.price as f64 / 100.0;
-                println!("[STRATEGY] {} Tick #{}: price=${:.2}, mom_60={:.4}%, mom_15={:.4}%, positions={}", 
-                    asset_name, tick_count, price_dollars, momentum_60 * 100.0, momentum_15 * 100.0, open_positions.len());
-            }
-            
-            // Check rate limiting for this asset
-            if let Some(&last_time) = last_trade_times.get(&asset_symbol) {
-                if last_time.elapsed() < cooldown { continue; }
-            }
-            
-            let asset_positions = open_positions.iter().filter(|p| p.asset_symbol == asset_symbol).count();
-            if asset_positions >= MAX_POSITIONS { continue; }
-            
-            // Entry Logic: 60-minute markets
-            if momentum_60.abs() >= MOMENTUM_THRESHOLD_60MIN {
-                let side = if momentum_60 > 0.0 { 0 } else { 1 };
-                let instruction = TradeInstruction { symbol: asset_symbol * 100 + 60, side, price_cents: 50, size: TRADE_SIZE_DOLLARS };
-                if producer.push(instruction).is_ok() {
-                    open_positions.push(Position { asset_symbol, market_type: 60, side, entry_momentum: momentum_60, entry_time: Instant::now(), entry_price_cents: 50 });
-                    last_trade_times.insert(asset_symbol, Instant::now());
-                }
-            }
-            
-            // Entry Logic: 15-minute markets
-            let asset_positions = open_positions.iter().filter(|p| p.asset_symbol == asset_symbol).count();
-            if momentum_15.abs() >= MOMENTUM_THRESHOLD_15MIN && asset_positions < MAX_POSITIONS {
-                let side = if momentum_15 > 0.0 { 0 } else { 1 };
-                let instruction = TradeInstruction { symbol: asset_symbol * 100 + 15, side, price_cents: 50, size: TRADE_SIZE_DOLLARS };
-                if producer.push(instruction).is_ok() {
-                    open_positions.push(Position { asset_symbol, market_type: 15, side, entry_momentum: momentum_15, entry_time: Instant::now(), entry_price_cents: 50 });
-                    last_trade_times.insert(asset_symbol, Instant::now());
+    #[test]
+    fn test_capital_budget() {
+        let now = Utc::now();
+        let later = now + chrono::Duration::minutes(15);
+        let mut budget = CapitalBudget::new(20.0);
+
+        budget.record_fill(&fill(115, 0, 10.0, later));
+        budget.record_fill(&fill(260, 1, 10.0, later));
+        assert!(budget.check(10.0, now).is_err());
+
+        // Selling the BTC 15-min YES entry (SELL carries the opposite side) frees its capital
+        budget.record_fill(&fill(1115, 1, 10.0, later));
+        assert!((budget.committed(now) - 10.0).abs() < 1e-9);
+        assert!(budget.check(10.0, now).is_ok());
+
+        // Expired windows release their capital
+        assert_eq!(budget.committed(later), 0.0);
+    }
+
+    #[test]
+    fn test_entries_reserve_capital_until_reported() {
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<TradeInstruction>::new(8);
+        let snapshot = MarketSnapshot::default();
+        let prices = PriceHistories::new();
+        let feeds = SpotFeeds::default();
+        let log = StrategyLog { tag: SignalKind::Momentum, db_logger: None };
+        let now = Utc::now();
+        // A BTC 15-min Up position held from before a restart: 10 shares at 40¢
+        let held = LedgerPosition {
+            asset: "BTC".to_string(),
+            market_type: MarketType::Fifteen,
+            token_id: "111".to_string(),
+            condition_id: "0xabc".to_string(),
+            side: Side::Yes,
+            shares: 10.0,
+            avg_price: 0.40,
+            expires_at: now + chrono::Duration::minutes(10),
+            updated_at: now,
+        };
+        let mut budget = CapitalBudget::new(24.0).with_positions(&[held]);
+        assert!((budget.committed(now) - 4.0).abs() < 1e-9);
+        let mut ctx = StrategyContext {
+            now,
+            markets: &snapshot,
+            prices: &prices,
+            feeds: &feeds,
+            log: &log,
+            budget: &mut budget,
+            producer: &mut producer,
+        };
+
+        // Entries sent before any fill comes back can't overrun the allocation
+        let instruction = |symbol, side| TradeInstruction {
+            symbol, side, price_cents: 50, size: 10, fair_prob: None, kind: SignalKind::Momentum,
+        };
+        assert!(ctx.submit(instruction(115, 1)).is_ok());
+        assert!(ctx.submit(instruction(260, 0)).is_ok());
+        assert!(ctx.submit(instruction(215, 0)).unwrap_err().contains("capital allocation"));
+        assert_eq!((consumer.pop().unwrap().symbol, consumer.pop().unwrap().symbol), (115, 260));
+        assert!(consumer.pop().is_err());
+
+        // A rejection releases its reservation; fills settle it at the cost
+        // traded, and the last one releases what the order didn't spend
+        let rejection = Rejection { kind: SignalKind::Momentum, symbol: 260, side: 0, filled: 0.0, reason: "spread too wide".into() };
+        budget.record_rejection(&rejection);
+        assert!((budget.committed(now) - 14.0).abs() < 1e-9);
+        let expires_at = now + chrono::Duration::minutes(15);
+        budget.record_fill(&Fill { remaining: 4.0, ..fill(115, 1, 6.0, expires_at) });
+        assert!((budget.committed(now) - 14.0).abs() < 1e-9);
+        budget.record_fill(&fill(115, 1, 2.0, expires_at));
+        assert!((budget.committed(now) - 12.0).abs() < 1e-9);
+
+        // Selling half the recovered position (5 of its 10 shares) frees half its capital
+        budget.record_fill(&fill(1115, 1, 2.5, now));
+        assert!((budget.committed(now) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_submit_tags_and_enforces_allocation() {
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<TradeInstruction>::new(4);
+        let snapshot = MarketSnapshot::default();
+        let prices = PriceHistories::new();
+        let feeds = SpotFeeds::default();
+        let log = StrategyLog { tag: SignalKind::LatencyArb, db_logger: None };
+        let mut budget = CapitalBudget::new(5.0);
+        let mut ctx = StrategyContext {
+            now: Utc::now(),
+            markets: &snapshot,
+            prices: &prices,
+            feeds: &feeds,
+            log: &log,
+            budget: &mut budget,
+            producer: &mut producer,
+        };
+
+        let instruction = |symbol, size| TradeInstruction {
+            symbol, side: 0, price_cents: 50, size, fair_prob: None, kind: SignalKind::Momentum,
+        };
+        assert!(ctx.submit(instruction(115, 10)).unwrap_err().contains("capital allocation"));
+        assert!(ctx.submit(instruction(115, 5)).is_ok());
+        // Exits are never blocked by the allocation
+        assert!(ctx.submit(instruction(1115, 10)).is_ok());
+
+        let sent = consumer.pop().unwrap();
+        assert_eq!((sent.symbol, sent.kind), (115, SignalKind::LatencyArb));
+    }
+
+    #[tokio::test]
+    async fn test_latency_entries_near_expiry_reach_the_venue() {
+        use crate::exchange::SimulatedExchange;
+        use crate::polymarket::{test_market, MarketStore};
+
+        // BTC 15-min window with 45s left: inside the strategy's limits, but
+        // past the minimum time to expiry other entries are held to
+        let now = Utc::now();
+        let now_ms = now.timestamp_millis() as u64;
+        let mut market = test_market("BTC", now + chrono::Duration::seconds(45));
+        market.open_price = Some(100_000.0);
+        let cache = Arc::new(MarketStore::new());
+        cache.publish(HashMap::from([("BTC".to_string(), vec![market])]));
+        let sim = SimulatedExchange::new(100.0);
+        sim.set_book("111", 0.79, 0.80);
+        let ledger = crate::ledger::SharedLedger::default();
+        let risk_manager = crate::risk::RiskManager::new(100.0);
+
+        let mut slots = build_strategies(&[SignalKind::LatencyArb], &[], &None);
+        let (mut producer, mut consumer) = rtrb::RingBuffer::<TradeInstruction>::new(4);
+        let (prices, feeds) = (PriceHistories::new(), SpotFeeds::default());
+        let snapshot = cache.load();
+        // Spot 0.5% above the open while the Up book still asks 80¢
+        let mut tick = |ts: u64, fill: Option<&Fill>| {
+            let books = [("111", 0.80), ("222", 0.22)].map(|(token, ask)| BookUpdate {
+                token_id: token.to_string(), best_bid: ask - 0.01, best_ask: ask, ts,
+            });
+            let update = MarketUpdate { symbol: 1, price: 10_050_000, quantity: 1.0, ts };
+            dispatch(&mut slots, &mut producer, &snapshot, &prices, &feeds, now, |s, ctx| {
+                if let Some(fill) = fill {
+                    s.on_fill(ctx, fill);
                 }
-            }
+                books.iter().for_each(|book| s.on_book(ctx, book));
+                s.on_market_update(ctx, &update);
+            });
+        };
+
+        tick(now_ms, None);
+        let entry = consumer.pop().unwrap();
+        assert_eq!((entry.symbol, entry.side, entry.kind), (115, 0, SignalKind::LatencyArb));
+        let placement = crate::execution::execute_instruction(&sim, &entry, &cache, &ledger, &risk_manager, now).await.unwrap();
+        assert!((placement.price - 0.80).abs() < 1e-9);
+
+        // Held while the order is out, however long it takes to fill
+        tick(now_ms + 1_000, None);
+        tick(now_ms + 11_000, None);
+        assert!(consumer.pop().is_err());
+
+        // The reconciler sees it matched; once the fill comes back the
+        // window is done for good (with 34s still left)
+        let venue = crate::ledger::fetch_venue_ledger(&sim, &cache, None).await.unwrap();
+        let reports = ledger.lock().unwrap().apply_fills(&venue, &cache.load().markets, now);
+        let [ExecutionReport::Filled(fill)] = reports.as_slice() else { panic!("expected one fill: {:?}", reports) };
+        assert!((fill.price - 0.80).abs() < 1e-9);
+        tick(now_ms + 11_000, Some(fill));
+        assert!(consumer.pop().is_err());
+    }
+
+    #[test]
+    fn test_history_keeps_the_hourly_open() {
+        let mut prices = PriceHistories::from([(1, VecDeque::new())]);
+        let hour_start = 3_600_000 * 10;
+        // A trade every second from just before the hour to 55 minutes in
+        for i in 0..3_400u64 {
+            let update = MarketUpdate { symbol: 1, price: 10_000_000 + i, quantity: 1.0, ts: hour_start - 5_000 + i * 1_000 };
+            assert!(record_price(&mut prices, &update));
         }
+        let open = crate::pricing::window_open_price(&prices[&1], hour_start).unwrap();
+        assert!((open - 100_000.05).abs() < 1e-9);
+        assert!(!record_price(&mut prices, &MarketUpdate { symbol: 9, price: 1, quantity: 1.0, ts: 0 }));
     }
 }
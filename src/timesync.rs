@@ -0,0 +1,295 @@
+//! Exchange clock synchronisation
+//!
+//! Binance trades carry the exchange's own timestamps while expiries and
+//! Polymarket events are judged against the local clock, so local drift
+//! shifts every time-to-expiry decision. This service periodically samples
+//! Binance `/api/v3/time` and CLOB `/time`, estimates each venue's offset
+//! from the local clock (NTP-style: server time vs. the midpoint of the
+//! request), and exposes corrected exchange time.
+//!
+//! Each round takes a burst of samples and keeps the one with the lowest
+//! round trip, whose midpoint assumption is the most accurate.
+
+use crate::database::DbLogger;
+use crate::exchange::ExchangeResult;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Default interval between sync rounds
+const DEFAULT_SYNC_INTERVAL_SECS: u64 = 30;
+/// Default skew that raises an alert (ms)
+const DEFAULT_MAX_SKEW_MS: i64 = 500;
+/// Samples per venue per round
+const SAMPLES_PER_ROUND: usize = 5;
+
+/// Venue whose clock we track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Venue {
+    Binance,
+    Polymarket,
+}
+
+impl Venue {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Venue::Binance => "Binance",
+            Venue::Polymarket => "Polymarket CLOB",
+        }
+    }
+
+    /// Resolution of the venue's time endpoint (ms). CLOB `/time` is whole
+    /// seconds, so its offset is only known to about ±500ms.
+    fn resolution_ms(&self) -> i64 {
+        match self {
+            Venue::Binance => 1,
+            Venue::Polymarket => 1000,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Venue::Binance => 0,
+            Venue::Polymarket => 1,
+        }
+    }
+}
+
+/// One round trip to a venue's time endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSample {
+    /// Venue time minus local time (ms)
+    pub offset_ms: i64,
+    /// Round trip time (ms)
+    pub rtt_ms: u64,
+}
+
+impl ClockSample {
+    /// `sent_ms`/`received_ms`: local time around the request;
+    /// `server_ms`: the venue's reported time
+    pub fn new(sent_ms: i64, server_ms: i64, received_ms: i64) -> Self {
+        let midpoint = sent_ms + (received_ms - sent_ms) / 2;
+        Self {
+            offset_ms: server_ms - midpoint,
+            rtt_ms: (received_ms - sent_ms).max(0) as u64,
+        }
+    }
+}
+
+/// Sample with the lowest round trip
+fn best_sample(samples: &[ClockSample]) -> Option<ClockSample> {
+    samples.iter().copied().min_by_key(|s| s.rtt_ms)
+}
+
+/// Per-venue clock state
+#[derive(Default)]
+struct VenueClock {
+    offset_ms: AtomicI64,
+    rtt_ms: AtomicU64,
+    synced: AtomicBool,
+}
+
+/// Shared, lock-free view of the venues' clocks
+#[derive(Default)]
+pub struct TimeSync {
+    venues: [VenueClock; 2],
+}
+
+pub type SharedClock = Arc<TimeSync>;
+
+impl TimeSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn venue(&self, venue: Venue) -> &VenueClock {
+        &self.venues[venue.index()]
+    }
+
+    /// Record a measurement for `venue`
+    pub fn update(&self, venue: Venue, sample: ClockSample) {
+        let clock = self.venue(venue);
+        clock.offset_ms.store(sample.offset_ms, Ordering::Relaxed);
+        clock.rtt_ms.store(sample.rtt_ms, Ordering::Relaxed);
+        clock.synced.store(true, Ordering::Relaxed);
+    }
+
+    /// Venue time minus local time (ms); 0 until the first sync
+    pub fn offset_ms(&self, venue: Venue) -> i64 {
+        self.venue(venue).offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Round trip of the last accepted sample (ms)
+    pub fn rtt_ms(&self, venue: Venue) -> u64 {
+        self.venue(venue).rtt_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn is_synced(&self, venue: Venue) -> bool {
+        self.venue(venue).synced.load(Ordering::Relaxed)
+    }
+
+    /// Current time on the venue's clock
+    pub fn now(&self, venue: Venue) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::milliseconds(self.offset_ms(venue))
+    }
+
+    /// Current time on the venue's clock, in Unix milliseconds
+    pub fn now_ms(&self, venue: Venue) -> u64 {
+        self.now(venue).timestamp_millis().max(0) as u64
+    }
+}
+
+/// Sampling policy (`TIME_SYNC_INTERVAL_SECS`, `MAX_CLOCK_SKEW_MS`)
+#[derive(Debug, Clone, Copy)]
+pub struct SyncPolicy {
+    pub interval_secs: u64,
+    pub max_skew_ms: i64,
+}
+
+impl SyncPolicy {
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok());
+        Self {
+            interval_secs: var("TIME_SYNC_INTERVAL_SECS").unwrap_or(DEFAULT_SYNC_INTERVAL_SECS),
+            max_skew_ms: var("MAX_CLOCK_SKEW_MS").map(|v: u64| v as i64).unwrap_or(DEFAULT_MAX_SKEW_MS),
+        }
+    }
+
+    /// Whether a measured offset is beyond the threshold, allowing for the
+    /// venue's timestamp resolution
+    pub fn is_skewed(&self, venue: Venue, offset_ms: i64) -> bool {
+        offset_ms.abs() > self.max_skew_ms + venue.resolution_ms() / 2
+    }
+}
+
+/// Venue time (Unix ms) from a time endpoint response
+fn parse_server_time(venue: Venue, body: &serde_json::Value) -> Option<i64> {
+    match venue {
+        // {"serverTime": 1768478850123}
+        Venue::Binance => body.get("serverTime")?.as_i64(),
+        // Whole seconds; take the middle of the second
+        Venue::Polymarket => Some(body.as_i64()? * 1000 + 500),
+    }
+}
+
+fn time_url(venue: Venue) -> String {
+    match venue {
+        Venue::Binance => format!("{}/api/v3/time", crate::endpoints::binance_rest_url()),
+        Venue::Polymarket => format!("{}/time", crate::endpoints::clob_url()),
+    }
+}
+
+/// Take one sample of the venue's clock
+pub async fn sample(http: &reqwest::Client, venue: Venue) -> ExchangeResult<ClockSample> {
+    let sent_ms = Utc::now().timestamp_millis();
+    let body: serde_json::Value = http.get(time_url(venue)).send().await?.error_for_status()?.json().await?;
+    let received_ms = Utc::now().timestamp_millis();
+
+    let server_ms = parse_server_time(venue, &body).ok_or_else(|| format!("unexpected time response: {}", body))?;
+    Ok(ClockSample::new(sent_ms, server_ms, received_ms))
+}
+
+/// Periodically re-measure both venues' clocks and alert on excessive skew
+pub async fn run_time_sync(clock: SharedClock, db_logger: Option<Arc<DbLogger>>, policy: SyncPolicy) {
+    println!(
+        "⏱️ Time sync running every {}s (alert above {}ms skew)",
+        policy.interval_secs, policy.max_skew_ms
+    );
+
+    let http = reqwest::Client::new();
+    let mut alerted = [false; 2];
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(policy.interval_secs));
+    loop {
+        interval.tick().await;
+
+        for venue in [Venue::Binance, Venue::Polymarket] {
+            let mut samples = Vec::with_capacity(SAMPLES_PER_ROUND);
+            for _ in 0..SAMPLES_PER_ROUND {
+                match sample(&http, venue).await {
+                    Ok(s) => samples.push(s),
+                    Err(e) => eprintln!("⚠️ Time sync: {} sample failed: {}", venue.name(), e),
+                }
+            }
+            let Some(best) = best_sample(&samples) else { continue };
+            let first_sync = !clock.is_synced(venue);
+            clock.update(venue, best);
+            if first_sync {
+                println!("⏱️ {} clock offset {:+}ms (rtt {}ms)", venue.name(), clock.offset_ms(venue), clock.rtt_ms(venue));
+            }
+
+            // Alert once when skew crosses the threshold, and again when it recovers
+            let skewed = policy.is_skewed(venue, best.offset_ms);
+            if skewed == alerted[venue.index()] {
+                continue;
+            }
+            alerted[venue.index()] = skewed;
+            if skewed {
+                eprintln!(
+                    "⚠️ Clock skew vs {}: {:+}ms (rtt {}ms, threshold {}ms)",
+                    venue.name(), best.offset_ms, best.rtt_ms, policy.max_skew_ms
+                );
+            } else {
+                println!("✅ Clock skew vs {} back within threshold: {:+}ms", venue.name(), best.offset_ms);
+            }
+            if let Some(ref logger) = db_logger {
+                logger.log_activity(
+                    if skewed { "warning" } else { "info" },
+                    "system",
+                    &if skewed {
+                        format!("Clock skew vs {}: {:+}ms", venue.name(), best.offset_ms)
+                    } else {
+                        format!("Clock skew vs {} recovered: {:+}ms", venue.name(), best.offset_ms)
+                    },
+                    Some(format!(
+                        r#"{{"venue": "{}", "offset_ms": {}, "rtt_ms": {}, "threshold_ms": {}}}"#,
+                        venue.name(), best.offset_ms, best.rtt_ms, policy.max_skew_ms
+                    )),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_uses_request_midpoint() {
+        // Sent at 1000, received at 1100 -> midpoint 1050; venue said 1250
+        let sample = ClockSample::new(1_000, 1_250, 1_100);
+        assert_eq!(sample.offset_ms, 200);
+        assert_eq!(sample.rtt_ms, 100);
+
+        // Lowest round trip wins
+        let samples = [sample, ClockSample::new(0, 180, 20), ClockSample::new(0, 900, 400)];
+        assert_eq!(best_sample(&samples), Some(ClockSample { offset_ms: 170, rtt_ms: 20 }));
+        assert_eq!(best_sample(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_server_time() {
+        let binance = serde_json::json!({"serverTime": 1768478850123i64});
+        assert_eq!(parse_server_time(Venue::Binance, &binance), Some(1768478850123));
+        assert_eq!(parse_server_time(Venue::Polymarket, &serde_json::json!(1768478850)), Some(1768478850500));
+        assert_eq!(parse_server_time(Venue::Polymarket, &binance), None);
+    }
+
+    #[test]
+    fn test_corrected_time_and_skew_threshold() {
+        let clock = TimeSync::new();
+        assert!(!clock.is_synced(Venue::Binance));
+        clock.update(Venue::Binance, ClockSample { offset_ms: 2_000, rtt_ms: 30 });
+
+        let drift = clock.now(Venue::Binance) - Utc::now();
+        assert!((drift.num_milliseconds() - 2_000).abs() < 100);
+        assert_eq!(clock.offset_ms(Venue::Polymarket), 0);
+
+        let policy = SyncPolicy { interval_secs: 30, max_skew_ms: 500 };
+        assert!(policy.is_skewed(Venue::Binance, -600));
+        assert!(!policy.is_skewed(Venue::Binance, 400));
+        // CLOB seconds resolution gets the extra half second
+        assert!(!policy.is_skewed(Venue::Polymarket, 900));
+        assert!(policy.is_skewed(Venue::Polymarket, 1_100));
+    }
+}
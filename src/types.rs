@@ -13,10 +13,50 @@ pub struct Position {
     pub market_id: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Market horizon (how long a market's window runs before it resolves)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MarketType {
     Hourly,    // 60-minute market
     Fifteen,   // 15-minute market
+    Daily,     // Daily "Up or Down" market
+}
+
+impl MarketType {
+    /// Map a strategy signal horizon in minutes (15 or 60) to a market type
+    pub fn from_minutes(minutes: u64) -> Option<Self> {
+        match minutes {
+            15 => Some(MarketType::Fifteen),
+            60 => Some(MarketType::Hourly),
+            _ => None,
+        }
+    }
+    
+    /// Strategy signal horizon in minutes (inverse of `from_minutes`)
+    pub fn minutes(&self) -> Option<u64> {
+        match self {
+            MarketType::Fifteen => Some(15),
+            MarketType::Hourly => Some(60),
+            _ => None,
+        }
+    }
+    
+    /// Label used in logs, tickers and the activity log
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarketType::Fifteen => "15-MIN",
+            MarketType::Hourly => "60-MIN",
+            MarketType::Daily => "DAILY",
+        }
+    }
+    
+    /// Nominal window length in seconds
+    pub fn window_secs(&self) -> i64 {
+        match self {
+            MarketType::Fifteen => 900,
+            MarketType::Hourly => 3_600,
+            MarketType::Daily => 86_400,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
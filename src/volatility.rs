@@ -0,0 +1,155 @@
+//! Online volatility per asset and horizon
+//!
+//! An EWMA of squared log returns, sampled at the last price of each
+//! `VOL_SAMPLE_MS` bucket and normalized by the time between samples, gives
+//! a variance rate per second. Each strategy horizon has its own half-life:
+//! short windows react quickly to a regime change, hourly windows average
+//! over longer. The strategy thread updates it on every Binance trade.
+//!
+//! Signals are expressed as z-scores: a price move divided by the move
+//! expected over the time left, `σ √τ`, so one threshold means the same in
+//! calm and volatile regimes.
+
+use crate::pricing::VOL_SAMPLE_MS;
+use crate::types::MarketType;
+use std::collections::HashMap;
+
+/// EWMA half-life per horizon (seconds)
+const HORIZONS: [(MarketType, f64); 2] = [(MarketType::Fifteen, 300.0), (MarketType::Hourly, 1_200.0)];
+/// Returns needed before an estimate is trusted
+const MIN_SAMPLES: u32 = 30;
+
+/// Move over `secs` remaining in units of the expected move `σ √secs`
+/// (at least one second, so the last instant of a window stays finite)
+pub fn z_score(move_frac: f64, sigma_per_sec: f64, secs: f64) -> f64 {
+    if sigma_per_sec <= 0.0 {
+        return 0.0;
+    }
+    move_frac / (sigma_per_sec * secs.max(1.0).sqrt())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EwmaVol {
+    half_life_secs: f64,
+    variance: f64, // Per second
+    samples: u32,
+    open: Option<(u64, f64)>,  // (bucket, latest price) still being sampled
+    close: Option<(u64, f64)>, // Last completed sample
+}
+
+impl EwmaVol {
+    fn new(half_life_secs: f64) -> Self {
+        Self { half_life_secs, variance: 0.0, samples: 0, open: None, close: None }
+    }
+
+    fn update(&mut self, price: f64, ts: u64) {
+        let bucket = ts / VOL_SAMPLE_MS;
+        match self.open {
+            // Same bucket (or out of order): keep the latest price
+            Some((open_bucket, _)) if bucket <= open_bucket => {
+                self.open = Some((open_bucket, price));
+                return;
+            }
+            Some(completed) => {
+                if let Some((prev_bucket, prev_price)) = self.close {
+                    let dt = (completed.0 - prev_bucket) as f64 * VOL_SAMPLE_MS as f64 / 1000.0;
+                    self.add_return(prev_price, completed.1, dt);
+                }
+                self.close = Some(completed);
+            }
+            None => {}
+        }
+        self.open = Some((bucket, price));
+    }
+
+    fn add_return(&mut self, from: f64, to: f64, dt_secs: f64) {
+        if from <= 0.0 || to <= 0.0 || dt_secs <= 0.0 {
+            return;
+        }
+        let r = (to / from).ln();
+        let rate = r * r / dt_secs;
+        // Decay by elapsed time, so gaps between trades weigh correctly
+        let alpha = 1.0 - (-dt_secs * std::f64::consts::LN_2 / self.half_life_secs).exp();
+        self.variance = if self.samples == 0 { rate } else { (1.0 - alpha) * self.variance + alpha * rate };
+        self.samples += 1;
+    }
+
+    fn sigma(&self) -> Option<f64> {
+        (self.samples >= MIN_SAMPLES).then(|| self.variance.sqrt())
+    }
+}
+
+/// EWMA volatility per (asset, horizon)
+#[derive(Debug, Default)]
+pub struct VolEstimator {
+    estimators: HashMap<(u64, MarketType), EwmaVol>,
+}
+
+impl VolEstimator {
+    /// Feed a trade (price in cents, ts in ms)
+    pub fn update(&mut self, symbol: u64, price_cents: u64, ts: u64) {
+        for (horizon, half_life) in HORIZONS {
+            self.estimators
+                .entry((symbol, horizon))
+                .or_insert_with(|| EwmaVol::new(half_life))
+                .update(price_cents as f64, ts);
+        }
+    }
+
+    /// Volatility per second for a horizon (`Fifteen` or `Hourly`); `None`
+    /// until warmed up
+    pub fn sigma(&self, symbol: u64, horizon: MarketType) -> Option<f64> {
+        self.estimators.get(&(symbol, horizon))?.sigma()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ewma_converges_to_realized_vol() {
+        // Alternating ±0.1% every second, with intra-second noise ignored
+        let mut vol = VolEstimator::default();
+        for (i, price) in [10_000_000, 10_010_000].into_iter().cycle().take(120).enumerate() {
+            let i = i as u64;
+            vol.update(1, price + 5_000, 1_000_000 + i * 1_000);
+            vol.update(1, price, 1_000_000 + i * 1_000 + 500);
+            if i == 10 {
+                assert_eq!(vol.sigma(1, MarketType::Fifteen), None); // Warming up
+            }
+        }
+        for horizon in [MarketType::Fifteen, MarketType::Hourly] {
+            let sigma = vol.sigma(1, horizon).unwrap();
+            assert!((sigma - 0.001).abs() < 1e-5, "{:?} sigma = {}", horizon, sigma);
+        }
+        assert_eq!(vol.sigma(2, MarketType::Fifteen), None);
+        assert_eq!(vol.sigma(1, MarketType::Daily), None);
+    }
+
+    #[test]
+    fn test_short_horizon_reacts_faster() {
+        let mut vol = VolEstimator::default();
+        let mut ts = 0;
+        // Calm: ±0.01% per second, then a volatile minute at ±0.2%
+        for (steps, size) in [(600, 0.0001), (60, 0.002)] {
+            for price in [10_000_000.0, 10_000_000.0 * (1.0 + size)].into_iter().cycle().take(steps) {
+                vol.update(1, price as u64, ts);
+                ts += 1_000;
+            }
+        }
+        let fast = vol.sigma(1, MarketType::Fifteen).unwrap();
+        let slow = vol.sigma(1, MarketType::Hourly).unwrap();
+        assert!(fast > slow && slow > 0.0001, "fast {} slow {}", fast, slow);
+    }
+
+    #[test]
+    fn test_z_score() {
+        // 0.3% move with 0.0001/√s vol and 900s left: expected move 0.3%
+        assert!((z_score(0.003, 0.0001, 900.0) - 1.0).abs() < 1e-9);
+        // The same move means more with less time left
+        assert!(z_score(0.003, 0.0001, 100.0) > 2.9);
+        assert!((z_score(-0.001, 0.0001, 0.0) + 10.0).abs() < 1e-9);
+        assert_eq!(z_score(0.001, 0.0, 60.0), 0.0);
+    }
+}
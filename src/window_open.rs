@@ -0,0 +1,165 @@
+//! Window open (strike) capture
+//!
+//! Up/Down markets resolve against the asset's price at window start: Up
+//! wins if the close is at or above the open. For every cached Up/Down
+//! window that has started, we take the open of the Binance 1-minute kline
+//! beginning at the window start, record it in the market cache (so every
+//! snapshot carries `open_price`) and persist it to `window_opens`, so the
+//! strike survives restarts. The latency and momentum strategies price
+//! windows off `CachedMarket::open_price`, falling back to their own price
+//! history until it is captured.
+
+use crate::database::DbLogger;
+use crate::exchange::ExchangeResult;
+use crate::polymarket::{MarketCache, MarketSnapshot};
+use crate::timesync::{SharedClock, Venue};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// How often started windows are checked for a missing open
+const CAPTURE_INTERVAL_MS: u64 = 1_000;
+/// Opens reloaded from the database at startup (covers daily windows)
+const RELOAD_HOURS: i64 = 48;
+
+/// Binance spot symbol for an asset ("BTC" -> "BTCUSDT")
+fn binance_symbol(asset: &str) -> String {
+    format!("{}USDT", asset)
+}
+
+/// Up/Down windows that have started but have no open yet: (asset, start)
+fn pending_windows(snapshot: &MarketSnapshot, now: DateTime<Utc>) -> Vec<(String, DateTime<Utc>)> {
+    let mut seen = HashSet::new();
+    snapshot
+        .markets
+        .values()
+        .flatten()
+        .filter(|m| m.open_price.is_none() && m.is_live_at(now))
+        .filter(|m| seen.insert((m.asset.clone(), m.start_date)))
+        .map(|m| (m.asset.clone(), m.start_date))
+        .collect()
+}
+
+/// Open of the kline starting exactly at `start_ms`, from a `/api/v3/klines`
+/// response (`[[openTime, "open", "high", "low", "close", ...], ...]`)
+fn parse_kline_open(body: &serde_json::Value, start_ms: i64) -> Option<f64> {
+    let kline = body.as_array()?.first()?.as_array()?;
+    if kline.first()?.as_i64()? != start_ms {
+        return None; // Minute not started on Binance yet
+    }
+    kline.get(1)?.as_str()?.parse().ok()
+}
+
+/// Binance 1-minute kline open at `start`; `None` until the kline exists
+pub async fn fetch_kline_open(http: &reqwest::Client, asset: &str, start: DateTime<Utc>) -> ExchangeResult<Option<f64>> {
+    let start_ms = start.timestamp_millis();
+    let url = format!(
+        "{}/api/v3/klines?symbol={}&interval=1m&startTime={}&limit=1",
+        crate::endpoints::binance_rest_url(),
+        binance_symbol(asset),
+        start_ms
+    );
+    let body: serde_json::Value = http.get(url).send().await?.error_for_status()?.json().await?;
+    Ok(parse_kline_open(&body, start_ms))
+}
+
+/// Capture the open of every started Up/Down window. Without a database the
+/// opens are kept in the market cache only.
+pub async fn run_window_open_capture(
+    cache: MarketCache,
+    clock: SharedClock,
+    db_pool: Option<PgPool>,
+    db_logger: Option<Arc<DbLogger>>,
+) {
+    // Windows opened before a restart keep the strike captured at the time
+    if let Some(ref pool) = db_pool {
+        let since = Utc::now() - chrono::Duration::hours(RELOAD_HOURS);
+        match crate::database::fetch_window_opens_since(pool, since).await {
+            Ok(rows) => {
+                for (asset, start, price) in &rows {
+                    cache.record_open(asset, *start, *price);
+                }
+                println!("📌 Reloaded {} window opens", rows.len());
+            }
+            Err(e) => eprintln!("⚠️ Could not reload window opens: {:?}", e),
+        }
+    }
+
+    let http = reqwest::Client::new();
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(CAPTURE_INTERVAL_MS));
+    loop {
+        interval.tick().await;
+
+        // Window starts are Binance kline boundaries, so judge them on its clock
+        let pending = pending_windows(&cache.load(), clock.now(Venue::Binance));
+        for (asset, start) in pending {
+            let open = match fetch_kline_open(&http, &asset, start).await {
+                Ok(Some(open)) => open,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("⚠️ Window open for {} {}: {}", asset, start.format("%H:%M"), e);
+                    continue;
+                }
+            };
+            if !cache.record_open(&asset, start, open) {
+                continue;
+            }
+            println!("📌 {} window {} opened at ${:.2}", asset, start.format("%H:%M"), open);
+
+            if let Some(ref pool) = db_pool {
+                if let Err(e) = crate::database::insert_window_open(pool, &asset, start, open, "binance_kline_1m").await {
+                    eprintln!("[WINDOW OPEN] DB error: {:?}", e);
+                }
+            }
+            if let Some(ref logger) = db_logger {
+                logger.log_activity(
+                    "info",
+                    "strategy",
+                    &format!("{} window {} open: ${:.2}", asset, start.format("%H:%M"), open),
+                    Some(format!(
+                        r#"{{"asset": "{}", "window_start": "{}", "open_price": {:.2}, "source": "binance_kline_1m"}}"#,
+                        asset, start.to_rfc3339(), open
+                    )),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::MarketType;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_kline_open() {
+        let body = serde_json::json!([[1768478400000i64, "97000.50", "97100.00", "96950.00", "97050.00", "12.5"]]);
+        assert_eq!(parse_kline_open(&body, 1768478400000), Some(97_000.5));
+        // Binance returns the next kline when the requested one doesn't exist yet
+        assert_eq!(parse_kline_open(&body, 1768478340000), None);
+        assert_eq!(parse_kline_open(&serde_json::json!([]), 1768478400000), None);
+    }
+
+    #[test]
+    fn test_pending_windows() {
+        let end = chrono::DateTime::parse_from_rfc3339("2026-01-16T12:15:00Z").unwrap().with_timezone(&Utc);
+        let btc = crate::polymarket::test_market("BTC", end);
+        let mut hourly = btc.clone();
+        hourly.market_type = MarketType::Hourly;
+        hourly.end_date = end + chrono::Duration::minutes(45); // Same start as the 15-min window
+        let mut eth = crate::polymarket::test_market("ETH", end);
+        eth.open_price = Some(3_400.0);
+
+        let mut markets = HashMap::new();
+        markets.insert("BTC".to_string(), vec![btc.clone(), hourly]);
+        markets.insert("ETH".to_string(), vec![eth]);
+        let snapshot = MarketSnapshot { version: 1, markets };
+
+        // One fetch per (asset, start); captured and not-yet-started windows are skipped
+        let now = btc.start_date + chrono::Duration::seconds(5);
+        assert_eq!(pending_windows(&snapshot, now), vec![("BTC".to_string(), btc.start_date)]);
+        assert!(pending_windows(&snapshot, btc.start_date - chrono::Duration::seconds(1)).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+//! End-to-end run of `PolymarketClient` against the mock venue binary:
+//! credentials derived via L1 auth, markets discovered from the Gamma
+//! fixtures and one order placed on the CLOB
+
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use trading_bot::exchange::ExchangeClient;
+use trading_bot::polymarket::{OrderSide, PolymarketClient};
+use trading_bot::types::MarketType;
+
+/// Well-known Anvil test key; never funded on a real chain
+const TEST_PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// The mock venue process, killed when dropped
+struct MockVenue(Child);
+
+impl Drop for MockVenue {
+    fn drop(&mut self) {
+        self.0.kill().ok();
+        self.0.wait().ok();
+    }
+}
+
+/// A local address nothing is listening on
+fn free_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+}
+
+/// Start the mock venue on free ports and wait until it accepts connections
+fn start_mock_venue() -> (MockVenue, SocketAddr) {
+    let (http, ws) = (free_addr(), free_addr());
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join("mock_venue");
+    let child = Command::new(env!("CARGO_BIN_EXE_mock_venue"))
+        .arg(fixtures)
+        .env("MOCK_HTTP_ADDR", http.to_string())
+        .env("MOCK_WS_ADDR", ws.to_string())
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("failed to start mock_venue");
+    let venue = MockVenue(child);
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while TcpStream::connect(http).is_err() {
+        assert!(Instant::now() < deadline, "mock_venue did not start listening on {}", http);
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    (venue, http)
+}
+
+#[tokio::test]
+async fn test_client_places_an_order_on_the_mock_venue() {
+    let (_venue, http) = start_mock_venue();
+    let creds_file = std::env::temp_dir().join(format!("mock_venue_creds_{}.json", std::process::id()));
+    std::fs::remove_file(&creds_file).ok();
+
+    // Set before the first endpoint lookup: the URLs are read once
+    let base = format!("http://{}", http);
+    for var in ["POLYMARKET_CLOB_URL", "POLYMARKET_GAMMA_URL", "POLYMARKET_DATA_API_URL"] {
+        std::env::set_var(var, &base);
+    }
+    for var in ["POLYMARKET_API_KEY", "POLYMARKET_API_SECRET", "POLYMARKET_PASSPHRASE", "POLYMARKET_FUNDER_ADDRESS"] {
+        std::env::remove_var(var);
+    }
+    std::env::set_var("POLYMARKET_PRIVATE_KEY", TEST_PRIVATE_KEY);
+    std::env::set_var("POLYMARKET_DERIVE_API_KEY", "true");
+    std::env::set_var("POLYMARKET_CREDS_FILE", &creds_file);
+
+    // No credentials in the environment: derived from the key and cached
+    let client = PolymarketClient::from_env().await.expect("client from the mock venue's credentials");
+    let cached = std::fs::read_to_string(&creds_file).expect("derived credentials are cached");
+    assert!(cached.contains("00000000-0000-4000-8000-000000000001"));
+
+    let markets = client.discover_markets().await.expect("discovery against the Gamma fixtures");
+    let market = markets
+        .get("BTC")
+        .and_then(|m| m.iter().find(|m| m.market_type == MarketType::Fifteen))
+        .expect("BTC 15-minute market from markets.json");
+    assert!(market.is_live_at(chrono::Utc::now()));
+
+    let order_id = client
+        .place_order(market, &market.token_ids[0], OrderSide::BUY, 5.0, 0.50)
+        .await
+        .expect("order accepted by the mock CLOB");
+    assert_eq!(order_id, "mock-1");
+
+    std::fs::remove_file(&creds_file).ok();
+}